@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pace26checker::checks::checker::check_instance_and_solution_from;
+
+fuzz_target!(|data: &[u8]| {
+    // instance and solution are separated by the first NUL byte
+    let Some(split) = data.iter().position(|&b| b == 0) else {
+        return;
+    };
+    let (instance, solution) = (&data[..split], &data[split + 1..]);
+
+    let _ = check_instance_and_solution_from(instance, solution, false, false);
+});
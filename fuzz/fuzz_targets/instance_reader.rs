@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pace26checker::io::instance_reader::Instance;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Instance::read_from(data, false);
+    let _ = Instance::read_from(data, true);
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pace26checker::io::solution_reader::Solution;
+
+fuzz_target!(|data: &[u8]| {
+    // the first byte selects the expected number of leaves
+    let Some((&num_leaves, data)) = data.split_first() else {
+        return;
+    };
+
+    let _ = Solution::read_from(data, num_leaves as u32, false);
+    let _ = Solution::read_last_complete_from(data, num_leaves as u32, false, Some("---"));
+});
@@ -1,5 +1,6 @@
 use super::bin_tree_with_parent::*;
-use pace26io::binary_tree::*;
+use pace26io::{binary_tree::*, newick::NewickWriter};
+use std::fmt::Display;
 use thiserror::Error;
 
 pub struct BinForest {
@@ -7,6 +8,15 @@ pub struct BinForest {
     leaves: Vec<WeakNodeCursor>,
 }
 
+/// Changes of the forest due to a single call of [`BinForest::isolate_tree_traced`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsolationStep {
+    /// Root of the isolated subtree
+    pub root: NodeIdx,
+    /// Roots of the siblings cut off while contracting paths, in the order of removal
+    pub cut: Vec<NodeIdx>,
+}
+
 #[derive(Error, Debug)]
 pub enum TreeInsertionError {
     #[error("Root is already present in the forest")]
@@ -75,11 +85,31 @@ impl BinForest {
     /// If an error occurs, the forest is left in an undefined state and should
     /// not be used further. Hence, we take ownership of self and only return it
     /// on success.
-    pub fn isolate_tree(mut self, other: &NodeCursor) -> Option<Self> {
-        if let Some(root) = self.isolate_tree_match(other) {
+    pub fn isolate_tree(self, other: &NodeCursor) -> Option<Self> {
+        self.isolate_tree_observed(other, |_| {})
+            .map(|(forest, _)| forest)
+    }
+
+    /// Same as [`BinForest::isolate_tree`], but additionally reports the root of the
+    /// isolated subtree and the siblings cut off while contracting paths towards it.
+    pub fn isolate_tree_traced(self, other: &NodeCursor) -> Option<(Self, IsolationStep)> {
+        let mut cut = Vec::new();
+        let (forest, root) = self.isolate_tree_observed(other, |sibling| {
+            cut.push(sibling.node_idx());
+        })?;
+        Some((forest, IsolationStep { root, cut }))
+    }
+
+    fn isolate_tree_observed(
+        mut self,
+        other: &NodeCursor,
+        mut on_cut: impl FnMut(&NodeCursor),
+    ) -> Option<(Self, NodeIdx)> {
+        if let Some(root) = self.isolate_tree_match(other, &mut on_cut) {
             root.update_topology_subtree();
+            let root_idx = root.node_idx();
             self.add_root(root);
-            Some(self)
+            Some((self, root_idx))
         } else {
             None
         }
@@ -94,33 +124,60 @@ impl BinForest {
         self.roots.push(root);
     }
 
-    fn isolate_tree_match(&mut self, other: &NodeCursor) -> Option<NodeCursor> {
-        if let Some((left, right)) = other.children() {
-            let match_left = self.isolate_tree_match(&left)?;
-            let match_right = self.isolate_tree_match(&right)?;
+    fn isolate_tree_match(
+        &mut self,
+        other: &NodeCursor,
+        on_cut: &mut impl FnMut(&NodeCursor),
+    ) -> Option<NodeCursor> {
+        // post-order traversal of `other` with an explicit stack; `matches` holds the
+        // matches of the finished subtrees
+        let mut stack = vec![(other.clone(), false)];
+        let mut matches: Vec<NodeCursor> = Vec::new();
+
+        while let Some((node, children_done)) = stack.pop() {
+            let Some((left, right)) = node.children() else {
+                let Label(l) = node.leaf_label()?;
+                matches.push(self.leaves.get(l as usize)?.upgrade()?);
+                continue;
+            };
+
+            if !children_done {
+                stack.push((node, true));
+                stack.push((right, false));
+                stack.push((left, false));
+                continue;
+            }
+
+            let match_right = matches.pop()?;
+            let match_left = matches.pop()?;
             let lca = NodeCursor::lowest_common_ancestor(match_left.clone(), match_right.clone())?;
 
-            if lca.depth() < other.depth() {
+            if lca.depth() < node.depth() {
                 return None;
             }
 
-            self.contract_path(&match_left, &lca);
-            self.contract_path(&match_right, &lca);
+            self.contract_path(&match_left, &lca, on_cut)?;
+            self.contract_path(&match_right, &lca, on_cut)?;
 
-            Some(lca)
-        } else if let Some(Label(l)) = other.leaf_label() {
-            self.leaves[l as usize].upgrade()
-        } else {
-            unreachable!()
+            matches.push(lca);
         }
+
+        matches.pop()
     }
 
-    fn contract_path(&mut self, lower: &NodeCursor, upper: &NodeCursor) {
+    fn contract_path(
+        &mut self,
+        lower: &NodeCursor,
+        upper: &NodeCursor,
+        on_cut: &mut impl FnMut(&NodeCursor),
+    ) -> Option<()> {
         debug_assert!(lower.depth() > upper.depth());
         for _ in (upper.depth() + 1)..lower.depth() {
-            let sibling = lower.remove_sibling().unwrap();
+            let sibling = lower.remove_sibling()?;
+            on_cut(&sibling);
             self.add_root(sibling);
         }
+        Some(())
     }
 
     /// Slice of all roots in the forest
@@ -133,6 +190,25 @@ impl BinForest {
     }
 }
 
+/// Compact single-line representation listing each root as `#<node id> <newick>`, e.g.
+/// `#9 ((1,2),3); #4 4;`. For a drawing, see [`forest_text_writer`](crate::io::forest_text_writer).
+impl Display for BinForest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, root) in self.roots.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(
+                f,
+                "#{} {}",
+                root.node_idx().0,
+                root.top_down().to_newick_string()
+            )?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,6 +270,60 @@ mod tests {
         assert_eq!(forest.roots[2].top_down().to_newick_string(), "(6,7);");
     }
 
+    #[test]
+    fn display() {
+        let host = BinTreeWithParentBuilder::default()
+            .parse_newick_from_str("((1,2),(3,4));", NodeIdx(5))
+            .unwrap();
+        let pattern = BinTreeWithParentBuilder::default()
+            .parse_newick_from_str("((1,2),3);", Default::default())
+            .unwrap();
+
+        let mut forest = BinForest::new(4);
+        assert_eq!(forest.to_string(), "");
+
+        forest = forest.add_tree(host).unwrap();
+        let root = forest.roots[0].node_idx().0;
+        assert_eq!(forest.to_string(), format!("#{root} ((1,2),(3,4));"));
+
+        forest = forest.isolate_tree(&pattern).unwrap();
+        assert_eq!(forest.to_string(), format!("#{root} ((1,2),3); #4 4;"));
+    }
+
+    #[test]
+    fn isolate_tree_traced() {
+        let host = BinTreeWithParentBuilder::default()
+            .parse_newick_from_str("(((1,2),(3,4)),(5,(6,7)));", NodeIdx(8))
+            .unwrap();
+        let pattern = BinTreeWithParentBuilder::default()
+            .parse_newick_from_str("((1,3),5);", Default::default())
+            .unwrap();
+
+        let forest = BinForest::new(7).add_tree(host.clone()).unwrap();
+        let (forest, step) = forest.isolate_tree_traced(&pattern).unwrap();
+
+        assert_eq!(step.root, host.node_idx());
+        let mut cut: Vec<_> = step
+            .cut
+            .iter()
+            .map(|&u| {
+                let root = forest.roots.iter().find(|r| r.node_idx() == u).unwrap();
+                root.top_down().to_newick_string()
+            })
+            .collect();
+        cut.sort();
+        assert_eq!(cut, ["(6,7);", "2;", "4;"]);
+
+        // isolating a leaf that is already a root cuts nothing
+        let leaf = BinTreeWithParentBuilder::default()
+            .parse_newick_from_str("4;", Default::default())
+            .unwrap();
+        let (forest, step) = forest.isolate_tree_traced(&leaf).unwrap();
+        assert_eq!(step.root, NodeIdx(4));
+        assert!(step.cut.is_empty());
+        assert_eq!(forest.roots().len(), 4);
+    }
+
     #[test]
     fn isolate_tree_failed() {
         let host = BinTreeWithParentBuilder::default()
@@ -75,62 +75,106 @@ impl NodeCursor {
     /// Deep-clones the subtree rooted in this node and updates the clone's topology,
     /// i.e. fixing depth and parent-links.
     pub fn clone_and_rebuild(&self) -> NodeCursor {
-        self.clone_tree_inner(0)
-    }
-
-    fn clone_tree_inner(&self, depth: usize) -> NodeCursor {
-        let inner_node = self.0.borrow();
-        match &inner_node.children {
-            Children::Inner { left, right } => {
-                let left_clone = NodeCursor(left.clone()).clone_tree_inner(depth + 1).0;
-                let right_clone = NodeCursor(right.clone()).clone_tree_inner(depth + 1).0;
-
-                let new_self = NodeCursor(Rc::new(RefCell::new(Node {
-                    parent: WeakNodeRef::new(),
-                    depth,
-                    id: inner_node.id,
-                    children: Children::Inner {
-                        left: left_clone.clone(),
-                        right: right_clone.clone(),
-                    },
-                })));
-
-                left_clone.borrow_mut().parent = Rc::downgrade(&new_self.0);
-                right_clone.borrow_mut().parent = Rc::downgrade(&new_self.0);
-
-                new_self
-            }
+        // post-order traversal with an explicit stack; `clones` holds the finished subtrees
+        let mut stack = vec![(self.clone(), false)];
+        let mut clones: Vec<NodeRef> = Vec::new();
+
+        while let Some((node, children_done)) = stack.pop() {
+            let inner_node = node.0.borrow();
+            let children = match &inner_node.children {
+                Children::Inner { left, right } if !children_done => {
+                    stack.push((node.clone(), true));
+                    stack.push((NodeCursor(right.clone()), false));
+                    stack.push((NodeCursor(left.clone()), false));
+                    continue;
+                }
+                Children::Inner { .. } => {
+                    let right = clones.pop().unwrap();
+                    let left = clones.pop().unwrap();
+                    Children::Inner { left, right }
+                }
+                Children::Leaf { label } => Children::Leaf { label: *label },
+            };
 
-            Children::Leaf { label } => NodeCursor(Rc::new(RefCell::new(Node {
+            clones.push(Rc::new(RefCell::new(Node {
                 parent: WeakNodeRef::new(),
-                depth,
+                depth: 0,
                 id: inner_node.id,
-                children: Children::Leaf { label: *label },
-            }))),
+                children,
+            })));
         }
+
+        let root = NodeCursor(clones.pop().unwrap());
+        root.update_topology();
+        root
     }
 
     /// Sorts children such that the child with the smallest leaf in its subtree sits left
     /// and returns the smallest leaf found.
     pub fn normalize_child_order(&self) -> Label {
-        if let Some((left, right)) = self.children() {
-            let min_left = left.normalize_child_order();
-            let min_right = right.normalize_child_order();
+        // post-order traversal with an explicit stack; `minima` holds the smallest leaf
+        // of each finished subtree
+        let mut stack = vec![(self.clone(), false)];
+        let mut minima: Vec<Label> = Vec::new();
+
+        while let Some((node, children_done)) = stack.pop() {
+            let Some((left, right)) = node.children() else {
+                minima.push(node.leaf_label().unwrap());
+                continue;
+            };
+
+            if !children_done {
+                stack.push((node, true));
+                stack.push((right, false));
+                stack.push((left, false));
+                continue;
+            }
+
+            let min_right = minima.pop().unwrap();
+            let min_left = minima.pop().unwrap();
 
             if min_left <= min_right {
-                return min_left;
+                minima.push(min_left);
+            } else {
+                node.0.borrow_mut().children = Children::Inner {
+                    left: right.0,
+                    right: left.0,
+                };
+                minima.push(min_right);
             }
+        }
+
+        minima.pop().unwrap()
+    }
+}
 
-            let mut inner_mut = self.0.borrow_mut();
-            inner_mut.children = Children::Inner {
-                left: right.0,
-                right: left.0,
+/// Dropping a node recursively drops its children, which overflows the stack for deep trees.
+/// Instead, we detach the children of each node about to be freed and free them one by one.
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut orphans = Vec::new();
+        let mut children =
+            std::mem::replace(&mut self.children, Children::Leaf { label: Label(0) });
+
+        loop {
+            if let Children::Inner { left, right } = children {
+                orphans.push(left);
+                orphans.push(right);
+            }
+
+            let Some(orphan) = orphans.pop() else {
+                break;
             };
 
-            return min_right;
+            // nodes still referenced elsewhere must stay intact
+            children = match Rc::try_unwrap(orphan) {
+                Ok(node) => std::mem::replace(
+                    &mut node.into_inner().children,
+                    Children::Leaf { label: Label(0) },
+                ),
+                Err(_) => Children::Leaf { label: Label(0) },
+            };
         }
-
-        self.leaf_label().unwrap()
     }
 }
 
@@ -178,15 +222,17 @@ impl NodeCursor {
     }
 
     fn update_topology_internal(node: &NodeRef, depth: usize, parent: WeakNodeRef) {
-        node.borrow_mut().depth = depth;
-        node.borrow_mut().parent = parent;
+        let mut stack = vec![(node.clone(), depth, parent)];
 
-        match &node.borrow().children {
-            Children::Inner { left, right } => {
-                Self::update_topology_internal(left, depth + 1, Rc::downgrade(node));
-                Self::update_topology_internal(right, depth + 1, Rc::downgrade(node));
+        while let Some((node, depth, parent)) = stack.pop() {
+            let mut inner = node.borrow_mut();
+            inner.depth = depth;
+            inner.parent = parent;
+
+            if let Children::Inner { left, right } = &inner.children {
+                stack.push((right.clone(), depth + 1, Rc::downgrade(&node)));
+                stack.push((left.clone(), depth + 1, Rc::downgrade(&node)));
             }
-            Children::Leaf { .. } => {}
         }
     }
 
@@ -4,9 +4,10 @@ use std::path::Path;
 
 use crate::checks::bin_forest::*;
 use crate::io::instance_reader::InstanceReaderError;
+use crate::io::limits::ReaderLimits;
 use crate::io::{instance_reader::Instance, solution_reader::*};
 use thiserror::Error;
-use tracing::debug;
+use tracing::{debug, warn};
 
 #[derive(Debug, Error)]
 pub enum CheckerError {
@@ -22,6 +23,10 @@ pub enum CheckerError {
         sol_lineno: usize,
     },
 
+    /// Only reported in paranoid mode; otherwise, a mismatch is merely logged as warning
+    #[error(transparent)]
+    ClaimedScoreMismatch(#[from] ClaimedScoreMismatch),
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
@@ -59,10 +64,260 @@ pub fn check_instance_and_solution_from(
     paranoid: bool,
     keep_instance_copy: bool,
 ) -> Result<(Option<Instance>, Solution, Vec<BinForest>), CheckerError> {
-    let instance = Instance::read_from(instance_reader, paranoid)?;
-    let instance_clone = keep_instance_copy.then(|| instance.clone());
+    check_instance_and_solution_observed_from(
+        instance_reader,
+        solution_reader,
+        paranoid,
+        keep_instance_copy,
+        ReaderLimits::default(),
+        &mut (),
+    )
+}
+
+/// Same as [`check_instance_and_solution_from`], but both inputs are read subject to `limits`
+/// rather than the default [`ReaderLimits`].
+pub fn check_instance_and_solution_with_limits_from(
+    instance_reader: impl BufRead,
+    solution_reader: impl BufRead,
+    paranoid: bool,
+    keep_instance_copy: bool,
+    limits: ReaderLimits,
+) -> Result<(Option<Instance>, Solution, Vec<BinForest>), CheckerError> {
+    check_instance_and_solution_observed_from(
+        instance_reader,
+        solution_reader,
+        paranoid,
+        keep_instance_copy,
+        limits,
+        &mut (),
+    )
+}
+
+/// Same as [`check_instance_and_solution_with_limits_from`], but `observer` is notified about
+/// the progress of the check; e.g. to visualize the algorithm using
+/// [`IsolationFrameWriter`](crate::io::isolation_frame_writer::IsolationFrameWriter).
+/// Errors of the observer abort the check and are returned as [`CheckerError::Io`], unless
+/// the check fails anyway.
+pub fn check_instance_and_solution_observed_from<O: IsolationObserver>(
+    instance_reader: impl BufRead,
+    solution_reader: impl BufRead,
+    paranoid: bool,
+    keep_instance_copy: bool,
+    limits: ReaderLimits,
+    observer: &mut O,
+) -> Result<(Option<Instance>, Solution, Vec<BinForest>), CheckerError> {
+    let outcome = check_inputs(
+        || {
+            Ok(Instance::read_from_with_limits(
+                instance_reader,
+                paranoid,
+                limits,
+            )?)
+        },
+        |num_leaves| {
+            let solution =
+                Solution::read_from_with_limits(solution_reader, num_leaves, paranoid, limits)?;
+            Ok((solution, ()))
+        },
+        paranoid,
+        keep_instance_copy,
+        observer,
+    );
+
+    let forests = outcome.forests?;
+    let (solution, ()) = outcome.solution.unwrap();
+    Ok((outcome.instance, solution, forests))
+}
+
+/// Same as [`check_instance_and_solution_observed_from`], but the solution reader may contain
+/// several consecutive solutions (e.g., the output of an anytime solver). Only the last
+/// complete solution is checked; see [`Solution::read_last_complete_from`] for details.
+pub fn check_instance_and_anytime_solution_from<O: IsolationObserver>(
+    instance_reader: impl BufRead,
+    solution_reader: impl BufRead,
+    separator: Option<&str>,
+    paranoid: bool,
+    keep_instance_copy: bool,
+    limits: ReaderLimits,
+    observer: &mut O,
+) -> Result<(Option<Instance>, Solution, Vec<BinForest>, AnytimeSummary), CheckerError> {
+    let outcome = check_inputs(
+        || {
+            Ok(Instance::read_from_with_limits(
+                instance_reader,
+                paranoid,
+                limits,
+            )?)
+        },
+        |num_leaves| {
+            let (solution, summary) = Solution::read_last_complete_from_with_limits(
+                solution_reader,
+                num_leaves,
+                separator,
+                paranoid,
+                limits,
+            )?;
+            debug!("Check last of {} complete solutions", summary.num_solutions);
+            Ok((solution, summary))
+        },
+        paranoid,
+        keep_instance_copy,
+        observer,
+    );
+
+    let forests = outcome.forests?;
+    let (solution, summary) = outcome.solution.unwrap();
+    Ok((outcome.instance, solution, forests, summary))
+}
+
+/// Inputs read by [`check_inputs`] together with the outcome of the check. The inputs are
+/// available as far as they could be read, even if the check fails.
+pub(crate) struct CheckOutcome<S> {
+    /// Unmodified copy of the instance, if requested
+    pub instance: Option<Instance>,
+    /// The solution and whatever else was obtained while reading it
+    pub solution: Option<(Solution, S)>,
+    pub forests: Result<Vec<BinForest>, CheckerError>,
+}
+
+/// Shared body of the check entry points, which only differ in how the inputs are read:
+/// reads the instance and then the solution (given the number of leaves), checks the
+/// solution while notifying `observer`, and compares the claimed score.
+pub(crate) fn check_inputs<O: IsolationObserver, S>(
+    read_instance: impl FnOnce() -> Result<Instance, CheckerError>,
+    read_solution: impl FnOnce(u32) -> Result<(Solution, S), CheckerError>,
+    paranoid: bool,
+    keep_instance_copy: bool,
+    observer: &mut O,
+) -> CheckOutcome<S> {
+    let mut outcome = CheckOutcome {
+        instance: None,
+        solution: None,
+        forests: Ok(Vec::new()),
+    };
+
+    let instance = match read_instance() {
+        Ok(instance) => instance,
+        Err(e) => {
+            outcome.forests = Err(e);
+            return outcome;
+        }
+    };
+    // isolating subtrees restructures the instance trees; hence we keep a copy
+    outcome.instance = keep_instance_copy.then(|| instance.clone());
+
+    let (solution, extra) = match read_solution(instance.num_leaves()) {
+        Ok(read) => read,
+        Err(e) => {
+            outcome.forests = Err(e);
+            return outcome;
+        }
+    };
+
+    let forests = check_solution_observed(&instance, &solution, observer);
+    outcome.forests = check_claimed_score(&solution, paranoid, forests);
+    outcome.solution = Some((solution, extra));
+    outcome
+}
+
+/// Score claimed in the metadata of a solution that differs from its actual score
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Solution claims score {claimed}, but consists of {score} trees")]
+pub struct ClaimedScoreMismatch {
+    pub claimed: u32,
+    pub score: u32,
+}
+
+/// Compares the score claimed in the solution's metadata (if any) with the actual score,
+/// i.e. the number of trees; this does not require the solution to be feasible.
+pub fn claimed_score_mismatch(solution: &Solution) -> Option<ClaimedScoreMismatch> {
+    let score = solution.num_trees() as u32;
+    solution
+        .metadata()
+        .score
+        .filter(|&claimed| claimed != score)
+        .map(|claimed| ClaimedScoreMismatch { claimed, score })
+}
+
+/// Passes on the outcome `forests` of the feasibility check after comparing the claimed score
+/// (even for infeasible solutions). A mismatch is a warning, which becomes an error in
+/// paranoid mode; infeasibility takes precedence though.
+fn check_claimed_score(
+    solution: &Solution,
+    paranoid: bool,
+    forests: Result<Vec<BinForest>, CheckerError>,
+) -> Result<Vec<BinForest>, CheckerError> {
+    let Some(mismatch) = claimed_score_mismatch(solution) else {
+        return forests;
+    };
+
+    warn!(" {mismatch}");
+    match forests {
+        Ok(_) if paranoid => Err(mismatch.into()),
+        forests => forests,
+    }
+}
+
+/// Receives the intermediate states of the forests built by the checker
+pub trait IsolationObserver {
+    /// If `false`, the checker neither notifies the observer nor traces the isolation steps
+    const OBSERVES: bool = true;
+
+    /// Called before the first solution tree is isolated in the instance tree in line
+    /// `inst_lineno`; `forest` consists of this tree only
+    fn on_begin(&mut self, _inst_lineno: usize, _forest: &BinForest) -> Result<(), std::io::Error> {
+        Ok(())
+    }
 
-    let solution = Solution::read_from(solution_reader, instance.num_leaves(), paranoid)?;
+    /// Called after the solution tree in line `sol_lineno` was isolated in the forest of the
+    /// instance tree in line `inst_lineno`; `forest` is the resulting forest
+    fn on_isolate(
+        &mut self,
+        inst_lineno: usize,
+        sol_lineno: usize,
+        forest: &BinForest,
+        step: &IsolationStep,
+    ) -> Result<(), std::io::Error>;
+
+    /// Called if the solution tree in line `sol_lineno` cannot be isolated in the forest of
+    /// the instance tree in line `inst_lineno`, i.e. right before the check fails with
+    /// [`CheckerError::Mismatch`]. The forest is not passed, since the failed attempt leaves
+    /// it in an undefined state.
+    fn on_mismatch(
+        &mut self,
+        _inst_lineno: usize,
+        _sol_lineno: usize,
+    ) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+impl IsolationObserver for () {
+    const OBSERVES: bool = false;
+
+    fn on_isolate(
+        &mut self,
+        _inst_lineno: usize,
+        _sol_lineno: usize,
+        _forest: &BinForest,
+        _step: &IsolationStep,
+    ) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+pub(crate) fn check_solution(
+    instance: &Instance,
+    solution: &Solution,
+) -> Result<Vec<BinForest>, CheckerError> {
+    check_solution_observed(instance, solution, &mut ())
+}
+
+fn check_solution_observed<O: IsolationObserver>(
+    instance: &Instance,
+    solution: &Solution,
+    observer: &mut O,
+) -> Result<Vec<BinForest>, CheckerError> {
     let mut forests = Vec::with_capacity(instance.num_trees() as usize);
 
     for (lineno, instance_tree) in instance.trees() {
@@ -78,14 +333,35 @@ pub fn check_instance_and_solution_from(
             }
         };
 
+        if O::OBSERVES {
+            observer.on_begin(*lineno, &forest)?;
+        }
+
         for (solution_line, subtree) in solution.trees() {
-            if let Some(f) = forest.isolate_tree(subtree) {
-                forest = f;
+            // tracing records the cut siblings, which is wasted effort if nobody observes
+            let isolated = if O::OBSERVES {
+                forest
+                    .isolate_tree_traced(subtree)
+                    .map(|(f, step)| (f, Some(step)))
             } else {
+                forest.isolate_tree(subtree).map(|f| (f, None))
+            };
+
+            let Some((f, step)) = isolated else {
+                if O::OBSERVES
+                    && let Err(e) = observer.on_mismatch(*lineno, *solution_line)
+                {
+                    warn!(" Observer failed on mismatch: {e}");
+                }
                 return Err(CheckerError::Mismatch {
                     inst_lineno: *lineno,
                     sol_lineno: *solution_line,
                 });
+            };
+
+            forest = f;
+            if let Some(step) = step {
+                observer.on_isolate(*lineno, *solution_line, &forest, &step)?;
             }
         }
 
@@ -94,15 +370,16 @@ pub fn check_instance_and_solution_from(
 
     debug!("Feasible solution found");
 
-    Ok((instance_clone, solution, forests))
+    Ok(forests)
 }
 
 // TODO: add unit tests
 #[cfg(test)]
 mod tests {
     use crate::checks::bin_tree_with_parent::NodeCursor;
-    use crate::checks::checker::{check_instance_and_solution, check_instance_only};
-    use crate::io::tests::{test_instances, test_instances_directory};
+    use crate::checks::checker::*;
+    use crate::io::limits::LimitExceeded;
+    use crate::io::tests::{Rng, test_instances, test_instances_directory};
     use pace26io::binary_tree::{TopDownCursor, TreeWithNodeIdx};
 
     #[test]
@@ -180,6 +457,57 @@ mod tests {
         }
     }
 
+    #[test]
+    fn anytime_tiny01() {
+        let dir = test_instances_directory("tiny");
+        let instance = std::fs::read(dir.join("tiny01.in")).unwrap();
+
+        // the first solution is feasible, the second one is not
+        let first = b"(5,3);\n6;\n4;\n(1,2);\n(4,";
+        let stream = b"(5,3);\n6;\n4;\n(1,2);\n(1,2);\n(3,4);\n(5,6);\n(4,";
+        let check = |solution: &[u8]| {
+            check_instance_and_anytime_solution_from(
+                &instance[..],
+                solution,
+                None,
+                false,
+                false,
+                ReaderLimits::default(),
+                &mut (),
+            )
+        };
+        let (_, solution, _, summary) = check(&first[..]).unwrap();
+        assert_eq!(summary.num_solutions, 1);
+        assert_eq!(solution.num_trees(), 4);
+
+        assert!(matches!(
+            check(&stream[..]),
+            Err(CheckerError::Mismatch { sol_lineno: 5, .. })
+        ));
+
+        // the limits apply to the whole stream rather than to each solution
+        let limits = ReaderLimits {
+            max_total_bytes: 200,
+            ..Default::default()
+        };
+        let repeated = "(5,3);\n6;\n4;\n(1,2);\n".repeat(20);
+        assert!(check(repeated.as_bytes()).is_ok());
+        assert!(matches!(
+            check_instance_and_anytime_solution_from(
+                &instance[..],
+                repeated.as_bytes(),
+                None,
+                false,
+                false,
+                limits,
+                &mut (),
+            ),
+            Err(CheckerError::SolutionReaderError(
+                SolutionReaderError::LimitExceeded(_)
+            ))
+        ));
+    }
+
     #[test]
     fn roots_tiny01() {
         let dir = test_instances_directory("tiny");
@@ -195,10 +523,140 @@ mod tests {
         fn collect_node_ids(nodes: &[NodeCursor]) -> Vec<u32> {
             let mut ids: Vec<_> = nodes.iter().map(|c| c.node_idx().0).collect();
             ids.sort();
-            return ids;
+            ids
         }
 
         assert_eq!(collect_node_ids(forests[0].roots()), vec![4, 6, 7, 8, 11]);
         assert_eq!(collect_node_ids(forests[1].roots()), vec![4, 6, 12, 13, 15]);
     }
+
+    /// Applies a few random edits, favouring tokens that are meaningful to the parser.
+    fn mutate(rng: &mut Rng, data: &[u8]) -> Vec<u8> {
+        const TOKENS: &[&[u8]] = &[
+            b"(",
+            b")",
+            b",",
+            b";",
+            b"\n",
+            b" ",
+            b"#",
+            b"#p",
+            b"#p 0 0",
+            b"#s",
+            b"#x",
+            b"#a",
+            b"#s \xc3\xa9",
+            b"0",
+            b"4294967296",
+            b"99999999999999999999",
+            b"\xff",
+            b"(((((",
+        ];
+
+        let mut data = data.to_vec();
+        for _ in 0..1 + rng.next(4) {
+            let pos = rng.next(data.len() + 1);
+            match rng.next(4) {
+                0 => {
+                    let token = TOKENS[rng.next(TOKENS.len())];
+                    data.splice(pos..pos, token.iter().copied());
+                }
+                1 => {
+                    let end = (pos + rng.next(8)).min(data.len());
+                    data.drain(pos..end);
+                }
+                2 if pos < data.len() => data[pos] = rng.next(256) as u8,
+                _ => {
+                    let end = (pos + rng.next(32)).min(data.len());
+                    let copy = data[pos..end].to_vec();
+                    let at = rng.next(data.len() + 1);
+                    data.splice(at..at, copy);
+                }
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn no_panic_on_mutated_input() {
+        let mut rng = Rng(0x5eed_0030_dead_beef);
+        let mut seeds = Vec::new();
+        for name in ["valid", "invalid", "tiny"] {
+            for (input, output) in test_instances(name) {
+                let output = output
+                    .map(|p| std::fs::read(p).unwrap())
+                    .unwrap_or_default();
+                seeds.push((std::fs::read(input).unwrap(), output));
+            }
+        }
+
+        for _ in 0..3000 {
+            let (instance, solution) = &seeds[rng.next(seeds.len())];
+            let instance = mutate(&mut rng, instance);
+            let solution = mutate(&mut rng, solution);
+
+            let _ = Instance::read_from(&instance[..], true);
+            let _ = Solution::read_from(&solution[..], rng.next(8) as u32, rng.next(2) == 0);
+            let _ = check_instance_and_solution_from(&instance[..], &solution[..], false, true);
+        }
+    }
+
+    #[test]
+    fn claimed_score() {
+        let instance = b"#p 2 3\n((1,2),3);\n(1,(2,3));\n";
+        let check = |solution: &str, paranoid| {
+            check_instance_and_solution_from(&instance[..], solution.as_bytes(), paranoid, false)
+                .map(|(_, solution, _)| solution.metadata().score)
+        };
+
+        assert_eq!(check("#s score 2\n(2,3);\n1;\n", true).ok(), Some(Some(2)));
+        assert_eq!(check("(2,3);\n1;\n", true).ok(), Some(None));
+        assert_eq!(check("#s score 1\n(2,3);\n1;\n", false).ok(), Some(Some(1)));
+        assert!(matches!(
+            check("#s score 1\n(2,3);\n1;\n", true),
+            Err(CheckerError::ClaimedScoreMismatch(ClaimedScoreMismatch {
+                claimed: 1,
+                score: 2
+            }))
+        ));
+
+        // infeasibility takes precedence over the claimed score
+        assert!(matches!(
+            check("#s score 2\n((1,2),3);\n", true),
+            Err(CheckerError::Mismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn no_panic_on_deep_nesting() {
+        // caterpillar far deeper than the stack of a test thread permits for recursion
+        let num_leaves = 50_000;
+        let mut tree = "(".repeat(num_leaves - 1);
+        tree.push('1');
+        for i in 2..=num_leaves {
+            tree.push_str(&format!(",{i})"));
+        }
+        let instance = format!("#p 2 {num_leaves}\n{tree};\n{tree};\n");
+        let (instance_copy, solution, _) = check_instance_and_solution_from(
+            instance.as_bytes(),
+            format!("{tree};").as_bytes(),
+            false,
+            true,
+        )
+        .unwrap();
+        assert_eq!(instance_copy.unwrap().num_trees(), 2);
+        assert_eq!(solution.num_trees(), 1);
+
+        // the nesting depth is only limited on request
+        let limits = ReaderLimits {
+            max_nesting_depth: num_leaves - 2,
+            ..Default::default()
+        };
+        assert!(matches!(
+            Instance::read_from_with_limits(instance.as_bytes(), false, limits),
+            Err(InstanceReaderError::LimitExceeded(
+                LimitExceeded::NestingDepth { .. }
+            ))
+        ));
+    }
 }
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use super::{
+    bin_forest::TreeInsertionError, checker::CheckerError, flat_tree::FlatTree,
+    forest_checker::ForestChecker,
+};
+use crate::io::{instance_reader::Instance, solution_reader::Solution};
+use pace26io::binary_tree::Label;
+
+/// Feasibility test based on cluster restriction that is independent of
+/// [`BinForest::isolate_tree`](super::bin_forest::BinForest::isolate_tree).
+///
+/// A solution with components `F_1, ..., F_k` on leaf sets `X_1, ..., X_k` is an agreement
+/// forest of an instance tree `T` iff
+///  - for each component the restriction `T|X_i` equals `F_i` (up to child order), and
+///  - the minimal subtrees of `T` spanning the `X_i` are vertex-disjoint.
+///
+/// Both conditions are tested by comparing canonical forms in time linear in the size of
+/// the instance.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ClusterChecker {}
+
+/// Assigns ids to canonical forms of unordered binary trees. Leaves use their label as id,
+/// inner nodes are identified by the (sorted) pair of their children's ids.
+struct CanonicalForms {
+    num_leaves: u32,
+    pairs: HashMap<(u32, u32), u32>,
+}
+
+impl CanonicalForms {
+    fn new(num_leaves: u32) -> Self {
+        Self {
+            num_leaves,
+            pairs: HashMap::new(),
+        }
+    }
+
+    fn leaf(&self, label: Label) -> u32 {
+        label.0
+    }
+
+    fn inner(&mut self, a: u32, b: u32) -> u32 {
+        let key = (a.min(b), a.max(b));
+        let next = self.num_leaves + 1 + self.pairs.len() as u32;
+        *self.pairs.entry(key).or_insert(next)
+    }
+
+    fn tree(&mut self, tree: &FlatTree) -> u32 {
+        let mut forms = vec![0; tree.len()];
+        for (u, node) in tree.nodes().iter().enumerate().rev() {
+            forms[u] = match node.children {
+                Some((l, r)) => self.inner(forms[l], forms[r]),
+                None => node.label.map_or(0, |l| self.leaf(l)),
+            };
+        }
+        forms[tree.root()]
+    }
+}
+
+impl ForestChecker for ClusterChecker {
+    fn check(&self, instance: &Instance, solution: &Solution) -> Result<(), CheckerError> {
+        let num_leaves = instance.num_leaves();
+        let mut forms = CanonicalForms::new(num_leaves);
+
+        // component of each leaf label and the canonical form of each component
+        let mut component_of = vec![usize::MAX; num_leaves as usize + 1];
+        let mut components = Vec::with_capacity(solution.num_trees());
+        for (i, (_, tree)) in solution.trees().iter().enumerate() {
+            let flat = FlatTree::new(tree);
+            for (_, Label(l)) in flat.leaves() {
+                let Some(c) = component_of.get_mut(l as usize).filter(|_| l > 0) else {
+                    return Err(CheckerError::TreeInsertion {
+                        lineno: solution.trees()[i].0,
+                        err: TreeInsertionError::LeafOutOfRange {
+                            leaf_label: l,
+                            num_leaves,
+                        },
+                    });
+                };
+                *c = i;
+            }
+            components.push(forms.tree(&flat));
+        }
+
+        for (inst_lineno, tree) in instance.trees() {
+            let tree = FlatTree::new(tree);
+            if let Err(component) = check_tree(&tree, &component_of, &components, &mut forms) {
+                return Err(CheckerError::Mismatch {
+                    inst_lineno: *inst_lineno,
+                    sol_lineno: solution.trees().get(component).map_or(0, |(l, _)| *l),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the index of a violating component if the solution is not an agreement forest
+/// of `tree`.
+fn check_tree(
+    tree: &FlatTree,
+    component_of: &[usize],
+    components: &[u32],
+    forms: &mut CanonicalForms,
+) -> Result<(), usize> {
+    const NONE: usize = usize::MAX;
+
+    // leaves of each component in pre-order
+    let mut leaves = vec![Vec::new(); components.len()];
+    for (u, Label(l)) in tree.leaves() {
+        let c = component_of.get(l as usize).copied().unwrap_or(NONE);
+        if c == NONE {
+            // leaf not covered by the solution; cannot happen after linting
+            return Err(0);
+        }
+        leaves[c].push(u);
+    }
+
+    // Mark the spanning subtree of each component. A node claimed twice belongs to
+    // two spanning subtrees, which hence are not disjoint. Since each node is marked at
+    // most once, the total work is linear.
+    let mut owner = vec![NONE; tree.len()];
+    let mut lca = vec![NONE; components.len()];
+
+    let claim = |u: usize, c: usize, owner: &mut [usize]| -> Result<bool, usize> {
+        match owner[u] {
+            x if x == c => Ok(false),
+            NONE => {
+                owner[u] = c;
+                Ok(true)
+            }
+            _ => Err(c),
+        }
+    };
+
+    for (c, component_leaves) in leaves.iter().enumerate() {
+        let (Some(&first), Some(&last)) = (component_leaves.first(), component_leaves.last())
+        else {
+            // component without leaves in this tree; cannot happen after linting
+            return Err(c);
+        };
+
+        // the paths between the leaves with smallest and largest pre-order index meet
+        // in the lowest common ancestor of all leaves
+        let (mut a, mut b) = (first, last);
+        claim(a, c, &mut owner)?;
+        claim(b, c, &mut owner)?;
+        while a != b {
+            if tree.node(a).depth >= tree.node(b).depth {
+                a = tree.node(a).parent.ok_or(c)?;
+                claim(a, c, &mut owner)?;
+            } else {
+                b = tree.node(b).parent.ok_or(c)?;
+                claim(b, c, &mut owner)?;
+            }
+        }
+        lca[c] = a;
+
+        // all other leaves are descendants of the lca and climb until they hit the
+        // already marked part of the spanning subtree
+        for &leaf in component_leaves.iter().skip(1) {
+            let mut u = leaf;
+            while claim(u, c, &mut owner)? {
+                u = tree.node(u).parent.ok_or(c)?;
+            }
+        }
+    }
+
+    // canonical form of each restriction `T|X_c`, computed bottom-up
+    let mut restricted = vec![0u32; tree.len()];
+    for (u, node) in tree.nodes().iter().enumerate().rev() {
+        let c = owner[u];
+        if c == NONE {
+            continue;
+        }
+
+        // marked inner nodes lie on a path to a marked leaf, so at least one child is marked
+        restricted[u] = match node.children {
+            Some((l, r)) => match (owner[l] == c, owner[r] == c) {
+                (true, true) => forms.inner(restricted[l], restricted[r]),
+                (true, false) => restricted[l],
+                (false, _) => restricted[r],
+            },
+            None => node.label.map_or(0, |l| forms.leaf(l)),
+        };
+    }
+
+    for (c, &form) in components.iter().enumerate() {
+        if restricted[lca[c]] != form {
+            return Err(c);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use super::bin_tree_with_parent::NodeCursor;
+use pace26io::binary_tree::*;
+
+/// Array-based snapshot of a binary tree. Nodes are stored in pre-order, i.e. the root has
+/// index 0 and each parent precedes its children. Hence, iterating the indices in reverse
+/// order visits children before their parents.
+///
+/// In contrast to [`NodeCursor`], a `FlatTree` is immutable and traversals require neither
+/// recursion nor reference counting.
+#[derive(Debug, Clone)]
+pub struct FlatTree {
+    nodes: Vec<FlatNode>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FlatNode {
+    pub parent: Option<usize>,
+    pub children: Option<(usize, usize)>,
+    pub label: Option<Label>,
+    pub node_idx: NodeIdx,
+    pub depth: usize,
+}
+
+impl FlatTree {
+    pub fn new(root: &NodeCursor) -> Self {
+        let mut nodes: Vec<FlatNode> = Vec::new();
+        let mut stack = vec![(root.clone(), None, 0)];
+
+        while let Some((cursor, parent, depth)) = stack.pop() {
+            let index = nodes.len();
+            nodes.push(FlatNode {
+                parent,
+                children: None,
+                label: cursor.leaf_label(),
+                node_idx: cursor.node_idx(),
+                depth,
+            });
+
+            if let Some(p) = parent {
+                // children are pushed right-to-left, so the left child is assigned first
+                let p: &mut FlatNode = &mut nodes[p];
+                p.children = Some(match p.children {
+                    None => (index, usize::MAX),
+                    Some((left, _)) => (left, index),
+                });
+            }
+
+            if let Some((left, right)) = cursor.children() {
+                stack.push((right, Some(index), depth + 1));
+                stack.push((left, Some(index), depth + 1));
+            }
+        }
+
+        Self { nodes }
+    }
+
+    pub fn root(&self) -> usize {
+        0
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, u: usize) -> &FlatNode {
+        &self.nodes[u]
+    }
+
+    pub fn nodes(&self) -> &[FlatNode] {
+        &self.nodes
+    }
+
+    /// Indices of all leaves in pre-order
+    pub fn leaves(&self) -> impl Iterator<Item = (usize, Label)> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, n)| Some((i, n.label?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::bin_tree_with_parent::BinTreeWithParentBuilder;
+    use pace26io::newick::BinaryTreeParser;
+
+    #[test]
+    fn flatten() {
+        let tree = BinTreeWithParentBuilder::default()
+            .parse_newick_from_str("((1,2),(3,(4,5)));", NodeIdx(6))
+            .unwrap();
+        let flat = FlatTree::new(&tree);
+
+        assert_eq!(flat.len(), 9);
+        assert_eq!(flat.node(0).parent, None);
+        assert_eq!(flat.node(0).node_idx, NodeIdx(6));
+        assert_eq!(flat.node(0).children, Some((1, 4)));
+        assert_eq!(flat.node(1).children, Some((2, 3)));
+        assert_eq!(flat.node(6).children, Some((7, 8)));
+        assert_eq!(flat.node(8).depth, 3);
+
+        let leaves: Vec<_> = flat.leaves().map(|(_, l)| l.0).collect();
+        assert_eq!(leaves, vec![1, 2, 3, 4, 5]);
+
+        for (i, node) in flat.nodes().iter().enumerate() {
+            if let Some((l, r)) = node.children {
+                assert_eq!(flat.node(l).parent, Some(i));
+                assert_eq!(flat.node(r).parent, Some(i));
+                assert!(i < l && l < r);
+            }
+        }
+    }
+}
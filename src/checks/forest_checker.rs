@@ -0,0 +1,287 @@
+use super::{checker::CheckerError, cluster_checker::ClusterChecker};
+use crate::io::{instance_reader::Instance, solution_reader::Solution};
+
+/// Common interface of feasibility tests, i.e. tests whether a solution is an agreement
+/// forest of all trees of an instance.
+///
+/// Implementations must not modify the instance or solution.
+pub trait ForestChecker {
+    fn check(&self, instance: &Instance, solution: &Solution) -> Result<(), CheckerError>;
+}
+
+/// Feasibility test based on [`BinForest::isolate_tree`](super::bin_forest::BinForest::isolate_tree),
+/// as used by [`check_instance_and_solution`](super::checker::check_instance_and_solution).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IsolateTreeChecker {}
+
+impl ForestChecker for IsolateTreeChecker {
+    fn check(&self, instance: &Instance, solution: &Solution) -> Result<(), CheckerError> {
+        // isolating subtrees restructures the instance trees; hence we work on a copy
+        super::checker::check_solution(&instance.clone(), solution).map(|_| ())
+    }
+}
+
+/// Runs two independent checkers and panics if they disagree on the feasibility of a
+/// solution. Otherwise, the result of the first checker is returned.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use pace26checker::checks::forest_checker::*;
+/// use pace26checker::io::{instance_reader::Instance, solution_reader::Solution};
+///
+/// let instance = Instance::read(Path::new("testcases/tiny/tiny01.in"), false).unwrap();
+/// let solution = Solution::read(Path::new("testcases/tiny/tiny01.out"), 6, false).unwrap();
+///
+/// let checker: DifferentialChecker = Default::default();
+/// assert!(checker.check(&instance, &solution).is_ok());
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DifferentialChecker<A = IsolateTreeChecker, B = ClusterChecker> {
+    pub first: A,
+    pub second: B,
+}
+
+impl<A: ForestChecker, B: ForestChecker> ForestChecker for DifferentialChecker<A, B> {
+    fn check(&self, instance: &Instance, solution: &Solution) -> Result<(), CheckerError> {
+        let first = self.first.check(instance, solution);
+        let second = self.second.check(instance, solution);
+
+        if first.is_ok() != second.is_ok() {
+            panic!(
+                "Checkers disagree on feasibility: first={first:?}, second={second:?}\nInstance: {:?}\nSolution: {:?}",
+                instance.trees(),
+                solution.trees()
+            );
+        }
+
+        first
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::tests::{Rng, test_instances};
+
+    fn check_testcases(checker: &impl ForestChecker, name: &str, feasible: bool) {
+        for (input, output) in test_instances(name) {
+            let instance = Instance::read(&input, false).unwrap();
+            let Ok(solution) = Solution::read(&output.unwrap(), instance.num_leaves(), false)
+            else {
+                assert!(!feasible);
+                continue;
+            };
+
+            assert_eq!(
+                checker.check(&instance, &solution).is_ok(),
+                feasible,
+                "{input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn cluster_checker_testcases() {
+        check_testcases(&ClusterChecker::default(), "valid", true);
+        check_testcases(&ClusterChecker::default(), "tiny", true);
+        check_testcases(&ClusterChecker::default(), "invalid", false);
+    }
+
+    #[test]
+    fn differential_testcases() {
+        check_testcases(
+            &DifferentialChecker::<IsolateTreeChecker>::default(),
+            "valid",
+            true,
+        );
+        check_testcases(
+            &DifferentialChecker::<IsolateTreeChecker>::default(),
+            "invalid",
+            false,
+        );
+    }
+
+    #[test]
+    fn isolate_tree_checker_keeps_instance() {
+        let (input, output) = test_instances("valid").pop().unwrap();
+        let instance = Instance::read(&input, false).unwrap();
+        let solution = Solution::read(&output.unwrap(), instance.num_leaves(), false).unwrap();
+
+        let before = format!("{:?}", instance.trees());
+        IsolateTreeChecker::default()
+            .check(&instance, &solution)
+            .unwrap();
+        assert_eq!(before, format!("{:?}", instance.trees()));
+    }
+
+    enum Shape {
+        Leaf(usize),
+        Inner(Box<Shape>, Box<Shape>),
+    }
+
+    impl Shape {
+        fn random(rng: &mut Rng, mut subtrees: Vec<Shape>) -> Shape {
+            while subtrees.len() > 1 {
+                let a = subtrees.swap_remove(rng.next(subtrees.len()));
+                let b = subtrees.swap_remove(rng.next(subtrees.len()));
+                subtrees.push(Shape::Inner(Box::new(a), Box::new(b)));
+            }
+            subtrees.pop().unwrap()
+        }
+
+        fn newick(&self) -> String {
+            match self {
+                Shape::Leaf(l) => l.to_string(),
+                Shape::Inner(a, b) => format!("({},{})", a.newick(), b.newick()),
+            }
+        }
+    }
+
+    /// Node-level tree in which components can be grafted onto arbitrary edges
+    #[derive(Default)]
+    struct Arena {
+        children: Vec<Option<(usize, usize)>>,
+        labels: Vec<usize>,
+        root: usize,
+    }
+
+    impl Arena {
+        fn insert(&mut self, shape: &Shape) -> usize {
+            let children = match shape {
+                Shape::Leaf(_) => None,
+                Shape::Inner(a, b) => Some((self.insert(a), self.insert(b))),
+            };
+            self.children.push(children);
+            self.labels.push(match shape {
+                Shape::Leaf(l) => *l,
+                Shape::Inner(..) => 0,
+            });
+            self.children.len() - 1
+        }
+
+        /// Subdivides the edge above `target` and attaches `shape` to the new node.
+        fn graft(&mut self, target: usize, shape: &Shape) {
+            let subtree = self.insert(shape);
+            self.children.push(Some((target, subtree)));
+            self.labels.push(0);
+            let new_inner = self.children.len() - 1;
+
+            if target == self.root {
+                self.root = new_inner;
+                return;
+            }
+
+            for (l, r) in self.children.iter_mut().take(subtree).flatten() {
+                if *l == target {
+                    *l = new_inner;
+                } else if *r == target {
+                    *r = new_inner;
+                }
+            }
+        }
+
+        fn newick(&self, u: usize) -> String {
+            match self.children[u] {
+                None => self.labels[u].to_string(),
+                Some((l, r)) => format!("({},{})", self.newick(l), self.newick(r)),
+            }
+        }
+    }
+
+    /// Grafts each component onto a random edge of the tree built so far. The components
+    /// hence form an agreement forest of the result.
+    fn graft_components(rng: &mut Rng, components: &[Shape]) -> String {
+        let mut order: Vec<_> = (0..components.len()).collect();
+        rng.shuffle(&mut order);
+
+        let mut arena = Arena::default();
+        arena.root = arena.insert(&components[order[0]]);
+        for &c in &order[1..] {
+            let target = rng.next(arena.children.len());
+            arena.graft(target, &components[c]);
+        }
+
+        arena.newick(arena.root)
+    }
+
+    fn random_components(rng: &mut Rng, num_leaves: usize) -> Vec<Shape> {
+        let num_components = 1 + rng.next(num_leaves);
+        let mut labels: Vec<_> = (1..=num_leaves).collect();
+        rng.shuffle(&mut labels);
+
+        let mut parts: Vec<Vec<Shape>> = (0..num_components).map(|_| Vec::new()).collect();
+        for (i, l) in labels.into_iter().enumerate() {
+            let part = if i < num_components {
+                i
+            } else {
+                rng.next(num_components)
+            };
+            parts[part].push(Shape::Leaf(l));
+        }
+
+        parts.into_iter().map(|p| Shape::random(rng, p)).collect()
+    }
+
+    fn read_pair(
+        trees: &[String],
+        components: &[Shape],
+        num_leaves: usize,
+    ) -> (Instance, Solution) {
+        let mut instance = format!("#p {} {num_leaves}\n", trees.len());
+        for t in trees {
+            instance += &format!("{t};\n");
+        }
+        let solution: String = components
+            .iter()
+            .map(|c| format!("{};\n", c.newick()))
+            .collect();
+
+        let instance = Instance::read_from(instance.as_bytes(), true).unwrap();
+        let solution = Solution::read_from(solution.as_bytes(), num_leaves as u32, true).unwrap();
+        (instance, solution)
+    }
+
+    #[test]
+    fn differential_random_feasible() {
+        let mut rng = Rng(0x5eed_1234_abcd_0001);
+        let checker = DifferentialChecker::<IsolateTreeChecker>::default();
+
+        for _ in 0..500 {
+            let num_leaves = 2 + rng.next(14);
+            let components = random_components(&mut rng, num_leaves);
+            let trees: Vec<_> = (0..2 + rng.next(3))
+                .map(|_| graft_components(&mut rng, &components))
+                .collect();
+
+            let (instance, solution) = read_pair(&trees, &components, num_leaves);
+            assert!(checker.check(&instance, &solution).is_ok());
+        }
+    }
+
+    #[test]
+    fn differential_random() {
+        let mut rng = Rng(0x5eed_1234_abcd_0002);
+        let checker = DifferentialChecker::<IsolateTreeChecker>::default();
+        let mut num_feasible = 0;
+
+        for _ in 0..2000 {
+            let num_leaves = 2 + rng.next(10);
+
+            // the first tree always admits the solution, the others only by chance
+            let components = random_components(&mut rng, num_leaves);
+            let mut trees = vec![graft_components(&mut rng, &components)];
+            for _ in 0..1 + rng.next(2) {
+                let other = random_components(&mut rng, num_leaves);
+                trees.push(graft_components(&mut rng, &other));
+            }
+
+            let (instance, solution) = read_pair(&trees, &components, num_leaves);
+            num_feasible += checker.check(&instance, &solution).is_ok() as usize;
+        }
+
+        // make sure both outcomes are covered
+        assert!(num_feasible > 0);
+        assert!(num_feasible < 2000);
+    }
+}
@@ -131,7 +131,7 @@ mod tests {
             .iter()
             .map(|s| {
                 BinTreeWithParentBuilder::default()
-                    .parse_newick_from_str(*s, Default::default())
+                    .parse_newick_from_str(s, Default::default())
                     .expect("Failed to parse tree")
             })
             .collect();
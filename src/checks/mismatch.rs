@@ -0,0 +1,390 @@
+use super::{checker::CheckerError, flat_tree::FlatTree};
+use crate::io::{instance_reader::Instance, solution_reader::Solution};
+use pace26io::binary_tree::{Label, NodeIdx};
+
+/// Explanation of a [`CheckerError::Mismatch`], i.e. of why a solution tree (the
+/// *component*) cannot be isolated from an instance tree. Following the conditions of
+/// [`ClusterChecker`](super::cluster_checker::ClusterChecker), there are two possible
+/// reasons:
+///  - the topologies disagree: some cluster of the instance tree restricted to the leaves of
+///    the component is not a cluster of the component, or vice versa;
+///  - the topologies agree, but the minimal subtree of the instance tree spanning the leaves
+///    of the component shares nodes with the one spanning the leaves of another component.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use pace26checker::checks::{forest_checker::*, mismatch::Mismatch};
+/// use pace26checker::io::{instance_reader::Instance, solution_reader::Solution};
+///
+/// let instance = Instance::read(Path::new("testcases/invalid/score1_sa2e2l7j.in"), false).unwrap();
+/// let solution = Solution::read(Path::new("testcases/invalid/score1_sa2e2l7j.out"), 6, false).unwrap();
+///
+/// let error = IsolateTreeChecker::default().check(&instance, &solution).err().unwrap();
+/// let mismatch = Mismatch::from_error(&instance, &solution, &error).unwrap();
+/// assert!(!mismatch.tree_conflicts().is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    tree: usize,
+    component: usize,
+    leaves: Vec<Label>,
+    tree_conflicts: Vec<NodeIdx>,
+    component_conflicts: Vec<Vec<Label>>,
+    overlapping: Vec<usize>,
+}
+
+/// Number of included leaves below each node and the node index of each leaf
+pub(crate) struct Restriction {
+    pub(crate) count: Vec<usize>,
+    pub(crate) leaf_of: Vec<usize>,
+}
+
+impl Restriction {
+    pub(crate) fn new(
+        tree: &FlatTree,
+        num_leaves: usize,
+        included: impl Fn(Label) -> bool,
+    ) -> Self {
+        let mut count = vec![0; tree.len()];
+        let mut leaf_of = vec![usize::MAX; num_leaves + 1];
+        for u in (0..tree.len()).rev() {
+            let node = tree.node(u);
+            count[u] = match (node.children, node.label) {
+                (Some((l, r)), _) => count[l] + count[r],
+                (None, Some(label)) => {
+                    if let Some(x) = leaf_of.get_mut(label.0 as usize) {
+                        *x = u;
+                    }
+                    included(label) as usize
+                }
+                (None, None) => 0,
+            };
+        }
+        Self { count, leaf_of }
+    }
+}
+
+/// Sorted leaf set below each node
+pub(crate) fn clusters(tree: &FlatTree) -> Vec<Vec<Label>> {
+    let mut clusters: Vec<Vec<Label>> = vec![Vec::new(); tree.len()];
+    for u in (0..tree.len()).rev() {
+        let node = tree.node(u);
+        clusters[u] = match node.children {
+            Some((l, r)) => {
+                let mut cluster = [clusters[l].as_slice(), clusters[r].as_slice()].concat();
+                cluster.sort();
+                cluster
+            }
+            None => node.label.into_iter().collect(),
+        };
+    }
+    clusters
+}
+
+fn lca(tree: &FlatTree, mut a: usize, mut b: usize) -> usize {
+    while a != b {
+        if tree.node(a).depth >= tree.node(b).depth {
+            a = tree.node(a).parent.unwrap();
+        } else {
+            b = tree.node(b).parent.unwrap();
+        }
+    }
+    a
+}
+
+/// Minimal subtree of `tree` spanning all leaves counted in `restriction`; the result
+/// contains all nodes `u` such that the edge into `u` belongs to the subtree, and its root
+fn spanning_subtree(tree: &FlatTree, restriction: &Restriction) -> Vec<bool> {
+    let total = restriction.count[tree.root()];
+    (0..tree.len())
+        .map(|u| {
+            let count = restriction.count[u];
+            // the root of the subtree is the lowest node covering all leaves
+            let is_root = count == total
+                && tree.node(u).children.is_none_or(|(l, r)| {
+                    restriction.count[l] < total && restriction.count[r] < total
+                });
+            count > 0 && (count < total || is_root)
+        })
+        .collect()
+}
+
+impl Mismatch {
+    /// Analyzes why the solution tree in line `sol_lineno` cannot be isolated from the
+    /// instance tree in line `inst_lineno`. Returns `None` if either line does not contain a
+    /// tree.
+    pub fn analyze(
+        instance: &Instance,
+        solution: &Solution,
+        inst_lineno: usize,
+        sol_lineno: usize,
+    ) -> Option<Self> {
+        let tree_index = instance
+            .trees()
+            .iter()
+            .position(|(l, _)| *l == inst_lineno)?;
+        let component = solution
+            .trees()
+            .iter()
+            .position(|(l, _)| *l == sol_lineno)?;
+        let num_leaves = instance.num_leaves() as usize;
+
+        let tree = FlatTree::new(&instance.trees()[tree_index].1);
+        let comp = FlatTree::new(&solution.trees()[component].1);
+
+        let mut in_component = vec![false; num_leaves + 1];
+        let mut leaves: Vec<Label> = comp.leaves().map(|(_, l)| l).collect();
+        leaves.sort();
+        for l in &leaves {
+            if let Some(x) = in_component.get_mut(l.0 as usize) {
+                *x = true;
+            }
+        }
+        let included = |l: Label| in_component.get(l.0 as usize).copied().unwrap_or(false);
+
+        let in_tree = Restriction::new(&tree, num_leaves, included);
+        let in_comp = Restriction::new(&comp, num_leaves, included);
+
+        // restricted clusters of the instance tree that are no clusters of the component
+        let mut tree_conflicts = Vec::new();
+        let mut comp_lca = vec![usize::MAX; tree.len()];
+        for u in (0..tree.len()).rev() {
+            let node = tree.node(u);
+            comp_lca[u] = match (node.children, node.label) {
+                (Some((l, r)), _) => match (comp_lca[l], comp_lca[r]) {
+                    (usize::MAX, x) | (x, usize::MAX) => x,
+                    (a, b) => {
+                        let v = lca(&comp, a, b);
+                        if in_comp.count[v] != in_tree.count[u] {
+                            tree_conflicts.push(node.node_idx);
+                        }
+                        v
+                    }
+                },
+                (None, Some(label)) if included(label) => in_comp.leaf_of[label.0 as usize],
+                _ => usize::MAX,
+            };
+        }
+        tree_conflicts.reverse();
+
+        // clusters of the component that are no restricted clusters of the instance tree
+        let mut component_conflicts = Vec::new();
+        let mut tree_lca = vec![usize::MAX; comp.len()];
+        let clusters = clusters(&comp);
+        for v in (0..comp.len()).rev() {
+            let node = comp.node(v);
+            tree_lca[v] = match (node.children, node.label) {
+                (Some((l, r)), _) => {
+                    let u = match (tree_lca[l], tree_lca[r]) {
+                        (usize::MAX, _) | (_, usize::MAX) => usize::MAX,
+                        (a, b) => lca(&tree, a, b),
+                    };
+                    if u == usize::MAX || in_tree.count[u] != clusters[v].len() {
+                        component_conflicts.push(clusters[v].clone());
+                    }
+                    u
+                }
+                (None, Some(label)) => in_tree
+                    .leaf_of
+                    .get(label.0 as usize)
+                    .copied()
+                    .unwrap_or(usize::MAX),
+                (None, None) => usize::MAX,
+            };
+        }
+        component_conflicts.reverse();
+
+        // if the topologies agree, the spanning subtree overlaps with another component
+        let mut overlapping = Vec::new();
+        if tree_conflicts.is_empty() && component_conflicts.is_empty() {
+            let spanned = spanning_subtree(&tree, &in_tree);
+            let mut shared = vec![false; tree.len()];
+
+            for (other, (_, other_tree)) in solution.trees().iter().enumerate() {
+                if other == component {
+                    continue;
+                }
+                let other_leaves: Vec<bool> = {
+                    let mut x = vec![false; num_leaves + 1];
+                    for (_, l) in FlatTree::new(other_tree).leaves() {
+                        if let Some(x) = x.get_mut(l.0 as usize) {
+                            *x = true;
+                        }
+                    }
+                    x
+                };
+                let other_restriction = Restriction::new(&tree, num_leaves, |l| {
+                    other_leaves.get(l.0 as usize).copied().unwrap_or(false)
+                });
+                let other_spanned = spanning_subtree(&tree, &other_restriction);
+
+                let mut overlaps = false;
+                for u in 0..tree.len() {
+                    if spanned[u] && other_spanned[u] {
+                        shared[u] = true;
+                        overlaps = true;
+                    }
+                }
+                if overlaps {
+                    overlapping.push(other);
+                }
+            }
+
+            tree_conflicts = (0..tree.len())
+                .filter(|&u| shared[u])
+                .map(|u| tree.node(u).node_idx)
+                .collect();
+        }
+
+        Some(Self {
+            tree: tree_index,
+            component,
+            leaves,
+            tree_conflicts,
+            component_conflicts,
+            overlapping,
+        })
+    }
+
+    /// Analyzes a [`CheckerError::Mismatch`]; returns `None` for all other errors
+    pub fn from_error(
+        instance: &Instance,
+        solution: &Solution,
+        error: &CheckerError,
+    ) -> Option<Self> {
+        match error {
+            CheckerError::Mismatch {
+                inst_lineno,
+                sol_lineno,
+            } => Self::analyze(instance, solution, *inst_lineno, *sol_lineno),
+            _ => None,
+        }
+    }
+
+    /// Index of the instance tree in [`Instance::trees`]
+    pub fn tree(&self) -> usize {
+        self.tree
+    }
+
+    /// Index of the component in [`Solution::trees`]
+    pub fn component(&self) -> usize {
+        self.component
+    }
+
+    /// Leaves of the component in increasing order
+    pub fn leaves(&self) -> &[Label] {
+        &self.leaves
+    }
+
+    /// Nodes of the instance tree at which the topologies disagree, i.e. whose restricted
+    /// cluster is no cluster of the component. If the topologies agree, these are the nodes
+    /// shared with the spanning subtrees of the [`Mismatch::overlapping`] components.
+    pub fn tree_conflicts(&self) -> &[NodeIdx] {
+        &self.tree_conflicts
+    }
+
+    /// Clusters (sorted leaf sets) of the component that are not induced by the instance
+    /// tree. Solution trees have no meaningful node indices, so nodes are given by their
+    /// clusters.
+    pub fn component_conflicts(&self) -> &[Vec<Label>] {
+        &self.component_conflicts
+    }
+
+    /// Indices of the other components whose spanning subtrees share nodes with the one of
+    /// this component (only computed if the topologies agree)
+    pub fn overlapping(&self) -> &[usize] {
+        &self.overlapping
+    }
+
+    /// Returns whether the mismatch is caused by disagreeing topologies (rather than by
+    /// overlapping components)
+    pub fn is_topological(&self) -> bool {
+        self.overlapping.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        checks::{
+            checker::ClaimedScoreMismatch,
+            forest_checker::{ForestChecker, IsolateTreeChecker},
+        },
+        io::tests::test_instances,
+    };
+
+    fn check_solution_from(instance: &Instance, solution: &Solution) -> Result<(), CheckerError> {
+        IsolateTreeChecker::default().check(instance, solution)
+    }
+
+    fn mismatch(instance: &str, solution: &str) -> Mismatch {
+        let instance = Instance::read_from(instance.as_bytes(), false).unwrap();
+        let solution = Solution::read_from(solution.as_bytes(), instance.num_leaves(), false)
+            .ok()
+            .unwrap();
+        let error = check_solution_from(&instance, &solution).err().unwrap();
+        Mismatch::from_error(&instance, &solution, &error).unwrap()
+    }
+
+    #[test]
+    fn topology() {
+        let m = mismatch(
+            "#p 2 4\n((1,2),(3,4));\n((1,2),(3,4));\n",
+            "((1,3),(2,4));\n",
+        );
+        assert_eq!((m.tree(), m.component()), (0, 0));
+        assert_eq!(m.leaves(), &[Label(1), Label(2), Label(3), Label(4)]);
+        assert!(m.is_topological());
+        // both children of the root of the instance tree induce wrong clusters
+        assert_eq!(m.tree_conflicts().len(), 2);
+        assert_eq!(
+            m.component_conflicts(),
+            &[vec![Label(1), Label(3)], vec![Label(2), Label(4)]]
+        );
+        assert!(m.overlapping().is_empty());
+    }
+
+    #[test]
+    fn overlap() {
+        // (1,3) and (2,4) agree with the tree, but their spanning subtrees share the root
+        let m = mismatch("#p 1 4\n((1,2),(3,4));\n", "(1,3);\n(2,4);\n");
+        assert!(!m.is_topological());
+        assert!(m.component_conflicts().is_empty());
+        assert_eq!(m.overlapping(), &[1 - m.component()]);
+        assert_eq!(m.tree_conflicts().len(), 3);
+    }
+
+    #[test]
+    fn invalid_testcases() {
+        for (path, solution) in test_instances("invalid") {
+            let instance = Instance::read(&path, false).unwrap();
+            let Ok(solution) = Solution::read(&solution.unwrap(), instance.num_leaves(), false)
+            else {
+                continue;
+            };
+            if let Err(error) = check_solution_from(&instance, &solution) {
+                let m = Mismatch::from_error(&instance, &solution, &error).unwrap();
+                assert!(
+                    !m.tree_conflicts().is_empty() || !m.component_conflicts().is_empty(),
+                    "{path:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn other_errors() {
+        let instance = Instance::read_from("#p 1 2\n(1,2);\n".as_bytes(), false).unwrap();
+        let solution = Solution::read_from("(1,2);\n".as_bytes(), 2, false)
+            .ok()
+            .unwrap();
+        let error = CheckerError::ClaimedScoreMismatch(ClaimedScoreMismatch {
+            claimed: 1,
+            score: 2,
+        });
+        assert!(Mismatch::from_error(&instance, &solution, &error).is_none());
+        assert!(Mismatch::analyze(&instance, &solution, 0, 7).is_none());
+    }
+}
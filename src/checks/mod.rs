@@ -1,4 +1,8 @@
 pub mod bin_forest;
 pub mod bin_tree_with_parent;
 pub mod checker;
+pub mod cluster_checker;
+pub mod flat_tree;
+pub mod forest_checker;
 pub mod lint_leaf_labels_coverage;
+pub mod mismatch;
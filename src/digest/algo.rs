@@ -1,58 +1,85 @@
-use crate::checks::bin_tree_with_parent::NodeCursor;
+use crate::checks::{bin_tree_with_parent::NodeCursor, flat_tree::FlatTree};
 use crate::digest::digest_output::{
-    DIGEST_BYTES, InstanceDigest, InstanceDigestBuilder, SolutionDigest, SolutionDigestBuilder,
+    BoundSolutionDigestBuilder, DIGEST_BYTES, DigestError, InstanceDigest, InstanceDigestBuilder,
+    SizeClass, SolutionDigest, SolutionDigestBuilder,
 };
+use crate::digest::merkle::digest_bintree_merkle;
+use crate::digest::version::*;
+use crate::io::{instance_reader::Instance, solution_reader::Solution};
 use digest::Output;
-use pace26io::binary_tree::TopDownCursor;
-use pace26io::newick::NewickWriter;
+use pace26io::binary_tree::{Label, TopDownCursor};
 use sha2::{Digest, Sha256};
-use std::io::Write;
+use thiserror::Error;
 
 pub const DIGEST_HEX_DIGITS: usize = 32;
-type Algo = Sha256;
+pub(crate) type Algo = Sha256;
 
 /// Computes the digest of an instance. The digest is invariant in the order of trees and
 /// swapping of children. The first digit indicates the number approximate number of trees,
 /// the second the approximate number of leaves (both in a logarithmic scale). The digest value
 /// is returned in hexadecimal representation containing exactly [`DIGEST_HEX_DIGITS`] digits.
-///
-/// # Warning
-/// Modifies the tree by normalizing the order of each inner leaf.
+/// The trees are not modified.
 pub fn digest_instance(trees: Vec<NodeCursor>, num_leaves: u32) -> InstanceDigest {
     digest_instance_with_approx(trees, num_leaves, None)
 }
 
 /// Same as [`digest_instance`] if approx is none. Otherwise also include the approx line in the digest hash.
-///
-/// # Warning
-/// Modifies the tree by normalizing the order of each inner leaf.
 pub fn digest_instance_with_approx(
     trees: Vec<NodeCursor>,
     num_leaves: u32,
     approx: Option<(f64, u32)>,
+) -> InstanceDigest {
+    digest_instance_impl(trees, num_leaves, approx, DigestVersion::V0)
+}
+
+/// Same as [`digest_instance_with_approx`], but computed with the scheme of the given
+/// version. Use [`DigestVersion::LATEST`] for new digests.
+pub fn digest_instance_with_version(
+    trees: Vec<NodeCursor>,
+    num_leaves: u32,
+    approx: Option<(f64, u32)>,
+    version: DigestVersion,
+) -> VersionedInstanceDigest {
+    Versioned::new(
+        version,
+        digest_instance_impl(trees, num_leaves, approx, version),
+    )
+}
+
+fn digest_instance_impl(
+    trees: Vec<NodeCursor>,
+    num_leaves: u32,
+    approx: Option<(f64, u32)>,
+    version: DigestVersion,
 ) -> InstanceDigest {
     let num_trees = trees.len() as u32;
 
     let digest = {
-        let mut digests: Vec<_> = trees.into_iter().map(digest_bintree).collect();
+        let mut digests: Vec<_> = trees.iter().map(|t| digest_tree(t, version)).collect();
         if let Some((a, b)) = approx {
             let mut algo = Algo::new();
-            let a = (1000. * a) as i32;
-            algo.update(a.to_le_bytes());
-            algo.update(b.to_le_bytes());
+            match version {
+                DigestVersion::V0 => {
+                    let a = (1000. * a) as i32;
+                    algo.update(a.to_le_bytes());
+                    algo.update(b.to_le_bytes());
+                }
+                _ => {
+                    // `Display` of `f64` yields the shortest representation that round-trips
+                    algo.update(format!("#a {a} {b}"));
+                }
+            }
             digests.push(algo.finalize());
         }
         digest_digests(&mut digests)
     };
 
-    // we use a logarithmic scale to indicate the approximate number of trees and leaves
-    let tree_score = num_trees.saturating_sub(1).ilog2().min(0xf);
-    let leaves_score = num_leaves.ilog(2).saturating_sub(3).min(0xf);
+    let size_class = SizeClass::new(num_trees, num_leaves);
 
     InstanceDigestBuilder::default()
-        .push_u4(tree_score as u8)
+        .push_u4(size_class.tree_class())
         .unwrap()
-        .push_u4(leaves_score as u8)
+        .push_u4(size_class.leaf_class())
         .unwrap()
         .push_slice(&digest.as_slice()[..DIGEST_BYTES - 1])
         .unwrap()
@@ -69,18 +96,16 @@ pub fn digest_instance_with_approx(
 /// to include isolated nodes (i.e. a tree where the root is the sole leaf). Such trees are
 /// ignored in the input, but can also be omitted. For this reason, the solution's size (including
 /// isolated nodes) needs to be passed explicitly.
-///
-/// # Warning
-/// Modifies the tree by normalizing the order of each inner leaf.
 pub fn digest_solution(trees: Vec<NodeCursor>, score: u32) -> SolutionDigest {
-    let digest = {
-        let mut digests: Vec<_> = trees
-            .into_iter()
-            .filter(|root| !root.is_leaf())
-            .map(digest_bintree)
-            .collect();
-        digest_digests(&mut digests)
-    };
+    digest_solution_impl(trees, score, DigestVersion::V0)
+}
+
+fn digest_solution_impl(
+    trees: Vec<NodeCursor>,
+    score: u32,
+    version: DigestVersion,
+) -> SolutionDigest {
+    let digest = digest_solution_forest(trees, version);
 
     SolutionDigestBuilder::default()
         .push_u16(score.min(0xffff) as u16)
@@ -91,26 +116,207 @@ pub fn digest_solution(trees: Vec<NodeCursor>, score: u32) -> SolutionDigest {
         .unwrap()
 }
 
-/// Computes a hash digest for a binary tree.
-///
-/// # Warning
-/// Modifies the tree by normalizing the order of each inner leaf.
-fn digest_bintree(tree: NodeCursor) -> Output<Algo> {
-    struct WriteAdapter(Algo);
-    impl Write for WriteAdapter {
-        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-            self.0.update(buf);
-            Ok(buf.len())
-        }
-        fn flush(&mut self) -> std::io::Result<()> {
-            Ok(())
+/// Computes a digest of a solution that is bound to the instance it solves. In contrast to
+/// [`digest_solution`], which only depends on the forest, the digest also covers the
+/// [`InstanceDigest`] of the instance. Hence, a (instance digest, bound solution digest) pair
+/// can only be reproduced from a solution of exactly this instance. As in [`SolutionDigest`],
+/// the first four digits indicate the number of trees in the solution (clamped at 0xffff).
+/// The forest is digested with the scheme of `version`; use [`DigestVersion::LATEST`] for
+/// new digests.
+pub fn digest_bound_solution(
+    trees: Vec<NodeCursor>,
+    score: u32,
+    instance: &InstanceDigest,
+    version: DigestVersion,
+) -> VersionedBoundSolutionDigest {
+    let forest = digest_solution_forest(trees, version);
+
+    let digest = {
+        let mut algo = Algo::new();
+        algo.update(b"bound");
+        algo.update(instance.to_binary());
+        algo.update(forest);
+        algo.finalize()
+    };
+
+    let digest = BoundSolutionDigestBuilder::default()
+        .push_u16(score.min(0xffff) as u16)
+        .unwrap()
+        .push_slice(&digest.as_slice()[..DIGEST_BYTES - 2])
+        .unwrap()
+        .build()
+        .unwrap();
+
+    Versioned::new(version, digest)
+}
+
+/// Same as [`digest_solution`], but computed with the scheme of the given version.
+pub fn digest_solution_with_version(
+    trees: Vec<NodeCursor>,
+    score: u32,
+    version: DigestVersion,
+) -> VersionedSolutionDigest {
+    Versioned::new(version, digest_solution_impl(trees, score, version))
+}
+
+/// Recomputes the digest of `instance` with the version of `declared` and compares both.
+/// The instance is not modified.
+pub fn verify_instance_digest(
+    instance: &Instance,
+    declared: &str,
+) -> Result<VersionedInstanceDigest, DigestVerifyError> {
+    let declared = VersionedInstanceDigest::try_from(declared)?;
+    let trees = trees_for_digest(instance.trees());
+    let computed = digest_instance_with_version(
+        trees,
+        instance.num_leaves(),
+        instance.approx(),
+        declared.version(),
+    );
+
+    if computed != declared {
+        return Err(DigestVerifyError::Mismatch {
+            declared: declared.to_string(),
+            computed: computed.to_string(),
+        });
+    }
+
+    Ok(computed)
+}
+
+/// Recomputes the digest of `solution` with the version of `declared` and compares both.
+/// The solution is not modified.
+pub fn verify_solution_digest(
+    solution: &Solution,
+    declared: &str,
+) -> Result<VersionedSolutionDigest, DigestVerifyError> {
+    let declared = VersionedSolutionDigest::try_from(declared)?;
+    let trees = trees_for_digest(solution.trees());
+    let computed =
+        digest_solution_with_version(trees, solution.num_trees() as u32, declared.version());
+
+    if computed != declared {
+        return Err(DigestVerifyError::Mismatch {
+            declared: declared.to_string(),
+            computed: computed.to_string(),
+        });
+    }
+
+    Ok(computed)
+}
+
+/// Recomputes the digest of `solution` bound to `instance` with the version of `declared` and
+/// compares both. Succeeds only if `declared` was computed from an identical solution of the
+/// same instance. The solution is not modified.
+pub fn verify_bound_solution_digest(
+    solution: &Solution,
+    instance: &InstanceDigest,
+    declared: &str,
+) -> Result<VersionedBoundSolutionDigest, DigestVerifyError> {
+    let declared = VersionedBoundSolutionDigest::try_from(declared)?;
+    let trees = trees_for_digest(solution.trees());
+    let computed = digest_bound_solution(
+        trees,
+        solution.num_trees() as u32,
+        instance,
+        declared.version(),
+    );
+
+    if computed != declared {
+        return Err(DigestVerifyError::Mismatch {
+            declared: declared.to_string(),
+            computed: computed.to_string(),
+        });
+    }
+
+    Ok(computed)
+}
+
+/// Returns (shallow) copies of the cursors of `trees` to be passed to a digest function
+pub(crate) fn trees_for_digest(trees: &[(usize, NodeCursor)]) -> Vec<NodeCursor> {
+    trees.iter().map(|(_, t)| t.clone()).collect()
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum DigestVerifyError {
+    #[error("Malformed digest: {0}")]
+    Malformed(#[from] DigestError),
+
+    #[error("Declared digest {declared} differs from computed digest {computed}")]
+    Mismatch { declared: String, computed: String },
+}
+
+/// Computes a hash digest for a binary tree with the scheme of `version`
+fn digest_tree(tree: &NodeCursor, version: DigestVersion) -> Output<Algo> {
+    match version {
+        DigestVersion::V0 | DigestVersion::V1 => digest_bintree(tree),
+        DigestVersion::V2 => digest_bintree_merkle(tree),
+    }
+}
+
+/// Computes a hash digest for a binary tree, namely of its Newick string after normalizing the
+/// child order (see [`NodeCursor::normalize_child_order`]). The string is fed to the hash
+/// while traversing the tree in this order; the tree is neither modified nor serialized.
+fn digest_bintree(tree: &NodeCursor) -> Output<Algo> {
+    enum Item {
+        Node(usize),
+        Text(&'static [u8]),
+    }
+
+    let tree = FlatTree::new(tree);
+
+    // smallest leaf in each subtree; children are stored after their parents
+    let mut min_leaf = vec![u32::MAX; tree.len()];
+    for (u, node) in tree.nodes().iter().enumerate().rev() {
+        min_leaf[u] = match (node.label, node.children) {
+            (Some(Label(label)), _) => label,
+            (None, Some((left, right))) => min_leaf[left].min(min_leaf[right]),
+            (None, None) => u32::MAX,
+        };
+    }
+
+    let mut algo = Algo::new();
+    let mut stack = vec![Item::Text(b";"), Item::Node(tree.root())];
+    while let Some(item) = stack.pop() {
+        let u = match item {
+            Item::Text(text) => {
+                algo.update(text);
+                continue;
+            }
+            Item::Node(u) => u,
+        };
+
+        match (tree.node(u).label, tree.node(u).children) {
+            (Some(Label(label)), _) => algo.update(label.to_string()),
+            (None, Some((left, right))) => {
+                let (first, second) = if min_leaf[left] <= min_leaf[right] {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                algo.update(b"(");
+                stack.extend([
+                    Item::Text(b")"),
+                    Item::Node(second),
+                    Item::Text(b","),
+                    Item::Node(first),
+                ]);
+            }
+            (None, None) => {}
         }
     }
 
-    let mut writer = WriteAdapter(Algo::new());
-    tree.normalize_child_order();
-    tree.write_newick(&mut writer).unwrap();
-    writer.0.finalize()
+    algo.finalize()
+}
+
+/// Digest of the non-trivial trees of a solution forest, independent of their order
+fn digest_solution_forest(trees: Vec<NodeCursor>, version: DigestVersion) -> Output<Algo> {
+    let mut digests: Vec<_> = trees
+        .iter()
+        .filter(|root| !root.is_leaf())
+        .map(|t| digest_tree(t, version))
+        .collect();
+    digest_digests(&mut digests)
 }
 
 fn digest_digests(digests: &mut [Output<Algo>]) -> Output<Algo> {
@@ -127,9 +333,10 @@ fn digest_digests(digests: &mut [Output<Algo>]) -> Output<Algo> {
 #[cfg(test)]
 mod tests {
     use crate::checks::bin_tree_with_parent::{BinTreeWithParentBuilder, NodeCursor};
+    use crate::digest::digest_output::{InstanceDigest, SolutionDigest};
     use hex_literal::hex;
     use pace26io::binary_tree::NodeIdx;
-    use pace26io::newick::BinaryTreeParser;
+    use pace26io::newick::{BinaryTreeParser, NewickWriter};
 
     fn parse_tree(nw: &str) -> NodeCursor {
         BinTreeWithParentBuilder::default()
@@ -140,7 +347,7 @@ mod tests {
     #[test]
     fn digest_bintree() {
         const TREE: &str = "((3,4),(2,1));"; // the hash digest below was computed for the string "((1,2),(3,4));"
-        let digest = super::digest_bintree(parse_tree(TREE));
+        let digest = super::digest_bintree(&parse_tree(TREE));
         assert_eq!(
             digest[..],
             hex!("5aecb10e41777da0a300dae254d01a2fad3fd892d0b3b553821e2e684194a1f6")
@@ -161,7 +368,12 @@ mod tests {
 
         for instance in instances {
             let trees: Vec<_> = instance.iter().map(|&x| parse_tree(x)).collect();
-            let digests = super::digest_instance(trees, 4);
+            let digests = super::digest_instance(trees.clone(), 4);
+
+            // the trees are not modified
+            for (tree, nw) in trees.iter().zip(&instance) {
+                assert_eq!(tree.top_down().to_newick_string(), *nw);
+            }
 
             if let Some(previous_hash) = previous_hash {
                 assert_eq!(digests, previous_hash);
@@ -182,6 +394,208 @@ mod tests {
         assert_ne!(digest_wo_approx, digest_with_approx);
     }
 
+    #[test]
+    fn digest_instance_with_version() {
+        use crate::digest::version::DigestVersion;
+
+        let trees = || vec![parse_tree("((3,4),(2,1));"), parse_tree("(1,(2,(3,4)));")];
+        let digest = |approx, version| {
+            *super::digest_instance_with_version(trees(), 4, approx, version).digest()
+        };
+
+        // legacy digests remain unchanged
+        assert_eq!(
+            digest(Some((1.23, 1337)), DigestVersion::V0),
+            super::digest_instance_with_approx(trees(), 4, Some((1.23, 1337)))
+        );
+
+        // V0 truncates the approx parameter, later versions encode it exactly
+        for (a, b) in [(1.2345, 1.2346), (0.1, 0.1 + 1e-12)] {
+            assert_eq!(
+                digest(Some((a, 5)), DigestVersion::V0),
+                digest(Some((b, 5)), DigestVersion::V0)
+            );
+            assert_ne!(
+                digest(Some((a, 5)), DigestVersion::V1),
+                digest(Some((b, 5)), DigestVersion::V1)
+            );
+        }
+
+        assert_eq!(
+            digest(None, DigestVersion::V0),
+            digest(None, DigestVersion::V1)
+        );
+        assert_ne!(
+            digest(Some((1.5, 5)), DigestVersion::V0),
+            digest(Some((1.5, 5)), DigestVersion::V1)
+        );
+    }
+
+    #[test]
+    fn digest_merkle_version() {
+        use crate::digest::version::DigestVersion;
+
+        let instances = [
+            ["((3,4),(2,1));", "(1,(2,(3,4)));"],
+            ["(1,(2,(4,3)));", "((4,3),(1,2));"],
+        ];
+
+        let mut digests = Vec::new();
+        for instance in instances {
+            let trees: Vec<_> = instance.iter().map(|&nw| parse_tree(nw)).collect();
+            let digest = super::digest_instance_with_version(
+                trees.clone(),
+                4,
+                Some((1.5, 2)),
+                DigestVersion::V2,
+            );
+            digests.push(digest);
+
+            // the trees are not modified
+            for (tree, nw) in trees.iter().zip(instance) {
+                assert_eq!(tree.top_down().to_newick_string(), nw);
+            }
+        }
+
+        assert_eq!(digests[0], digests[1]);
+        assert_eq!(digests[0].digest().size_class().num_leaves(), 0..=15);
+
+        let trees = || instances[0].iter().map(|&nw| parse_tree(nw)).collect();
+        let v1 = super::digest_instance_with_version(trees(), 4, None, DigestVersion::V1);
+        let v2 = super::digest_instance_with_version(trees(), 4, None, DigestVersion::V2);
+        assert_ne!(v1.digest(), v2.digest());
+
+        let v1 = super::digest_solution_with_version(trees(), 2, DigestVersion::V1);
+        let v2 = super::digest_solution_with_version(trees(), 2, DigestVersion::V2);
+        assert_ne!(v1.digest(), v2.digest());
+        assert_eq!(v2.digest().score(), 2);
+    }
+
+    #[test]
+    fn verify_digests() {
+        use super::DigestVerifyError;
+        use crate::digest::version::DigestVersion;
+        use crate::io::{instance_reader::Instance, solution_reader::Solution};
+        use std::path::Path;
+
+        const IDIGEST: &str = "0010b172a28d0664d5521e1296fc3586";
+
+        let instance = Instance::read(Path::new("testcases/tiny/tiny01.in"), false)
+            .ok()
+            .unwrap();
+        assert!(super::verify_instance_digest(&instance, IDIGEST).is_ok());
+        assert!(super::verify_instance_digest(&instance, &format!("01{IDIGEST}")).is_ok());
+        assert!(matches!(
+            super::verify_instance_digest(&instance, "0010b172a28d0664d5521e1296fc3587"),
+            Err(DigestVerifyError::Mismatch { .. })
+        ));
+        assert!(matches!(
+            super::verify_instance_digest(&instance, &format!("ff{IDIGEST}")),
+            Err(DigestVerifyError::Malformed(_))
+        ));
+
+        // verification does not modify the instance
+        assert!(super::verify_instance_digest(&instance, IDIGEST).is_ok());
+
+        let latest = super::digest_instance_with_version(
+            instance.trees().iter().map(|(_, t)| t.clone()).collect(),
+            instance.num_leaves(),
+            instance.approx(),
+            DigestVersion::LATEST,
+        );
+        assert!(super::verify_instance_digest(&instance, &latest.to_string()).is_ok());
+        assert!(super::verify_instance_digest(&instance, IDIGEST).is_ok());
+
+        let solution = Solution::read(Path::new("testcases/tiny/tiny01.out"), 6, false)
+            .ok()
+            .unwrap();
+        let trees = solution
+            .trees()
+            .iter()
+            .map(|(_, t)| t.clone_and_rebuild())
+            .collect();
+        let sdigest = super::digest_solution(trees, 4).to_string();
+        assert!(super::verify_solution_digest(&solution, &sdigest).is_ok());
+        assert!(super::verify_solution_digest(&solution, &format!("01{sdigest}")).is_ok());
+        assert!(matches!(
+            super::verify_solution_digest(&solution, &format!("02{sdigest}")),
+            Err(DigestVerifyError::Mismatch { .. })
+        ));
+        assert!(matches!(
+            super::verify_solution_digest(&solution, &format!("7f{sdigest}")),
+            Err(DigestVerifyError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn digest_bound_solution() {
+        use crate::digest::version::DigestVersion;
+
+        let instance_a = InstanceDigest::try_from("0010b172a28d0664d5521e1296fc3586").unwrap();
+        let instance_b = InstanceDigest::try_from("0010b172a28d0664d5521e1296fc3587").unwrap();
+        let trees = |nws: &[&str]| nws.iter().map(|&nw| parse_tree(nw)).collect::<Vec<_>>();
+        let digest = |nws: &[&str], instance, version| {
+            super::digest_bound_solution(trees(nws), 2, instance, version)
+        };
+
+        let a = digest(&["((3,4),(2,1));", "5;"], &instance_a, DigestVersion::V0);
+        let reordered = digest(&["5;", "((1,2),(4,3));"], &instance_a, DigestVersion::V0);
+        let b = digest(&["((3,4),(2,1));", "5;"], &instance_b, DigestVersion::V0);
+        let other = digest(&["((3,5),(2,1));", "4;"], &instance_a, DigestVersion::V0);
+
+        assert_eq!(a, reordered);
+        assert_ne!(a, b);
+        assert_ne!(a, other);
+        assert_eq!(a.digest().score(), 2);
+
+        // the unbound digest of the same forest does not depend on the instance
+        assert_ne!(
+            a.digest().to_binary(),
+            super::digest_solution(trees(&["((3,4),(2,1));", "5;"]), 2).to_binary()
+        );
+
+        // the version is encoded in the text and selects the scheme of the forest digest
+        let v2 = digest(&["((3,4),(2,1));", "5;"], &instance_a, DigestVersion::V2);
+        assert_eq!(v2.version(), DigestVersion::V2);
+        assert!(v2.to_string().starts_with("02"));
+        assert_ne!(a.digest(), v2.digest());
+    }
+
+    #[test]
+    fn verify_bound_solution_digest() {
+        use super::DigestVerifyError;
+        use crate::digest::version::DigestVersion;
+        use crate::io::solution_reader::Solution;
+
+        let solution = Solution::read_from(&b"((1,2),3);\n4;\n"[..], 4, false)
+            .ok()
+            .unwrap();
+        let instance = InstanceDigest::try_from("0010b172a28d0664d5521e1296fc3586").unwrap();
+        let other = InstanceDigest::try_from("0010b172a28d0664d5521e1296fc3587").unwrap();
+
+        for version in [DigestVersion::V0, DigestVersion::LATEST] {
+            let trees = solution
+                .trees()
+                .iter()
+                .map(|(_, t)| t.clone_and_rebuild())
+                .collect();
+            let declared = super::digest_bound_solution(trees, 2, &instance, version).to_string();
+
+            let verified =
+                super::verify_bound_solution_digest(&solution, &instance, &declared).unwrap();
+            assert_eq!(verified.version(), version);
+            assert!(matches!(
+                super::verify_bound_solution_digest(&solution, &other, &declared),
+                Err(DigestVerifyError::Mismatch { .. })
+            ));
+        }
+
+        assert!(matches!(
+            super::verify_bound_solution_digest(&solution, &instance, "00"),
+            Err(DigestVerifyError::Malformed(_))
+        ));
+    }
+
     #[test]
     fn digest_instance_tree_number() {
         let instances = vec![
@@ -200,6 +614,24 @@ mod tests {
         assert!(digests[1].to_string().starts_with("1"));
     }
 
+    #[test]
+    fn decode_header() {
+        for num_trees in 1..=40 {
+            let trees = || (0..num_trees).map(|_| parse_tree("(1,2);")).collect();
+            for num_leaves in [1, 2, 15, 16, 17, 31, 32, 1000, 1 << 18, u32::MAX] {
+                let size = super::digest_instance(trees(), num_leaves).size_class();
+                assert!(size.num_trees().contains(&num_trees), "{num_trees}");
+                assert!(size.num_leaves().contains(&num_leaves), "{num_leaves}");
+            }
+
+            let digest = super::digest_solution(trees(), num_trees);
+            assert_eq!(digest.score(), num_trees);
+        }
+
+        let digest = super::digest_solution(vec![parse_tree("(1,2);")], 100_000);
+        assert_eq!(digest.score(), SolutionDigest::MAX_SCORE);
+    }
+
     #[test]
     fn digest_solution() {
         // the following instances need to receive the same digest
@@ -214,7 +646,12 @@ mod tests {
 
         for sol in solutions {
             let trees: Vec<_> = sol.iter().map(|&nw| parse_tree(nw)).collect();
-            let digests = super::digest_solution(trees, 3);
+            let digests = super::digest_solution(trees.clone(), 3);
+
+            // the trees are not modified
+            for (tree, nw) in trees.iter().zip(&sol) {
+                assert_eq!(tree.top_down().to_newick_string(), *nw);
+            }
 
             if let Some(previous_hash) = previous_hash {
                 assert_eq!(digests, previous_hash);
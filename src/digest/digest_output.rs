@@ -2,6 +2,7 @@ use itertools::Itertools;
 use serde::de::{Error, Unexpected};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
 use thiserror::Error;
 
 pub const DIGEST_BYTES: usize = 16;
@@ -22,6 +23,9 @@ pub enum DigestError {
 
     #[error("Invalid value")]
     InvalidValue,
+
+    #[error("Unsupported digest version {0}")]
+    UnsupportedVersion(u8),
 }
 
 macro_rules! impl_digest_output {
@@ -435,3 +439,232 @@ macro_rules! impl_digest_output {
 impl_digest_output!(InstanceDigest);
 impl_digest_output!(SolutionDigest);
 impl_digest_output!(FileDigest);
+impl_digest_output!(BoundSolutionDigest);
+impl_digest_output!(InstanceFingerprint);
+
+/// Approximate size of an instance as encoded in the first byte of its [`InstanceDigest`].
+/// Both numbers are stored on a logarithmic scale, so only ranges can be recovered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SizeClass {
+    tree_class: u8,
+    leaf_class: u8,
+}
+
+impl SizeClass {
+    /// Largest class value; it also covers all larger instances
+    pub const MAX_CLASS: u8 = 0xf;
+
+    /// Size class of an instance with the given number of trees and leaves
+    pub fn new(num_trees: u32, num_leaves: u32) -> Self {
+        // we use a logarithmic scale to indicate the approximate number of trees and leaves
+        let tree_class = num_trees
+            .saturating_sub(1)
+            .checked_ilog2()
+            .unwrap_or(0)
+            .min(Self::MAX_CLASS as u32);
+        let leaf_class = num_leaves
+            .checked_ilog2()
+            .unwrap_or(0)
+            .saturating_sub(3)
+            .min(Self::MAX_CLASS as u32);
+
+        Self {
+            tree_class: tree_class as u8,
+            leaf_class: leaf_class as u8,
+        }
+    }
+
+    fn decode(buffer: &DigestBuffer) -> Self {
+        Self {
+            tree_class: buffer[0] >> 4,
+            leaf_class: buffer[0] & 0xf,
+        }
+    }
+
+    /// Raw value of the first hex digit
+    pub fn tree_class(&self) -> u8 {
+        self.tree_class
+    }
+
+    /// Raw value of the second hex digit
+    pub fn leaf_class(&self) -> u8 {
+        self.leaf_class
+    }
+
+    /// Range of the number of trees; open-ended (up to `u32::MAX`) for the largest class
+    pub fn num_trees(&self) -> RangeInclusive<u32> {
+        // tree_class = ilog2(num_trees - 1), where num_trees <= 2 maps to 0
+        match self.tree_class {
+            0 => 1..=2,
+            Self::MAX_CLASS => (1 << Self::MAX_CLASS) + 1..=u32::MAX,
+            c => (1 << c) + 1..=1 << (c + 1),
+        }
+    }
+
+    /// Range of the number of leaves; open-ended (up to `u32::MAX`) for the largest class
+    pub fn num_leaves(&self) -> RangeInclusive<u32> {
+        // leaf_class = ilog2(num_leaves) - 3, where num_leaves < 16 maps to 0
+        match self.leaf_class {
+            0 => 0..=15,
+            Self::MAX_CLASS => 1 << (Self::MAX_CLASS + 3)..=u32::MAX,
+            c => 1 << (c + 3)..=(1 << (c + 4)) - 1,
+        }
+    }
+}
+
+impl InstanceDigest {
+    /// Decodes the approximate number of trees and leaves stored in the first byte.
+    ///
+    /// # Example
+    /// ```
+    /// use pace26checker::digest::digest_output::InstanceDigest;
+    ///
+    /// let digest = InstanceDigest::try_from("3510b172a28d0664d5521e1296fc3586").unwrap();
+    /// assert_eq!(digest.size_class().num_trees(), 9..=16);
+    /// assert_eq!(digest.size_class().num_leaves(), 256..=511);
+    /// ```
+    pub fn size_class(&self) -> SizeClass {
+        SizeClass::decode(&self.0)
+    }
+
+    /// Returns a [`Display`] adapter that appends the decoded size class to the digest,
+    /// e.g. `3510b172a28d0664d5521e1296fc3586 (9-16 trees, 256-511 leaves)`.
+    pub fn explain(&self) -> impl Display + '_ {
+        struct Explain<'a>(&'a InstanceDigest);
+
+        impl Display for Explain<'_> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                let size = self.0.size_class();
+                write!(
+                    f,
+                    "{} ({} trees, {} leaves)",
+                    self.0,
+                    DisplayRange(size.num_trees()),
+                    DisplayRange(size.num_leaves())
+                )
+            }
+        }
+
+        Explain(self)
+    }
+}
+
+impl SolutionDigest {
+    /// Largest score that can be stored; larger scores are clamped to it
+    pub const MAX_SCORE: u32 = 0xffff;
+
+    /// Decodes the score (i.e. the number of trees in the solution) stored in the first two
+    /// bytes. Scores of at least [`SolutionDigest::MAX_SCORE`] are returned as `MAX_SCORE`.
+    ///
+    /// # Example
+    /// ```
+    /// use pace26checker::digest::digest_output::SolutionDigest;
+    ///
+    /// let digest = SolutionDigest::try_from("002ab172a28d0664d5521e1296fc3586").unwrap();
+    /// assert_eq!(digest.score(), 42);
+    /// ```
+    pub fn score(&self) -> u32 {
+        decode_score(&self.0)
+    }
+
+    /// Returns a [`Display`] adapter that appends the decoded score to the digest,
+    /// e.g. `002ab172a28d0664d5521e1296fc3586 (score 42)`.
+    pub fn explain(&self) -> impl Display + '_ {
+        struct Explain<'a>(&'a SolutionDigest);
+
+        impl Display for Explain<'_> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                let score = self.0.score();
+                if score == SolutionDigest::MAX_SCORE {
+                    write!(f, "{} (score >= {score})", self.0)
+                } else {
+                    write!(f, "{} (score {score})", self.0)
+                }
+            }
+        }
+
+        Explain(self)
+    }
+}
+
+impl InstanceFingerprint {
+    /// Decodes the approximate number of trees and leaves; see [`InstanceDigest::size_class`].
+    pub fn size_class(&self) -> SizeClass {
+        SizeClass::decode(&self.0)
+    }
+}
+
+impl BoundSolutionDigest {
+    /// Decodes the score stored in the first two bytes; see [`SolutionDigest::score`].
+    pub fn score(&self) -> u32 {
+        decode_score(&self.0)
+    }
+}
+
+fn decode_score(buffer: &DigestBuffer) -> u32 {
+    u16::from_be_bytes([buffer[0], buffer[1]]) as u32
+}
+
+struct DisplayRange(RangeInclusive<u32>);
+
+impl Display for DisplayRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if *self.0.end() == u32::MAX {
+            write!(f, ">= {}", self.0.start())
+        } else {
+            write!(f, "{}-{}", self.0.start(), self.0.end())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_class() {
+        let digest = |prefix: &str| {
+            InstanceDigest::try_from(format!("{prefix}10b172a28d0664d5521e1296fc3586")).unwrap()
+        };
+
+        let small = digest("00").size_class();
+        assert_eq!((small.tree_class(), small.leaf_class()), (0, 0));
+        assert_eq!(small.num_trees(), 1..=2);
+        assert_eq!(small.num_leaves(), 0..=15);
+
+        let medium = digest("1a").size_class();
+        assert_eq!(medium.num_trees(), 3..=4);
+        assert_eq!(medium.num_leaves(), 8192..=16383);
+
+        let large = digest("ff").size_class();
+        assert_eq!(large.num_trees(), 32769..=u32::MAX);
+        assert_eq!(large.num_leaves(), 262144..=u32::MAX);
+
+        assert_eq!(
+            digest("35").explain().to_string(),
+            "3510b172a28d0664d5521e1296fc3586 (9-16 trees, 256-511 leaves)"
+        );
+        assert_eq!(
+            digest("ff").explain().to_string(),
+            "ff10b172a28d0664d5521e1296fc3586 (>= 32769 trees, >= 262144 leaves)"
+        );
+    }
+
+    #[test]
+    fn score() {
+        let digest = |prefix: &str| {
+            SolutionDigest::try_from(format!("{prefix}b172a28d0664d5521e1296fc3586")).unwrap()
+        };
+
+        assert_eq!(digest("0000").score(), 0);
+        assert_eq!(digest("0102").score(), 0x102);
+        assert_eq!(
+            digest("002a").explain().to_string(),
+            "002ab172a28d0664d5521e1296fc3586 (score 42)"
+        );
+        assert_eq!(
+            digest("ffff").explain().to_string(),
+            "ffffb172a28d0664d5521e1296fc3586 (score >= 65535)"
+        );
+    }
+}
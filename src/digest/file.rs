@@ -0,0 +1,171 @@
+use crate::digest::{
+    algo::Algo,
+    digest_output::{DIGEST_BYTES, FileDigest},
+};
+use sha2::Digest;
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+/// Digests of the raw and the normalized content of a file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FileDigests {
+    pub raw: FileDigest,
+    pub normalized: FileDigest,
+}
+
+/// Computes the digest of the exact bytes read. Any change to the file, including
+/// whitespace and line endings, changes the digest.
+pub fn digest_file_raw(mut reader: impl Read) -> std::io::Result<FileDigest> {
+    let mut algo = Algo::new();
+    std::io::copy(&mut reader, &mut HashWriter(&mut algo))?;
+    Ok(truncate(algo))
+}
+
+/// Computes the digest of the normalized content. Normalization
+///  - removes comment lines (`# ...`) and empty lines,
+///  - strips leading and trailing whitespace of each line,
+///  - replaces each run of whitespace within a line by a single space, except within
+///    double-quoted strings (e.g. JSON values of stride lines), and
+///  - terminates each line by `\n` (independent of the original line ending).
+///
+/// Hence, a file that is already normalized has identical raw and normalized digests. In
+/// contrast to [`InstanceDigest`](super::digest_output::InstanceDigest), the digest is
+/// sensitive to the order of trees and children, and covers stride lines.
+pub fn digest_file_normalized(mut reader: impl BufRead) -> std::io::Result<FileDigest> {
+    let mut algo = Algo::new();
+    let mut line = Vec::new();
+    let mut normalized = Vec::new();
+
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+
+        let content = line.trim_ascii();
+        if content.is_empty() || content == b"#" || content.starts_with(b"# ") {
+            continue;
+        }
+
+        normalized.clear();
+        let mut in_string = false;
+        let mut escaped = false;
+        for &b in content {
+            if in_string {
+                in_string = escaped || b != b'"';
+                escaped = !escaped && b == b'\\';
+            } else if b.is_ascii_whitespace() {
+                if normalized.last() == Some(&b' ') {
+                    continue;
+                }
+                normalized.push(b' ');
+                continue;
+            } else {
+                in_string = b == b'"';
+            }
+            normalized.push(b);
+        }
+        normalized.push(b'\n');
+        algo.update(&normalized);
+    }
+
+    Ok(truncate(algo))
+}
+
+/// Computes the raw and the normalized digest of a file, reading it only once.
+pub fn digest_file(path: &Path) -> std::io::Result<FileDigests> {
+    let mut content = Vec::new();
+    BufReader::new(File::open(path)?).read_to_end(&mut content)?;
+
+    Ok(FileDigests {
+        raw: digest_file_raw(content.as_slice())?,
+        normalized: digest_file_normalized(content.as_slice())?,
+    })
+}
+
+fn truncate(algo: Algo) -> FileDigest {
+    let mut buffer = [0u8; DIGEST_BYTES];
+    buffer.copy_from_slice(&algo.finalize()[..DIGEST_BYTES]);
+    buffer.into()
+}
+
+struct HashWriter<'a>(&'a mut Algo);
+
+impl std::io::Write for HashWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::tests::test_instances;
+
+    #[test]
+    fn raw() {
+        // truncated SHA-256 of the empty input
+        assert_eq!(
+            digest_file_raw(&b""[..]).unwrap().to_string(),
+            "e3b0c44298fc1c149afbf4c8996fb924"
+        );
+
+        let a = digest_file_raw(&b"#p 2 2\n(1,2);\n(1,2);\n"[..]).unwrap();
+        let b = digest_file_raw(&b"#p 2 2\r\n(1,2);\r\n(1,2);\r\n"[..]).unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn normalized() {
+        let canonical = b"#s name \"a  b\"\n#p 2 2\n(1,2);\n(1,2);\n";
+        let variants: [&[u8]; 4] = [
+            b"#s name \"a  b\"\n#p 2 2\n(1,2);\n(1,2);",
+            b"#s  name \"a  b\"\r\n#p 2 2\r\n(1,2);\r\n(1,2);\r\n",
+            b"# comment\n\n#s name   \"a  b\"\n  #p\t2 2 \n(1,2);\n#\n(1,2);\n\n",
+            b"#s name \"a  b\"\n#p 2  2\n(1,2);\n(1,2);\n# trailing comment",
+        ];
+
+        let expected = digest_file_normalized(&canonical[..]).unwrap();
+        assert_eq!(expected, digest_file_raw(&canonical[..]).unwrap());
+
+        for variant in variants {
+            assert_eq!(digest_file_normalized(variant).unwrap(), expected);
+        }
+
+        let different: [&[u8]; 4] = [
+            // whitespace within strings is significant
+            b"#s name \"a b\"\n#p 2 2\n(1,2);\n(1,2);\n",
+            b"#s name \"a  b\\\"  \"\n#p 2 2\n(1,2);\n(1,2);\n",
+            // order of trees and children is significant
+            b"#s name \"a  b\"\n#p 2 2\n(2,1);\n(1,2);\n",
+            // hash lines that are not comments are kept
+            b"#x\n#s name \"a  b\"\n#p 2 2\n(1,2);\n(1,2);\n",
+        ];
+        for variant in different {
+            assert_ne!(digest_file_normalized(variant).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn file() {
+        for (input, output) in test_instances("valid") {
+            for path in [input, output.unwrap()] {
+                let content = std::fs::read(&path).unwrap();
+                let digests = digest_file(&path).unwrap();
+                assert_eq!(digests.raw, digest_file_raw(content.as_slice()).unwrap());
+                assert_eq!(
+                    digests.normalized,
+                    digest_file_normalized(content.as_slice()).unwrap()
+                );
+            }
+        }
+    }
+}
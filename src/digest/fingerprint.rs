@@ -0,0 +1,667 @@
+use crate::checks::flat_tree::FlatTree;
+use crate::digest::{
+    algo::Algo,
+    digest_output::{DIGEST_BYTES, InstanceFingerprint, InstanceFingerprintBuilder, SizeClass},
+    merkle::{MerkleTree, SubtreeHash},
+};
+use crate::io::instance_reader::Instance;
+use pace26io::binary_tree::Label;
+use sha2::Digest;
+use std::collections::{BTreeMap, HashMap};
+
+/// Computes a fingerprint of an instance that is invariant under relabeling of the leaves,
+/// reordering of the trees, and swapping of children. Like an
+/// [`InstanceDigest`](super::digest_output::InstanceDigest), the first two hex digits encode
+/// the [`SizeClass`]. The approx line is not covered.
+///
+/// The fingerprint is the hash of a canonical form of the instance, i.e. of the
+/// [`MerkleTree`] root hashes of all trees after relabeling the leaves canonically. The
+/// canonical labeling is found by individualization-refinement: the leaves are colored by
+/// their positions (up to child order) in all trees until the coloring is stable, and while
+/// some leaves remain indistinguishable, each of them is individualized in turn. Among all
+/// labelings obtained, the one yielding the smallest form is chosen. Hence, two instances
+/// receive the same fingerprint iff they are equivalent (barring hash collisions). The running
+/// time may be exponential for highly symmetric instances, though the search skips branches
+/// that are equivalent under the symmetries found so far.
+///
+/// # Example
+/// ```
+/// use pace26checker::digest::fingerprint::fingerprint_instance;
+/// use pace26checker::io::instance_reader::Instance;
+///
+/// let a = Instance::read_from(&b"#p 2 4\n((1,2),(3,4));\n(1,(2,(3,4)));\n"[..], false).ok().unwrap();
+/// let b = Instance::read_from(&b"#p 2 4\n(4,(3,(2,1)));\n((4,3),(1,2));\n"[..], false).ok().unwrap();
+/// assert_eq!(fingerprint_instance(&a), fingerprint_instance(&b));
+/// ```
+pub fn fingerprint_instance(instance: &Instance) -> InstanceFingerprint {
+    let forest = Forest::new(instance);
+    let mut canonizer = Canonizer {
+        refiner: Refiner {
+            forests: vec![&forest],
+        },
+        forms: HashMap::new(),
+        automorphisms: Vec::new(),
+    };
+    canonizer.search(forest.initial_colors(), &mut Vec::new());
+
+    let mut algo = Algo::new();
+    if let Some(form) = canonizer.forms.keys().min() {
+        for hash in form {
+            algo.update(hash);
+        }
+    }
+    algo.update(instance.num_trees().to_le_bytes());
+    algo.update(instance.num_leaves().to_le_bytes());
+    let digest = algo.finalize();
+
+    let size_class = SizeClass::new(instance.num_trees(), instance.num_leaves());
+    InstanceFingerprintBuilder::default()
+        .push_u4(size_class.tree_class())
+        .unwrap()
+        .push_u4(size_class.leaf_class())
+        .unwrap()
+        .push_slice(&digest.as_slice()[..DIGEST_BYTES - 1])
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+/// Bijection between the leaf labels of two equivalent instances; see [`find_leaf_relabeling`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafRelabeling {
+    // index 0 is unused, since labels start at 1
+    map: Vec<Label>,
+}
+
+impl LeafRelabeling {
+    /// Returns the label in the second instance that corresponds to `label` of the first one
+    pub fn get(&self, label: Label) -> Option<Label> {
+        if label.0 == 0 {
+            return None;
+        }
+        self.map.get(label.0 as usize).copied()
+    }
+
+    /// Iterates over all pairs `(label in first instance, label in second instance)`
+    pub fn iter(&self) -> impl Iterator<Item = (Label, Label)> + '_ {
+        self.map
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(l, &m)| (Label(l as u32), m))
+    }
+}
+
+/// Searches a permutation of the leaf labels that maps instance `a` onto instance `b`, i.e.
+/// after relabeling each leaf `l` of `a` into `mapping.get(l)`, both instances contain the
+/// same trees up to their order and child swaps. Returns `None` if the instances are not
+/// equivalent. The approx line is ignored.
+///
+/// The search refines the leaf colorings of both instances jointly (as in
+/// [`fingerprint_instance`]) and backtracks over the remaining ambiguities. Each candidate is
+/// verified, so a returned relabeling is always correct. The running time may be exponential
+/// for highly symmetric instances.
+pub fn find_leaf_relabeling(a: &Instance, b: &Instance) -> Option<LeafRelabeling> {
+    if a.num_leaves() != b.num_leaves() || a.num_trees() != b.num_trees() {
+        return None;
+    }
+
+    let forests = [Forest::new(a), Forest::new(b)];
+    let target = forests[1].sorted_root_hashes(|l| l);
+    let mut refiner = Refiner {
+        forests: forests.iter().collect(),
+    };
+
+    let colors = vec![forests[0].initial_colors(), forests[1].initial_colors()];
+    refiner.search(colors, &target)
+}
+
+/// Leaf colors indexed by label; index 0 is unused
+type Colors = Vec<u32>;
+
+struct Forest {
+    trees: Vec<FlatTree>,
+    num_leaves: usize,
+}
+
+impl Forest {
+    fn new(instance: &Instance) -> Self {
+        Self {
+            trees: instance
+                .trees()
+                .iter()
+                .map(|(_, t)| FlatTree::new(t))
+                .collect(),
+            num_leaves: instance.num_leaves() as usize,
+        }
+    }
+
+    fn initial_colors(&self) -> Colors {
+        vec![0; self.num_leaves + 1]
+    }
+
+    fn sorted_root_hashes(&self, relabel: impl Fn(Label) -> Label) -> Vec<SubtreeHash> {
+        let mut hashes: Vec<_> = self
+            .trees
+            .iter()
+            .map(|t| *MerkleTree::new_relabeled(t, &relabel).root_hash())
+            .collect();
+        hashes.sort_unstable();
+        hashes
+    }
+}
+
+/// Color refinement over one or more forests. Colors are ranks of signatures that are sorted
+/// jointly over all forests, so equal colors in different forests have the same meaning.
+struct Refiner<'a> {
+    forests: Vec<&'a Forest>,
+}
+
+impl Refiner<'_> {
+    /// Refines `colors` (one per forest) until the partition of the leaves is stable
+    fn refine(&mut self, colors: &mut [Colors]) {
+        let mut num_classes = count_classes(colors);
+
+        loop {
+            let node_colors = self.node_colors(colors);
+            let contexts = self.contexts(&node_colors);
+
+            // a leaf's signature is its color followed by its sorted contexts in all trees
+            let mut leaves = Vec::new();
+            let mut signatures = Vec::new();
+            for (f, forest) in self.forests.iter().enumerate() {
+                let mut leaf_contexts = vec![Vec::new(); forest.num_leaves + 1];
+                for (t, tree) in forest.trees.iter().enumerate() {
+                    for (u, Label(l)) in tree.leaves() {
+                        if let Some(ctx) = leaf_contexts.get_mut(l as usize) {
+                            ctx.push(contexts[f][t][u]);
+                        }
+                    }
+                }
+
+                for (l, mut ctx) in leaf_contexts.into_iter().enumerate().skip(1) {
+                    ctx.sort_unstable();
+                    let mut signature = vec![colors[f][l]];
+                    signature.extend(ctx);
+                    signatures.push(signature);
+                    leaves.push((f, l));
+                }
+            }
+
+            let (ranks, _) = self.rank(&signatures, 0);
+            for ((f, l), rank) in leaves.into_iter().zip(ranks) {
+                colors[f][l] = rank;
+            }
+
+            let refined = count_classes(colors);
+            if refined == num_classes {
+                break;
+            }
+            num_classes = refined;
+        }
+    }
+
+    /// Colors all nodes bottom-up (by height), such that two nodes share a color iff their
+    /// subtrees are identical up to child order and the colors of their leaves.
+    fn node_colors(&mut self, colors: &[Colors]) -> Vec<Vec<Vec<u32>>> {
+        let mut result: Vec<Vec<Vec<u32>>> = self.empty_node_map();
+        let mut buckets: Vec<Vec<(usize, usize, usize)>> = Vec::new();
+
+        for (f, forest) in self.forests.iter().enumerate() {
+            for (t, tree) in forest.trees.iter().enumerate() {
+                let mut heights = vec![0usize; tree.len()];
+                for (u, node) in tree.nodes().iter().enumerate().rev() {
+                    if let Some((l, r)) = node.children {
+                        heights[u] = 1 + heights[l].max(heights[r]);
+                    }
+                    if buckets.len() <= heights[u] {
+                        buckets.resize_with(heights[u] + 1, Vec::new);
+                    }
+                    buckets[heights[u]].push((f, t, u));
+                }
+            }
+        }
+
+        let mut offset = 0;
+        for bucket in buckets {
+            let signatures: Vec<_> = bucket
+                .iter()
+                .map(|&(f, t, u)| {
+                    let node = self.forests[f].trees[t].node(u);
+                    match node.children {
+                        Some((l, r)) => {
+                            let (a, b) = (result[f][t][l], result[f][t][r]);
+                            vec![1, a.min(b), a.max(b)]
+                        }
+                        None => {
+                            let label = node.label.map_or(0, |Label(l)| l as usize);
+                            vec![0, colors[f].get(label).copied().unwrap_or(u32::MAX)]
+                        }
+                    }
+                })
+                .collect();
+
+            let (ranks, next) = self.rank(&signatures, offset);
+            for ((f, t, u), rank) in bucket.into_iter().zip(ranks) {
+                result[f][t][u] = rank;
+            }
+            offset = next;
+        }
+
+        result
+    }
+
+    /// Colors all nodes top-down (by depth), such that the color of a node describes its own
+    /// subtree and the path to the root including all subtrees hanging off this path.
+    fn contexts(&mut self, node_colors: &[Vec<Vec<u32>>]) -> Vec<Vec<Vec<u32>>> {
+        let mut result = self.empty_node_map();
+        let mut buckets: Vec<Vec<(usize, usize, usize)>> = Vec::new();
+
+        for (f, forest) in self.forests.iter().enumerate() {
+            for (t, tree) in forest.trees.iter().enumerate() {
+                for (u, node) in tree.nodes().iter().enumerate() {
+                    if buckets.len() <= node.depth {
+                        buckets.resize_with(node.depth + 1, Vec::new);
+                    }
+                    buckets[node.depth].push((f, t, u));
+                }
+            }
+        }
+
+        let mut offset = 0;
+        for bucket in buckets {
+            let signatures: Vec<_> = bucket
+                .iter()
+                .map(|&(f, t, u)| {
+                    let tree = &self.forests[f].trees[t];
+                    let colors = &node_colors[f][t];
+                    match tree.node(u).parent {
+                        Some(p) => {
+                            let (l, r) = tree.node(p).children.unwrap();
+                            let sibling = if l == u { r } else { l };
+                            vec![result[f][t][p], colors[u], colors[sibling]]
+                        }
+                        None => vec![colors[u]],
+                    }
+                })
+                .collect();
+
+            let (ranks, next) = self.rank(&signatures, offset);
+            for ((f, t, u), rank) in bucket.into_iter().zip(ranks) {
+                result[f][t][u] = rank;
+            }
+            offset = next;
+        }
+
+        result
+    }
+
+    /// Assigns the rank `offset + i` to all signatures equal to the `i`-th smallest distinct
+    /// signature. Returns the ranks and the next unused rank.
+    fn rank(&mut self, signatures: &[Vec<u32>], offset: u32) -> (Vec<u32>, u32) {
+        let mut order: Vec<usize> = (0..signatures.len()).collect();
+        order.sort_unstable_by(|&a, &b| signatures[a].cmp(&signatures[b]));
+
+        let mut ranks = vec![0; signatures.len()];
+        let mut next = offset;
+        for group in order.chunk_by(|&a, &b| signatures[a] == signatures[b]) {
+            for &i in group {
+                ranks[i] = next;
+            }
+            next += 1;
+        }
+
+        (ranks, next)
+    }
+
+    fn empty_node_map(&self) -> Vec<Vec<Vec<u32>>> {
+        self.forests
+            .iter()
+            .map(|f| f.trees.iter().map(|t| vec![0; t.len()]).collect())
+            .collect()
+    }
+
+    /// Refines the joint coloring of two forests and, while some color is shared by several
+    /// leaves, individualizes a leaf of the first forest together with each candidate of the
+    /// second one in turn.
+    fn search(
+        &mut self,
+        mut colors: Vec<Colors>,
+        target: &[SubtreeHash],
+    ) -> Option<LeafRelabeling> {
+        self.refine(&mut colors);
+
+        let histogram = |c: &Colors| {
+            let mut sorted = c[1..].to_vec();
+            sorted.sort_unstable();
+            sorted
+        };
+        if histogram(&colors[0]) != histogram(&colors[1]) {
+            return None;
+        }
+
+        let Some(color) = target_class(&colors[0]) else {
+            // the coloring is discrete, so there is only a single candidate
+            let by_color: HashMap<u32, usize> = colors[1]
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(l, &c)| (c, l))
+                .collect();
+            let mut map = vec![Label(0); colors[0].len()];
+            for (l, c) in colors[0].iter().enumerate().skip(1) {
+                map[l] = Label(by_color[c] as u32);
+            }
+
+            let relabeled = self.forests[0]
+                .sorted_root_hashes(|Label(l)| map.get(l as usize).copied().unwrap_or(Label(l)));
+            return (relabeled == target).then_some(LeafRelabeling { map });
+        };
+
+        let fresh = colors.iter().flatten().max().copied().unwrap_or(0) + 1;
+        let leaf_a = colors[0].iter().skip(1).position(|&c| c == color)? + 1;
+        let candidates: Vec<_> = (1..colors[1].len())
+            .filter(|&l| colors[1][l] == color)
+            .collect();
+
+        for leaf_b in candidates {
+            let mut individualized = colors.clone();
+            individualized[0][leaf_a] = fresh;
+            individualized[1][leaf_b] = fresh;
+            if let Some(relabeling) = self.search(individualized, target) {
+                return Some(relabeling);
+            }
+        }
+
+        None
+    }
+}
+
+/// Individualization-refinement search for a canonical labeling of a single forest. Each leaf
+/// of the search tree (i.e. a discrete coloring) yields a labeling; the smallest form obtained
+/// this way is canonical, since the search tree does not depend on the original labels.
+struct Canonizer<'a> {
+    refiner: Refiner<'a>,
+
+    /// Forms found so far, each with the first labeling (indexed by leaf) that produced it and
+    /// the individualized leaves leading to it
+    forms: HashMap<Vec<SubtreeHash>, (Vec<Label>, Vec<usize>)>,
+
+    /// Automorphisms of the forest (indexed by leaf) found by reaching some form twice
+    automorphisms: Vec<Vec<usize>>,
+}
+
+impl Canonizer<'_> {
+    /// Refines `colors` and individualizes each leaf of the target class in turn, unless it
+    /// is mapped onto an explored one by an automorphism fixing all leaves of `prefix`.
+    ///
+    /// Returns the length of a prefix if the search found that all remaining branches below it
+    /// are equivalent to explored ones and should backtrack to there.
+    fn search(&mut self, mut colors: Colors, prefix: &mut Vec<usize>) -> Option<usize> {
+        self.refiner.refine(std::slice::from_mut(&mut colors));
+
+        let Some(color) = target_class(&colors) else {
+            return self.visit_labeling(canonical_labeling(&colors), prefix);
+        };
+
+        let fresh = colors.iter().max().copied().unwrap_or(0) + 1;
+        let candidates: Vec<_> = (1..colors.len()).filter(|&l| colors[l] == color).collect();
+        let mut explored = Vec::new();
+
+        for leaf in candidates {
+            if self.is_equivalent_to_any(leaf, &explored, prefix) {
+                continue;
+            }
+
+            let mut individualized = colors.clone();
+            individualized[leaf] = fresh;
+            prefix.push(leaf);
+            let backtrack = self.search(individualized, prefix);
+            prefix.pop();
+            explored.push(leaf);
+
+            if backtrack.is_some_and(|len| len < prefix.len()) {
+                return backtrack;
+            }
+        }
+
+        None
+    }
+
+    /// Records the form obtained by `labeling`. If it was found before, the automorphism
+    /// mapping this path onto the earlier one also maps the subtree below their common prefix
+    /// onto an explored one, so the search may backtrack to the common prefix.
+    fn visit_labeling(&mut self, labeling: Vec<Label>, prefix: &[usize]) -> Option<usize> {
+        let form = self.refiner.forests[0]
+            .sorted_root_hashes(|Label(l)| labeling.get(l as usize).copied().unwrap_or(Label(l)));
+
+        let Some((first_labeling, first_prefix)) = self.forms.get(&form) else {
+            self.forms.insert(form, (labeling, prefix.to_vec()));
+            return None;
+        };
+
+        // both labelings yield the same forest, so they differ by an automorphism
+        let mut inverse = vec![0; first_labeling.len()];
+        for (l, &Label(c)) in first_labeling.iter().enumerate() {
+            inverse[c as usize] = l;
+        }
+        let automorphism = labeling.iter().map(|&Label(c)| inverse[c as usize]);
+        self.automorphisms.push(automorphism.collect());
+
+        let common = prefix.iter().zip(first_prefix).take_while(|(a, b)| a == b);
+        Some(common.count())
+    }
+
+    /// Whether an automorphism generated by those found so far that fix all leaves in
+    /// `prefix` maps `leaf` onto one of `explored`
+    fn is_equivalent_to_any(&self, leaf: usize, explored: &[usize], prefix: &[usize]) -> bool {
+        if explored.is_empty() {
+            return false;
+        }
+
+        let mut orbits: Vec<usize> = (0..self.refiner.forests[0].num_leaves + 1).collect();
+        fn find(orbits: &mut [usize], mut u: usize) -> usize {
+            while orbits[u] != u {
+                orbits[u] = orbits[orbits[u]];
+                u = orbits[u];
+            }
+            u
+        }
+
+        for automorphism in &self.automorphisms {
+            if prefix.iter().any(|&l| automorphism[l] != l) {
+                continue;
+            }
+            for (l, &m) in automorphism.iter().enumerate() {
+                let (a, b) = (find(&mut orbits, l), find(&mut orbits, m));
+                orbits[a] = b;
+            }
+        }
+
+        let orbit = find(&mut orbits, leaf);
+        explored.iter().any(|&l| find(&mut orbits, l) == orbit)
+    }
+}
+
+/// Smallest class of leaves (by size, then color) containing more than one leaf, if any
+fn target_class(colors: &Colors) -> Option<u32> {
+    let mut class_sizes: BTreeMap<u32, usize> = BTreeMap::new();
+    for &c in &colors[1..] {
+        *class_sizes.entry(c).or_default() += 1;
+    }
+
+    class_sizes
+        .into_iter()
+        .filter(|&(_, size)| size > 1)
+        .min_by_key(|&(color, size)| (size, color))
+        .map(|(color, _)| color)
+}
+
+/// Labels the leaves of a discrete coloring `1..=n` in the order of their colors
+fn canonical_labeling(colors: &Colors) -> Vec<Label> {
+    let mut leaves: Vec<usize> = (1..colors.len()).collect();
+    leaves.sort_unstable_by_key(|&l| colors[l]);
+
+    let mut labeling = vec![Label(0); colors.len()];
+    for (rank, l) in leaves.into_iter().enumerate() {
+        labeling[l] = Label(rank as u32 + 1);
+    }
+    labeling
+}
+
+fn count_classes(colors: &[Colors]) -> usize {
+    let mut all: Vec<u32> = colors.iter().flat_map(|c| c[1..].iter().copied()).collect();
+    all.sort_unstable();
+    all.dedup();
+    all.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(text: &str) -> Instance {
+        Instance::read_from(text.as_bytes(), false).ok().unwrap()
+    }
+
+    /// Applies `permutation` (of labels 1..=n) to all labels in the Newick strings
+    fn relabel(text: &str, permutation: &[u32]) -> String {
+        let mut result = String::new();
+        for line in text.lines() {
+            if line.starts_with('#') {
+                result.push_str(line);
+            } else {
+                let mut number = String::new();
+                for c in line.chars().chain(std::iter::once('\n')) {
+                    if c.is_ascii_digit() {
+                        number.push(c);
+                        continue;
+                    }
+                    if !number.is_empty() {
+                        let l: usize = number.parse().unwrap();
+                        result.push_str(&permutation[l - 1].to_string());
+                        number.clear();
+                    }
+                    if c != '\n' {
+                        result.push(c);
+                    }
+                }
+            }
+            result.push('\n');
+        }
+        result
+    }
+
+    const INSTANCE: &str = "#p 3 8\n(((1,2),(3,4)),((5,6),(7,8)));\n((((1,3),2),4),(5,(6,(7,8))));\n(((8,1),(2,7)),((3,6),(4,5)));\n";
+
+    #[test]
+    fn relabeling_invariant() {
+        let original = instance(INSTANCE);
+        let permutation = [5, 3, 8, 1, 2, 7, 4, 6];
+        let permuted = relabel(INSTANCE, &permutation);
+        let reordered = {
+            let lines: Vec<_> = permuted.lines().collect();
+            format!("{}\n{}\n{}\n{}\n", lines[0], lines[3], lines[1], lines[2])
+        };
+        let permuted = instance(&reordered);
+
+        assert_eq!(
+            fingerprint_instance(&original),
+            fingerprint_instance(&permuted)
+        );
+        assert_eq!(
+            fingerprint_instance(&original).size_class().num_leaves(),
+            0..=15
+        );
+
+        let relabeling = find_leaf_relabeling(&original, &permuted).unwrap();
+        for (a, b) in relabeling.iter() {
+            assert_eq!(b.0, permutation[a.0 as usize - 1]);
+        }
+        assert_eq!(relabeling.get(Label(0)), None);
+        assert_eq!(relabeling.get(Label(9)), None);
+    }
+
+    #[test]
+    fn different_instances() {
+        let original = instance(INSTANCE);
+        let other = instance(&INSTANCE.replace("((3,6),(4,5))", "(3,(6,(4,5)))"));
+
+        // swapping 1 and 2 in a single tree is equivalent to swapping 7 and 8 in all trees
+        let swapped = instance(&INSTANCE.replace("(((8,1),(2,7))", "(((8,2),(1,7))"));
+        assert_eq!(
+            fingerprint_instance(&original),
+            fingerprint_instance(&swapped)
+        );
+        let relabeling = find_leaf_relabeling(&original, &swapped).unwrap();
+        assert_eq!(relabeling.get(Label(7)), Some(Label(8)));
+        assert_eq!(relabeling.get(Label(1)), Some(Label(1)));
+
+        assert_ne!(
+            fingerprint_instance(&original),
+            fingerprint_instance(&other)
+        );
+        assert!(find_leaf_relabeling(&original, &other).is_none());
+
+        let fewer_trees =
+            instance("#p 2 8\n(((1,2),(3,4)),((5,6),(7,8)));\n((((1,3),2),4),(5,(6,(7,8))));\n");
+        assert!(find_leaf_relabeling(&original, &fewer_trees).is_none());
+    }
+
+    #[test]
+    fn testcases() {
+        for (path, _) in crate::io::tests::test_instances("valid") {
+            let instance = Instance::read(&path, false).ok().unwrap();
+            let fingerprint = fingerprint_instance(&instance);
+            assert_eq!(
+                fingerprint.size_class(),
+                SizeClass::new(instance.num_trees(), instance.num_leaves())
+            );
+            assert!(find_leaf_relabeling(&instance, &instance).is_some());
+        }
+    }
+
+    #[test]
+    fn symmetric_instances() {
+        // all leaves are indistinguishable by refinement, so the search needs to backtrack
+        let a = instance("#p 2 4\n((1,2),(3,4));\n((1,3),(2,4));\n");
+        let b = instance("#p 2 4\n((1,4),(2,3));\n((1,2),(3,4));\n");
+        let c = instance("#p 2 4\n((1,2),(3,4));\n((1,2),(3,4));\n");
+
+        assert_eq!(fingerprint_instance(&a), fingerprint_instance(&b));
+
+        let relabeling = find_leaf_relabeling(&a, &b).unwrap();
+        assert_eq!(
+            Forest::new(&a).sorted_root_hashes(|l| relabeling.get(l).unwrap()),
+            Forest::new(&b).sorted_root_hashes(|l| l)
+        );
+
+        // refinement alone cannot distinguish `a` and `c`, but their canonical forms differ
+        assert_ne!(fingerprint_instance(&a), fingerprint_instance(&c));
+        assert!(find_leaf_relabeling(&a, &c).is_none());
+    }
+
+    #[test]
+    fn highly_symmetric_instance() {
+        // a complete binary tree on 64 leaves has 2^63 automorphisms, which need to be pruned
+        fn complete(labels: &[u32]) -> String {
+            match labels {
+                [l] => l.to_string(),
+                _ => {
+                    let (left, right) = labels.split_at(labels.len() / 2);
+                    format!("({},{})", complete(left), complete(right))
+                }
+            }
+        }
+
+        let n = 64;
+        let labels: Vec<u32> = (1..=n).collect();
+        let text = format!("#p 2 {n}\n{0};\n{0};\n", complete(&labels));
+        let permutation: Vec<u32> = (1..=n).map(|l| (l * 97) % n + 1).collect();
+
+        assert_eq!(
+            fingerprint_instance(&instance(&text)),
+            fingerprint_instance(&instance(&relabel(&text, &permutation)))
+        );
+    }
+}
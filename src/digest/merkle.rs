@@ -0,0 +1,210 @@
+use crate::checks::{bin_tree_with_parent::NodeCursor, flat_tree::FlatTree};
+use digest::Output;
+use pace26io::binary_tree::Label;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Hash of a subtree; see [`MerkleTree`]
+pub type SubtreeHash = Output<Sha256>;
+
+// domain separation, so that no leaf can collide with an inner node
+const LEAF_TAG: u8 = 0;
+const INNER_TAG: u8 = 1;
+
+/// Bottom-up (Merkle) hashes of all subtrees of a binary tree. The hash of a leaf depends
+/// only on its label, the hash of an inner node only on the unordered pair of its
+/// children's hashes. Hence, two subtrees have the same hash iff they are identical up to
+/// the order of children (barring collisions).
+///
+/// In contrast to the Newick-based digests of [`DigestVersion::V0`] and
+/// [`DigestVersion::V1`], the children need not be ordered, and the computation takes
+/// linear time without recursion.
+///
+/// [`DigestVersion::V0`]: super::version::DigestVersion::V0
+/// [`DigestVersion::V1`]: super::version::DigestVersion::V1
+///
+/// # Example
+/// ```
+/// use pace26checker::checks::{bin_tree_with_parent::BinTreeWithParentBuilder, flat_tree::FlatTree};
+/// use pace26checker::digest::merkle::MerkleTree;
+/// use pace26io::{binary_tree::NodeIdx, newick::BinaryTreeParser};
+///
+/// let parse = |nw| BinTreeWithParentBuilder::default()
+///     .parse_newick_from_str(nw, NodeIdx::default())
+///     .unwrap();
+///
+/// let a = MerkleTree::new(&FlatTree::new(&parse("((1,2),(3,4));")));
+/// let b = MerkleTree::new(&FlatTree::new(&parse("((4,3),(2,1));")));
+/// assert_eq!(a.root_hash(), b.root_hash());
+/// ```
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    hashes: Vec<SubtreeHash>,
+    parents: Vec<Option<usize>>,
+}
+
+impl MerkleTree {
+    pub fn new(tree: &FlatTree) -> Self {
+        Self::new_relabeled(tree, |label| label)
+    }
+
+    /// Computes the hashes of `tree` after replacing each leaf label `l` by `relabel(l)`
+    /// (without modifying the tree).
+    pub fn new_relabeled(tree: &FlatTree, relabel: impl Fn(Label) -> Label) -> Self {
+        let mut hashes = vec![SubtreeHash::default(); tree.len()];
+
+        // children have larger indices than their parents, so a reverse scan is bottom-up
+        for (u, node) in tree.nodes().iter().enumerate().rev() {
+            let mut algo = Sha256::new();
+            match (node.children, node.label) {
+                (Some((left, right)), _) => {
+                    let (a, b) = if hashes[left] <= hashes[right] {
+                        (left, right)
+                    } else {
+                        (right, left)
+                    };
+                    algo.update([INNER_TAG]);
+                    algo.update(hashes[a]);
+                    algo.update(hashes[b]);
+                }
+                (None, Some(label)) => {
+                    let Label(label) = relabel(label);
+                    algo.update([LEAF_TAG]);
+                    algo.update(label.to_le_bytes());
+                }
+                // each node of a `FlatTree` is either inner or a leaf
+                (None, None) => {}
+            }
+            hashes[u] = algo.finalize();
+        }
+
+        Self {
+            hashes,
+            parents: tree.nodes().iter().map(|n| n.parent).collect(),
+        }
+    }
+
+    /// Hash of the whole tree
+    pub fn root_hash(&self) -> &SubtreeHash {
+        &self.hashes[0]
+    }
+
+    /// Hash of the subtree rooted in node `u` of the [`FlatTree`] this was computed from
+    pub fn hash(&self, u: usize) -> &SubtreeHash {
+        &self.hashes[u]
+    }
+
+    /// Hashes of all subtrees, indexed like the nodes of the [`FlatTree`]
+    pub fn hashes(&self) -> &[SubtreeHash] {
+        &self.hashes
+    }
+
+    /// Returns all maximal subtrees that also occur in `other` as pairs `(u, v)`, where `u`
+    /// is a node of `self` and `v` a node of `other` rooting an identical subtree (up to the
+    /// order of children). Subtrees nested in a reported one are omitted. Pairs are returned
+    /// in pre-order of `self`.
+    pub fn common_subtrees(&self, other: &MerkleTree) -> Vec<(usize, usize)> {
+        let lookup: HashMap<&SubtreeHash, usize> = other
+            .hashes
+            .iter()
+            .enumerate()
+            .map(|(v, h)| (h, v))
+            .collect();
+
+        // in pre-order, each parent is visited before its children
+        let mut matched = vec![false; self.hashes.len()];
+        let mut subtrees = Vec::new();
+        for (u, hash) in self.hashes.iter().enumerate() {
+            let Some(&v) = lookup.get(hash) else {
+                continue;
+            };
+            matched[u] = true;
+            if !self.parents[u].is_some_and(|p| matched[p]) {
+                subtrees.push((u, v));
+            }
+        }
+
+        subtrees
+    }
+}
+
+/// Computes the Merkle hash of a binary tree without modifying it; see [`MerkleTree`].
+pub fn digest_bintree_merkle(tree: &NodeCursor) -> SubtreeHash {
+    *MerkleTree::new(&FlatTree::new(tree)).root_hash()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::bin_tree_with_parent::BinTreeWithParentBuilder;
+    use pace26io::{binary_tree::NodeIdx, newick::*};
+
+    fn parse_tree(nw: &str) -> NodeCursor {
+        BinTreeWithParentBuilder::default()
+            .parse_newick_from_str(nw, NodeIdx::default())
+            .unwrap()
+    }
+
+    fn merkle(nw: &str) -> MerkleTree {
+        MerkleTree::new(&FlatTree::new(&parse_tree(nw)))
+    }
+
+    #[test]
+    fn child_order_invariant() {
+        let equivalent = ["((1,2),(3,4));", "((4,3),(1,2));", "((3,4),(2,1));"];
+        for nw in equivalent {
+            assert_eq!(merkle(nw).root_hash(), merkle(equivalent[0]).root_hash());
+        }
+
+        let different = [
+            "((1,3),(2,4));",
+            "(1,(2,(3,4)));",
+            "((1,2),(3,5));",
+            "(1,2);",
+        ];
+        for nw in different {
+            assert_ne!(merkle(nw).root_hash(), merkle(equivalent[0]).root_hash());
+        }
+
+        assert_ne!(merkle("1;").root_hash(), merkle("2;").root_hash());
+    }
+
+    #[test]
+    fn does_not_modify_tree() {
+        let tree = parse_tree("((4,3),(2,1));");
+        let hash = digest_bintree_merkle(&tree);
+        assert_eq!(tree.top_down().to_newick_string(), "((4,3),(2,1));");
+        assert_eq!(&hash, merkle("((1,2),(3,4));").root_hash());
+    }
+
+    #[test]
+    fn subtree_hashes() {
+        let tree = merkle("((1,2),(3,(4,5)));");
+        assert_eq!(tree.hashes().len(), 9);
+        assert_eq!(tree.hash(0), tree.root_hash());
+        assert_eq!(tree.hash(1), merkle("(2,1);").root_hash());
+        assert_eq!(tree.hash(5), merkle("3;").root_hash());
+        assert_eq!(tree.hash(6), merkle("(4,5);").root_hash());
+    }
+
+    #[test]
+    fn relabeled() {
+        let tree = FlatTree::new(&parse_tree("((1,2),(3,4));"));
+        let swapped = MerkleTree::new_relabeled(&tree, |Label(l)| Label(5 - l));
+        assert_eq!(swapped.root_hash(), merkle("((4,3),(2,1));").root_hash());
+
+        let moved = MerkleTree::new_relabeled(&tree, |Label(l)| Label(l % 4 + 1));
+        assert_eq!(moved.root_hash(), merkle("((2,3),(4,1));").root_hash());
+        assert_ne!(moved.root_hash(), merkle("((1,2),(3,4));").root_hash());
+    }
+
+    #[test]
+    fn common_subtrees() {
+        let a = merkle("((1,2),(3,(4,5)));");
+        let b = merkle("(((5,4),6),((2,1),3));");
+
+        // (1,2) and (4,5) are maximal; 3 is a pendant leaf of both trees
+        assert_eq!(a.common_subtrees(&b), vec![(1, 7), (5, 10), (6, 2)]);
+        assert_eq!(b.common_subtrees(&b), vec![(0, 0)]);
+    }
+}
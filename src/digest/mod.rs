@@ -1,2 +1,6 @@
 pub mod algo;
 pub mod digest_output;
+pub mod file;
+pub mod fingerprint;
+pub mod merkle;
+pub mod version;
@@ -0,0 +1,222 @@
+use crate::digest::digest_output::{
+    BoundSolutionDigest, DIGEST_HEX_DIGITS, DigestError, InstanceDigest, SolutionDigest,
+};
+use serde::de::{Error, Unexpected};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+
+/// Version of the digest scheme. Digests computed with different versions are
+/// incomparable; hence the version is part of the textual encoding of a [`Versioned`]
+/// digest (except for the legacy version [`DigestVersion::V0`]).
+#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum DigestVersion {
+    /// Legacy scheme without version prefix. The approx parameter `a` is encoded as
+    /// `(1000 * a) as i32`.
+    V0,
+
+    /// The approx parameter `a` is encoded by its shortest decimal representation that
+    /// round-trips, i.e. exactly.
+    V1,
+
+    /// As [`DigestVersion::V1`], but trees are hashed bottom-up by a [`MerkleTree`] instead
+    /// of their normalized Newick string.
+    ///
+    /// [`MerkleTree`]: super::merkle::MerkleTree
+    V2,
+}
+
+impl DigestVersion {
+    pub const LATEST: Self = DigestVersion::V2;
+
+    pub fn id(self) -> u8 {
+        match self {
+            DigestVersion::V0 => 0,
+            DigestVersion::V1 => 1,
+            DigestVersion::V2 => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, DigestError> {
+        match id {
+            0 => Ok(DigestVersion::V0),
+            1 => Ok(DigestVersion::V1),
+            2 => Ok(DigestVersion::V2),
+            _ => Err(DigestError::UnsupportedVersion(id)),
+        }
+    }
+
+    /// Number of hex digits in the textual encoding of a digest of this version
+    pub fn hex_digits(self) -> usize {
+        match self {
+            DigestVersion::V0 => DIGEST_HEX_DIGITS,
+            _ => DIGEST_HEX_DIGITS + 2,
+        }
+    }
+}
+
+/// A digest together with the version of the scheme used to compute it.
+///
+/// Digests of version [`DigestVersion::V0`] are encoded as [`DIGEST_HEX_DIGITS`] hex digits
+/// (compatible with the unversioned digests); all later versions are prefixed by two hex
+/// digits encoding the version id.
+///
+/// # Example
+/// ```
+/// use pace26checker::digest::version::*;
+///
+/// let legacy = VersionedInstanceDigest::try_from("0010b172a28d0664d5521e1296fc3586").unwrap();
+/// assert_eq!(legacy.version(), DigestVersion::V0);
+///
+/// let current = VersionedInstanceDigest::try_from("010010b172a28d0664d5521e1296fc3586").unwrap();
+/// assert_eq!(current.version(), DigestVersion::V1);
+/// assert_eq!(current.digest(), legacy.digest());
+/// assert_ne!(current, legacy);
+/// ```
+#[derive(Clone, Copy, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct Versioned<D> {
+    version: DigestVersion,
+    digest: D,
+}
+
+pub type VersionedInstanceDigest = Versioned<InstanceDigest>;
+pub type VersionedSolutionDigest = Versioned<SolutionDigest>;
+pub type VersionedBoundSolutionDigest = Versioned<BoundSolutionDigest>;
+
+impl<D> Versioned<D> {
+    pub fn new(version: DigestVersion, digest: D) -> Self {
+        Self { version, digest }
+    }
+
+    pub fn version(&self) -> DigestVersion {
+        self.version
+    }
+
+    pub fn digest(&self) -> &D {
+        &self.digest
+    }
+}
+
+impl<D: Display> Display for Versioned<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.version != DigestVersion::V0 {
+            write!(f, "{:02x}", self.version.id())?;
+        }
+        write!(f, "{}", self.digest)
+    }
+}
+
+impl<'a, D: TryFrom<&'a str, Error = DigestError>> TryFrom<&'a str> for Versioned<D> {
+    type Error = DigestError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        if value.len() == DIGEST_HEX_DIGITS {
+            return Ok(Self::new(DigestVersion::V0, D::try_from(value)?));
+        }
+
+        if value.len() != DIGEST_HEX_DIGITS + 2 || !value.is_char_boundary(2) {
+            return Err(DigestError::InvalidLength);
+        }
+
+        let (prefix, digest) = value.split_at(2);
+        let id = u8::from_str_radix(prefix, 16).map_err(|_| {
+            DigestError::InvalidChar(
+                prefix
+                    .chars()
+                    .find(|c| !c.is_ascii_hexdigit())
+                    .unwrap_or('+'),
+            )
+        })?;
+
+        // legacy digests carry no prefix, so an explicit version 0 is not a valid encoding
+        let version = match DigestVersion::from_id(id)? {
+            DigestVersion::V0 => return Err(DigestError::InvalidLength),
+            v => v,
+        };
+
+        Ok(Self::new(version, D::try_from(digest)?))
+    }
+}
+
+impl<D: Display> Serialize for Versioned<D> {
+    fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        ser.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de, D> Deserialize<'de> for Versioned<D>
+where
+    D: for<'a> TryFrom<&'a str, Error = DigestError>,
+{
+    fn deserialize<De>(de: De) -> Result<Self, De::Error>
+    where
+        De: Deserializer<'de>,
+    {
+        let s = String::deserialize(de)?;
+        Self::try_from(s.as_str())
+            .map_err(|e| De::Error::invalid_value(Unexpected::Str(&s), &e.to_string().as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEX: &str = "0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn parse_and_display() {
+        let v0 = VersionedInstanceDigest::try_from(HEX).unwrap();
+        assert_eq!(v0.version(), DigestVersion::V0);
+        assert_eq!(v0.to_string(), HEX);
+
+        let prefixed = format!("01{HEX}");
+        let v1 = VersionedInstanceDigest::try_from(prefixed.as_str()).unwrap();
+        assert_eq!(v1.version(), DigestVersion::V1);
+        assert_eq!(v1.digest(), v0.digest());
+        assert_eq!(v1.to_string(), prefixed);
+
+        assert_eq!(
+            VersionedSolutionDigest::try_from(format!("00{HEX}").as_str()),
+            Err(DigestError::InvalidLength)
+        );
+        assert_eq!(
+            VersionedSolutionDigest::try_from(format!("7f{HEX}").as_str()),
+            Err(DigestError::UnsupportedVersion(0x7f))
+        );
+        assert_eq!(
+            VersionedSolutionDigest::try_from(format!("x1{HEX}").as_str()),
+            Err(DigestError::InvalidChar('x'))
+        );
+        assert_eq!(
+            VersionedSolutionDigest::try_from(&HEX[1..]),
+            Err(DigestError::InvalidLength)
+        );
+        assert!(VersionedSolutionDigest::try_from(format!("\u{e9}{HEX}").as_str()).is_err());
+    }
+
+    #[test]
+    fn serde() {
+        for text in [HEX.to_string(), format!("01{HEX}")] {
+            let digest = VersionedSolutionDigest::try_from(text.as_str()).unwrap();
+            let json = serde_json::to_string(&digest).unwrap();
+            assert_eq!(json, format!("\"{text}\""));
+            assert_eq!(
+                serde_json::from_str::<VersionedSolutionDigest>(&json).unwrap(),
+                digest
+            );
+        }
+
+        assert!(serde_json::from_str::<VersionedSolutionDigest>("\"ff\"").is_err());
+    }
+
+    #[test]
+    fn version_ids() {
+        for version in [DigestVersion::V0, DigestVersion::V1, DigestVersion::V2] {
+            assert_eq!(DigestVersion::from_id(version.id()), Ok(version));
+        }
+        assert_eq!(DigestVersion::LATEST.hex_digits(), DIGEST_HEX_DIGITS + 2);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::{
+    checks::{bin_tree_with_parent::*, flat_tree::FlatTree},
+    io::{instance_reader::Instance, solution_reader::Solution},
+};
+use pace26io::binary_tree::*;
+use std::io::Write;
+use thiserror::Error;
+
+pub use super::forest_coloring::Palette;
+
+#[derive(Debug, Error)]
+pub enum ComponentDotWriterError {
+    #[error("Leaf {} of solution tree in line {} is missing in instance tree in line {}", label.0, sol_lineno + 1, inst_lineno + 1)]
+    MissingLeaf {
+        sol_lineno: usize,
+        inst_lineno: usize,
+        label: Label,
+    },
+
+    #[error("Solution tree in line {} cannot be embedded into instance tree in line {}", sol_lineno + 1, inst_lineno + 1)]
+    NotEmbeddable {
+        sol_lineno: usize,
+        inst_lineno: usize,
+    },
+
+    #[error("Solution tree in line {} contains an inner node without children", sol_lineno + 1)]
+    ChildlessNode { sol_lineno: usize },
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Produce a view of the agreement forest itself in GraphViz Dot format. In contrast to
+/// [`ForestDotWriter`](super::forest_dot_writer::ForestDotWriter), which draws the instance
+/// trees and marks the roots of the components, each component (i.e. solution tree) is drawn
+/// exactly once in its own cluster.
+///
+/// Each component is annotated with the node it is embedded at in every instance tree, i.e.
+/// the lowest common ancestor of its leaves; these are the nodes drawn as triangles by
+/// `ForestDotWriter`. Similarly, each inner node of a component is labeled with the ids of its
+/// embeddings in all instance trees, separated by slashes.
+///
+/// # Example
+/// ```
+/// use std::{path::PathBuf, io::stdout};
+/// use pace26checker::{checks::checker::check_instance_and_solution, io::component_dot_writer::*};
+///
+/// const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
+/// const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
+///
+/// let (instance, solution, _forests) = check_instance_and_solution(
+///         &PathBuf::from(PATH_INSTANCE),
+///         &PathBuf::from(PATH_SOLUTION),
+///         false,
+///         true,
+///     )
+///     .unwrap();
+///
+/// let mut writer = ComponentDotWriter::new(instance.as_ref().unwrap(), &solution);
+/// writer.set_palette(Palette::Generated);
+/// writer.write(&mut stdout().lock()).unwrap();
+/// ```
+pub struct ComponentDotWriter<'a> {
+    instance: &'a Instance,
+    solution: &'a Solution,
+    palette: Palette,
+}
+
+/// A component with the embeddings of its nodes into each instance tree
+struct EmbeddedComponent {
+    tree: FlatTree,
+    /// `embeddings[u][i]` is the node of the `i`-th instance tree that node `u` is embedded at
+    embeddings: Vec<Vec<NodeIdx>>,
+}
+
+impl<'a> ComponentDotWriter<'a> {
+    /// The solution should be feasible for the instance, e.g. as returned by
+    /// [`check_instance_and_solution`](crate::checks::checker::check_instance_and_solution).
+    /// Otherwise, writing fails if a solution tree has a leaf missing in an instance tree.
+    pub fn new(instance: &'a Instance, solution: &'a Solution) -> Self {
+        Self {
+            instance,
+            solution,
+            palette: Palette::default(),
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) -> &mut Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Returns for each component (in the order of the solution) the nodes it is embedded at
+    /// in the instance trees
+    pub fn embeddings(&self) -> Result<Vec<Vec<NodeIdx>>, ComponentDotWriterError> {
+        Ok(self
+            .embed()?
+            .into_iter()
+            .map(|mut component| component.embeddings.swap_remove(component.tree.root()))
+            .collect())
+    }
+
+    fn embed(&self) -> Result<Vec<EmbeddedComponent>, ComponentDotWriterError> {
+        let leaves: Vec<Vec<Option<NodeCursor>>> = self
+            .instance
+            .trees()
+            .iter()
+            .map(|(_, tree)| {
+                let mut leaves = vec![None; 1 + self.instance.num_leaves() as usize];
+                for u in tree.top_down().dfs() {
+                    if let Some(Label(l)) = u.leaf_label() {
+                        leaves[l as usize] = Some(u);
+                    }
+                }
+                leaves
+            })
+            .collect();
+
+        self.solution
+            .trees()
+            .iter()
+            .map(|&(sol_lineno, ref component)| {
+                // we draw a normalized copy, so the layout does not depend on the solution file
+                let ordered = component.clone_and_rebuild();
+                ordered.normalize_child_order();
+                let tree = FlatTree::new(&ordered);
+
+                let inst_linenos = self.instance.trees().iter().map(|&(l, _)| l);
+                let mut cursors: Vec<Vec<NodeCursor>> = vec![Vec::new(); tree.len()];
+                for u in (0..tree.len()).rev() {
+                    let node = tree.node(u);
+                    cursors[u] = match (node.label, node.children) {
+                        (Some(label), _) => leaves
+                            .iter()
+                            .zip(inst_linenos.clone())
+                            .map(|(t, inst_lineno)| {
+                                t.get(label.0 as usize).cloned().flatten().ok_or(
+                                    ComponentDotWriterError::MissingLeaf {
+                                        sol_lineno,
+                                        inst_lineno,
+                                        label,
+                                    },
+                                )
+                            })
+                            .collect::<Result<_, _>>()?,
+                        (None, Some((l, r))) => cursors[l]
+                            .iter()
+                            .zip(&cursors[r])
+                            .zip(inst_linenos.clone())
+                            .map(|((a, b), inst_lineno)| {
+                                NodeCursor::lowest_common_ancestor(a.clone(), b.clone()).ok_or(
+                                    ComponentDotWriterError::NotEmbeddable {
+                                        sol_lineno,
+                                        inst_lineno,
+                                    },
+                                )
+                            })
+                            .collect::<Result<_, _>>()?,
+                        (None, None) => {
+                            return Err(ComponentDotWriterError::ChildlessNode { sol_lineno });
+                        }
+                    };
+                }
+
+                let embeddings = cursors
+                    .iter()
+                    .map(|nodes| nodes.iter().map(|u| u.node_idx()).collect())
+                    .collect();
+
+                Ok(EmbeddedComponent { tree, embeddings })
+            })
+            .collect()
+    }
+
+    /// Writes the Dot representation; neither the instance nor the solution is modified
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), ComponentDotWriterError> {
+        let components = self.embed()?;
+        writeln!(writer, "digraph Components {{")?;
+        if self.palette == Palette::Set19 {
+            writeln!(writer, " node [colorscheme=set19];")?;
+        }
+
+        for (k, component) in components.iter().enumerate() {
+            let color = self.palette.dot_color(2 + k as u32);
+            let tree = &component.tree;
+            let name = |u: usize| match tree.node(u).label {
+                Some(l) => format!("c{}l{}", k + 1, l.0),
+                None => format!("c{}v{}", k + 1, u),
+            };
+
+            let embedded_at: Vec<_> = component.embeddings[tree.root()]
+                .iter()
+                .enumerate()
+                .map(|(i, v)| format!("t{}: {}", i + 1, v.0))
+                .collect();
+
+            writeln!(writer, " subgraph cluster_c{} {{", k + 1)?;
+            writeln!(
+                writer,
+                "  label=\"Component {} ({})\";",
+                k + 1,
+                embedded_at.join(", ")
+            )?;
+
+            for (u, node) in tree.nodes().iter().enumerate() {
+                if let Some(l) = node.label {
+                    writeln!(
+                        writer,
+                        "  {} [label=\"{}\", color={color}, shape=\"box\"]",
+                        name(u),
+                        l.0
+                    )?;
+                } else {
+                    let ids: Vec<_> = component.embeddings[u]
+                        .iter()
+                        .map(|v| v.0.to_string())
+                        .collect();
+                    writeln!(
+                        writer,
+                        "  {}[label=\"{}\",color={color}]",
+                        name(u),
+                        ids.join("/")
+                    )?;
+                }
+
+                if let Some(p) = node.parent {
+                    writeln!(writer, "  {} -> {};", name(p), name(u))?;
+                }
+            }
+
+            writeln!(writer, " }}")?;
+        }
+
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::checks::checker::{check_instance_and_solution, check_instance_and_solution_from};
+    use std::{collections::HashSet, path::PathBuf};
+
+    const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
+    const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
+
+    fn render(writer: &ComponentDotWriter) -> String {
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn small() {
+        let (instance, solution, _) = check_instance_and_solution_from(
+            &b"#p 2 4\n((1,2),(3,4));\n(((1,2),3),4);\n"[..],
+            &b"((2,1),4);\n3;\n"[..],
+            false,
+            true,
+        )
+        .unwrap();
+        let instance = instance.unwrap();
+        let writer = ComponentDotWriter::new(&instance, &solution);
+
+        let root = |i: usize| instance.trees()[i].1.node_idx();
+        assert_eq!(
+            writer.embeddings().unwrap(),
+            vec![vec![root(0), root(1)], vec![NodeIdx(3), NodeIdx(3)]]
+        );
+
+        let dot = render(&writer);
+        assert_eq!(dot.matches("subgraph cluster_c").count(), 2);
+        assert!(dot.contains(&format!(
+            "label=\"Component 1 (t1: {}, t2: {})\";",
+            root(0).0,
+            root(1).0
+        )));
+        assert!(dot.contains("label=\"Component 2 (t1: 3, t2: 3)\";"));
+        assert!(dot.contains("c1l1 [label=\"1\""));
+        assert!(dot.contains("c2l3 [label=\"3\""));
+        assert_eq!(dot.matches(" -> ").count(), 4);
+    }
+
+    #[test]
+    fn instance_and_solution() {
+        let (instance, solution, forests) = check_instance_and_solution(
+            &PathBuf::from(PATH_INSTANCE),
+            &PathBuf::from(PATH_SOLUTION),
+            false,
+            true,
+        )
+        .unwrap();
+        let instance = instance.unwrap();
+
+        let mut writer = ComponentDotWriter::new(&instance, &solution);
+        writer.set_palette(Palette::Generated);
+
+        // components are embedded at roots of the forests obtained by the checker
+        let embeddings = writer.embeddings().unwrap();
+        assert_eq!(embeddings.len(), solution.num_trees());
+        for (i, forest) in forests.iter().enumerate() {
+            let roots: HashSet<_> = forest.roots().iter().map(|r| r.node_idx()).collect();
+            assert!(embeddings.iter().all(|e| roots.contains(&e[i])));
+        }
+
+        let dot = render(&writer);
+        assert!(!dot.contains("colorscheme"));
+        assert_eq!(
+            dot.matches("subgraph cluster_c").count(),
+            solution.num_trees()
+        );
+        assert_eq!(
+            dot.matches("shape=\"box\"").count(),
+            instance.num_leaves() as usize
+        );
+    }
+
+    #[test]
+    fn missing_leaf() {
+        let instance = Instance::read_from(&b"#p 1 3\n((1,2),3);\n"[..], false).unwrap();
+        let solution = Solution::read_from(&b"((1,2),(3,4));\n"[..], 4, false)
+            .ok()
+            .unwrap();
+        let writer = ComponentDotWriter::new(&instance, &solution);
+
+        assert!(matches!(
+            writer.embeddings(),
+            Err(ComponentDotWriterError::MissingLeaf {
+                sol_lineno: 0,
+                inst_lineno: 1,
+                label: Label(4)
+            })
+        ));
+        assert!(writer.write(&mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{
+    checks::{bin_forest::*, flat_tree::FlatTree},
+    io::{instance_reader::Instance, solution_reader::Solution},
+};
+use pace26io::binary_tree::*;
+use std::collections::HashSet;
+
+/// Color index of nodes that do not belong to a single component of the solution
+pub const MIXED_COLOR: u32 = 1;
+
+/// Assignment of the nodes of all instance trees to the components of a solution, shared by
+/// the forest writers. Color indices are [`MIXED_COLOR`] for nodes that do not belong to a
+/// single component and `2 + i` for nodes of the `i`-th solution tree. Without a solution
+/// (i.e. before [`ForestColoring::color_leafs`]), all nodes are mixed.
+#[derive(Debug, Clone)]
+pub struct ForestColoring {
+    colors: Vec<u32>,
+    roots: Vec<HashSet<NodeIdx>>,
+}
+
+impl ForestColoring {
+    pub fn new(instance: &Instance) -> Self {
+        let num_nodes = (1 + instance.num_trees() as usize)
+            * (instance.num_leaves() as usize).saturating_sub(1)
+            + 2;
+
+        Self {
+            colors: std::iter::repeat_n(MIXED_COLOR, num_nodes).collect(),
+            roots: Vec::with_capacity(instance.num_trees() as usize),
+        }
+    }
+
+    pub fn color_leafs(&mut self, instance: &Instance, solution: &Solution, forests: &[BinForest]) {
+        // color leaves using the index of the solution tree
+        for (i, (_, tree)) in solution.trees().iter().enumerate() {
+            for (_, l) in FlatTree::new(tree).leaves() {
+                if let Some(color) = self.colors.get_mut(l.0 as usize) {
+                    *color = 2 + i as u32;
+                }
+            }
+        }
+
+        self.roots.clear();
+        for (forest, (_, inst)) in forests.iter().zip(instance.trees()) {
+            let roots: HashSet<_> = forest.roots().iter().map(|c| c.node_idx()).collect();
+
+            // children are stored after their parents, so a reverse scan colors them first
+            let tree = FlatTree::new(inst);
+            let color_of = |colors: &[u32], u: usize| colors[tree.node(u).node_idx.0 as usize];
+            for u in (0..tree.len()).rev() {
+                let node = tree.node(u);
+                let Some((left, right)) = node.children else {
+                    continue;
+                };
+
+                let (l_color, r_color) =
+                    (color_of(&self.colors, left), color_of(&self.colors, right));
+                let l_root = roots.contains(&tree.node(left).node_idx);
+                let r_root = roots.contains(&tree.node(right).node_idx);
+
+                self.colors[node.node_idx.0 as usize] = if l_color == r_color || !l_root {
+                    l_color
+                } else if !r_root {
+                    r_color
+                } else {
+                    MIXED_COLOR
+                };
+            }
+
+            self.roots.push(roots);
+        }
+    }
+
+    /// Color index of a node (leaves have node index equal to their label)
+    pub fn color(&self, node: NodeIdx) -> u32 {
+        self.colors
+            .get(node.0 as usize)
+            .copied()
+            .unwrap_or(MIXED_COLOR)
+    }
+
+    /// Roots of the components within the `tree`-th instance tree (empty without solution)
+    pub fn roots(&self, tree: usize) -> HashSet<NodeIdx> {
+        self.roots.get(tree).cloned().unwrap_or_default()
+    }
+}
+
+/// Colors used for the components of a solution
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Palette {
+    /// Brewer color scheme `set19`; only the first 8 components receive distinct colors
+    #[default]
+    Set19,
+
+    /// Generated colors with evenly spread hues, suitable for hundreds of components
+    Generated,
+
+    /// Explicit colors (e.g. `"#1f77b4"` or `"navy"`); the first color is used for nodes not
+    /// belonging to a single component, the remaining ones are repeated cyclically
+    Custom(Vec<String>),
+}
+
+impl Palette {
+    /// Colors of the Brewer scheme `set19`
+    const SET19: [&'static str; 9] = [
+        "#e41a1c", "#377eb8", "#4daf4a", "#984ea3", "#ff7f00", "#ffff33", "#a65628", "#f781bf",
+        "#999999",
+    ];
+
+    /// GraphViz color attribute value of the color index `color`
+    pub(crate) fn dot_color(&self, color: u32) -> String {
+        match self {
+            Palette::Set19 => color.to_string(),
+            Palette::Generated => match Self::hsv(color) {
+                None => "gray40".to_string(),
+                Some((h, s, v)) => format!("\"{h:.3} {s:.3} {v:.3}\""),
+            },
+            Palette::Custom(colors) if colors.is_empty() => "black".to_string(),
+            Palette::Custom(_) => format!("\"{}\"", self.css_color(color)),
+        }
+    }
+
+    /// Color of the color index `color` as understood by SVG and HTML
+    pub fn css_color(&self, color: u32) -> String {
+        match self {
+            Palette::Set19 => {
+                Self::SET19[(color.max(1) as usize - 1) % Self::SET19.len()].to_string()
+            }
+            Palette::Generated => match Self::hsv(color) {
+                None => "#666666".to_string(),
+                Some((h, s, v)) => {
+                    let (r, g, b) = hsv_to_rgb(h, s, v);
+                    format!("#{r:02x}{g:02x}{b:02x}")
+                }
+            },
+            Palette::Custom(colors) => match colors.len() {
+                0 => "black".to_string(),
+                1 => colors[0].clone(),
+                n => {
+                    let index = if color < 2 {
+                        0
+                    } else {
+                        1 + (color as usize - 2) % (n - 1)
+                    };
+                    colors[index].clone()
+                }
+            },
+        }
+    }
+
+    fn hsv(color: u32) -> Option<(f64, f64, f64)> {
+        let i = color.checked_sub(2)?;
+        // consecutive components are separated by the golden angle
+        let hue = (i as f64 * 0.618_033_988_75).fract();
+        let (saturation, value) = [(0.75, 0.85), (0.55, 0.65), (0.9, 0.55)][(i % 3) as usize];
+        Some((hue, saturation, value))
+    }
+}
+
+fn hsv_to_rgb(h: f64, s: f64, v: f64) -> (u8, u8, u8) {
+    let sector = (h * 6.0).floor();
+    let f = h * 6.0 - sector;
+    let (p, q, t) = (v * (1.0 - s), v * (1.0 - f * s), v * (1.0 - (1.0 - f) * s));
+    let (r, g, b) = match sector as u32 % 6 {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    };
+    let channel = |x: f64| (x * 255.0).round() as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palettes() {
+        let generated: HashSet<_> = (2..302).map(|c| Palette::Generated.dot_color(c)).collect();
+        assert_eq!(generated.len(), 300);
+        let generated: HashSet<_> = (2..302).map(|c| Palette::Generated.css_color(c)).collect();
+        assert_eq!(generated.len(), 300);
+        assert_ne!(
+            Palette::Generated.dot_color(1),
+            Palette::Generated.dot_color(2)
+        );
+
+        let custom = Palette::Custom(vec!["gray".into(), "red".into(), "blue".into()]);
+        assert_eq!(custom.dot_color(1), "\"gray\"");
+        assert_eq!(custom.dot_color(2), "\"red\"");
+        assert_eq!(custom.dot_color(3), "\"blue\"");
+        assert_eq!(custom.dot_color(4), "\"red\"");
+        assert_eq!(custom.css_color(4), "red");
+
+        assert_eq!(Palette::Set19.dot_color(3), "3");
+        assert_eq!(Palette::Set19.css_color(1), "#e41a1c");
+        assert_eq!(Palette::Set19.css_color(10), "#e41a1c");
+    }
+
+    #[test]
+    fn hsv() {
+        assert_eq!(hsv_to_rgb(0.0, 1.0, 1.0), (255, 0, 0));
+        assert_eq!(hsv_to_rgb(1.0 / 3.0, 1.0, 1.0), (0, 255, 0));
+        assert_eq!(hsv_to_rgb(2.0 / 3.0, 1.0, 0.5), (0, 0, 128));
+        assert_eq!(hsv_to_rgb(0.5, 0.0, 1.0), (255, 255, 255));
+    }
+}
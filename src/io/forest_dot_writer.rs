@@ -1,11 +1,111 @@
 use crate::{
     checks::bin_forest::*,
-    checks::bin_tree_with_parent::*,
-    io::{instance_reader::Instance, solution_reader::Solution},
+    checks::flat_tree::FlatNode,
+    io::{forest_layout::TreeLayout, instance_reader::Instance, solution_reader::Solution},
 };
 use pace26io::binary_tree::*;
 use std::{collections::HashSet, io::Write};
 
+pub use super::forest_coloring::Palette;
+use super::forest_coloring::*;
+
+/// Direction in which the trees grow; see GraphViz' `rankdir`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RankDir {
+    #[default]
+    TopBottom,
+    BottomTop,
+    LeftRight,
+    RightLeft,
+}
+
+impl RankDir {
+    fn as_str(self) -> &'static str {
+        match self {
+            RankDir::TopBottom => "TB",
+            RankDir::BottomTop => "BT",
+            RankDir::LeftRight => "LR",
+            RankDir::RightLeft => "RL",
+        }
+    }
+}
+
+/// Options of [`ForestDotWriter`]. The default reproduces the original output.
+///
+/// # Example
+/// ```
+/// use pace26checker::io::forest_dot_writer::*;
+/// use pace26io::binary_tree::Label;
+///
+/// let mut options = DotOptions::default();
+/// options
+///     .set_rank_dir(RankDir::LeftRight)
+///     .set_palette(Palette::Generated)
+///     .set_show_node_ids(false)
+///     .select_trees([0])
+///     .select_leaves([Label(1), Label(2)]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DotOptions {
+    rank_dir: RankDir,
+    palette: Palette,
+    show_node_ids: bool,
+    show_cut_edges: bool,
+    trees: Option<HashSet<usize>>,
+    leaves: Option<HashSet<Label>>,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self {
+            rank_dir: RankDir::default(),
+            palette: Palette::default(),
+            show_node_ids: true,
+            show_cut_edges: true,
+            trees: None,
+            leaves: None,
+        }
+    }
+}
+
+impl DotOptions {
+    pub fn set_rank_dir(&mut self, rank_dir: RankDir) -> &mut Self {
+        self.rank_dir = rank_dir;
+        self
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) -> &mut Self {
+        self.palette = palette;
+        self
+    }
+
+    /// If disabled, inner nodes are drawn without labels, i.e. only leaf labels are shown
+    pub fn set_show_node_ids(&mut self, show: bool) -> &mut Self {
+        self.show_node_ids = show;
+        self
+    }
+
+    /// If enabled (the default), edges cut by the solution are dashed and roots of
+    /// components are drawn as triangles
+    pub fn set_show_cut_edges(&mut self, show: bool) -> &mut Self {
+        self.show_cut_edges = show;
+        self
+    }
+
+    /// Restricts the output to the trees with the given (0-based) indices
+    pub fn select_trees(&mut self, trees: impl IntoIterator<Item = usize>) -> &mut Self {
+        self.trees = Some(trees.into_iter().collect());
+        self
+    }
+
+    /// Restricts the output to the components (i.e. solution trees) containing at least one
+    /// of the given leaves. Without a solution, each tree forms a single component.
+    pub fn select_leaves(&mut self, leaves: impl IntoIterator<Item = Label>) -> &mut Self {
+        self.leaves = Some(leaves.into_iter().collect());
+        self
+    }
+}
+
 /// Produce a visual representation of an instance (optionally together with a solution) in
 /// GraphViz Dot format.
 ///
@@ -27,175 +127,184 @@ use std::{collections::HashSet, io::Write};
 ///
 /// let mut writer = ForestDotWriter::new(instance.as_ref().unwrap());
 /// writer.color_leafs(&solution, &forests);
+/// writer.set_options(DotOptions::default().set_rank_dir(RankDir::LeftRight).clone());
 /// writer.write(&mut stdout().lock()).unwrap();
 /// ```
 pub struct ForestDotWriter<'a> {
     instance: &'a Instance,
-    colors: Vec<u32>,
-    roots: Vec<HashSet<NodeIdx>>,
+    coloring: ForestColoring,
     leaf_names: Vec<String>,
+    options: DotOptions,
+    selected_colors: Option<HashSet<u32>>,
 }
 
 impl<'a> ForestDotWriter<'a> {
     pub fn new(instance: &'a Instance) -> Self {
-        let num_nodes =
-            (1 + instance.num_trees() as usize) * (instance.num_leaves() as usize - 1) + 2;
-
-        let colors = std::iter::repeat_n(1, num_nodes).collect();
         Self {
             instance,
-            colors,
+            coloring: ForestColoring::new(instance),
             leaf_names: Vec::with_capacity(
                 instance.num_leaves() as usize * instance.num_trees() as usize,
             ),
-            roots: Vec::with_capacity(instance.num_trees() as usize),
+            options: DotOptions::default(),
+            selected_colors: None,
         }
     }
 
-    pub fn color_leafs(&mut self, solution: &Solution, forests: &[BinForest]) {
-        // color leaves using the index of the solution tree
-        for (i, (_, tree)) in solution.trees().iter().enumerate() {
-            for u in tree.clone().top_down().dfs() {
-                if let Some(l) = u.leaf_label() {
-                    self.colors[l.0 as usize] = 2 + i as u32;
-                }
-            }
-        }
-
-        for (forest, (_, inst)) in forests.iter().zip(self.instance.trees()) {
-            let roots: HashSet<_> = forest.roots().iter().map(|c| c.node_idx()).collect();
-
-            fn recurse(
-                colors: &mut [u32],
-                roots: &HashSet<NodeIdx>,
-                node: NodeCursor,
-            ) -> (bool, u32) {
-                let is_root = roots.contains(&node.node_idx());
-                if let Some((left, right)) = node.children() {
-                    let (l_root, l_color) = recurse(colors, roots, left);
-                    let (r_root, r_color) = recurse(colors, roots, right);
-
-                    let color = if l_color == r_color || !l_root {
-                        l_color
-                    } else if !r_root {
-                        r_color
-                    } else {
-                        1
-                    };
-
-                    colors[node.node_idx().0 as usize] = color;
-                }
+    pub fn set_options(&mut self, options: DotOptions) -> &mut Self {
+        self.options = options;
+        self
+    }
 
-                (is_root, colors[node.node_idx().0 as usize])
-            }
+    pub fn options(&self) -> &DotOptions {
+        &self.options
+    }
 
-            recurse(&mut self.colors, &roots, inst.clone());
+    /// Returns whether a node of the given color index belongs to a selected component
+    fn is_selected(&self, color: u32) -> bool {
+        self.selected_colors
+            .as_ref()
+            .is_none_or(|colors| colors.contains(&color))
+    }
 
-            self.roots.push(roots);
-        }
+    pub fn color_leafs(&mut self, solution: &Solution, forests: &[BinForest]) {
+        self.coloring.color_leafs(self.instance, solution, forests);
     }
 
-    fn node_name(&self, name: &str, u: &NodeCursor) -> String {
-        if let Some(l) = u.leaf_label() {
+    fn node_name(&self, name: &str, u: &FlatNode) -> String {
+        if let Some(l) = u.label {
             format!("t{}l{}", name, l.0)
         } else {
-            format!("t{}v{}", name, u.node_idx().0)
+            format!("t{}v{}", name, u.node_idx.0)
         }
     }
 
-    fn recurse(
+    /// Writes the nodes and edges of a single tree in depth-first order, i.e. each edge is
+    /// directly followed by the subtree below it
+    fn write_tree(
         &mut self,
         writer: &mut impl Write,
-        root: NodeCursor,
+        layout: &TreeLayout,
         name: &str,
         roots: &HashSet<NodeIdx>,
     ) -> Result<(), std::io::Error> {
-        let color = self.colors[root.node_idx().0 as usize];
-        let my_key = self.node_name(name, &root);
-        let is_root = roots.contains(&root.node_idx());
-
-        fn can_reach_leaf(roots: &HashSet<NodeIdx>, node: &NodeCursor) -> bool {
-            if roots.contains(&node.node_idx()) {
-                false
-            } else if let Some((l, r)) = node.children() {
-                can_reach_leaf(roots, &l) || can_reach_leaf(roots, &r)
-            } else {
-                true
-            }
+        enum Item {
+            Node(usize),
+            Edge(usize, usize),
         }
 
-        if let Some((l, r)) = root.children() {
-            let l_name = self.node_name(name, &l);
-            let r_name = self.node_name(name, &r);
-            let l_is_root = roots.contains(&l.node_idx());
-            let r_is_root = roots.contains(&r.node_idx());
-
-            let l_reaches_leaf = can_reach_leaf(roots, &l);
-            let r_reaches_leaf = can_reach_leaf(roots, &r);
-
-            writeln!(
-                writer,
-                "  {my_key}[label=\"{}\",color={color}{}]",
-                root.node_idx().0,
-                if is_root {
-                    if l_reaches_leaf || r_reaches_leaf {
-                        ",shape=\"triangle\""
-                    } else {
-                        ",style=\"dotted\""
+        let tree = layout.tree();
+        let reaches_leaf = layout.reaches_leaf(roots);
+        let cut_edges = self.options.show_cut_edges;
+
+        let mut stack = vec![Item::Node(tree.root())];
+        while let Some(item) = stack.pop() {
+            let u = match item {
+                Item::Node(u) => u,
+                Item::Edge(u, child) => {
+                    let node = tree.node(child);
+                    let color = self.coloring.color(tree.node(u).node_idx);
+                    let child_color = self.coloring.color(node.node_idx);
+                    if self.is_selected(color) && self.is_selected(child_color) {
+                        writeln!(
+                            writer,
+                            "  {} -> {}{};",
+                            self.node_name(name, tree.node(u)),
+                            self.node_name(name, node),
+                            if cut_edges && roots.contains(&node.node_idx) {
+                                " [style=dashed]"
+                            } else if !reaches_leaf[child] {
+                                "[style=dotted]"
+                            } else {
+                                ""
+                            },
+                        )?;
                     }
-                } else if l_reaches_leaf || r_reaches_leaf {
-                    ""
-                } else {
-                    ",style=\"dotted\""
+                    continue;
                 }
-            )?;
-
-            writeln!(
-                writer,
-                "  {my_key} -> {l_name}{};",
-                if l_is_root {
-                    " [style=dashed]"
-                } else if !l_reaches_leaf {
-                    "[style=dotted]"
-                } else {
-                    ""
-                },
-            )?;
-            self.recurse(writer, l, name, roots)?;
-
-            writeln!(
-                writer,
-                "  {my_key} -> {r_name}{};",
-                if r_is_root {
-                    " [style=dashed]"
-                } else if !r_reaches_leaf {
-                    "[style=dotted]"
-                } else {
-                    ""
-                },
-            )?;
-            self.recurse(writer, r, name, roots)?;
-        } else if let Some(l) = root.leaf_label() {
-            writeln!(
-                writer,
-                "  {my_key} [label=\"{}\", color={color}, shape=\"{}\"]",
-                l.0,
-                if is_root { "triangle" } else { "box" }
-            )?;
-            self.leaf_names.push(my_key);
-        } else {
-            unreachable!();
+            };
+
+            let node = tree.node(u);
+            let color = self.coloring.color(node.node_idx);
+            let my_key = self.node_name(name, node);
+            let is_root = cut_edges && roots.contains(&node.node_idx);
+
+            match (node.children, node.label) {
+                (Some((l, r)), _) => {
+                    if self.is_selected(color) {
+                        let label = if self.options.show_node_ids {
+                            node.node_idx.0.to_string()
+                        } else {
+                            String::new()
+                        };
+                        let child_reaches_leaf = reaches_leaf[l] || reaches_leaf[r];
+
+                        writeln!(
+                            writer,
+                            "  {my_key}[label=\"{label}\",color={}{}]",
+                            self.options.palette.dot_color(color),
+                            match (is_root, child_reaches_leaf) {
+                                (true, true) => ",shape=\"triangle\"",
+                                (false, true) => "",
+                                (_, false) => ",style=\"dotted\"",
+                            }
+                        )?;
+                    }
+
+                    stack.extend([
+                        Item::Node(r),
+                        Item::Edge(u, r),
+                        Item::Node(l),
+                        Item::Edge(u, l),
+                    ]);
+                }
+                (None, Some(l)) => {
+                    if self.is_selected(color) {
+                        writeln!(
+                            writer,
+                            "  {my_key} [label=\"{}\", color={}, shape=\"{}\"]",
+                            l.0,
+                            self.options.palette.dot_color(color),
+                            if is_root { "triangle" } else { "box" }
+                        )?;
+                        self.leaf_names.push(my_key);
+                    }
+                }
+                // an unlabeled node without children carries no information
+                (None, None) => {}
+            }
         }
         Ok(())
     }
 
+    /// Writes the Dot representation. Children are ordered by their smallest leaf, so the
+    /// layout is deterministic; the trees of the instance are not modified.
     pub fn write(&mut self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        self.leaf_names.clear();
+        self.selected_colors = self.options.leaves.as_ref().map(|leaves| {
+            leaves
+                .iter()
+                .map(|&l| self.coloring.color(l.into()))
+                .collect()
+        });
+
         writeln!(writer, "digraph Instance {{")?;
-        writeln!(writer, " rankdir=TB;")?;
-        writeln!(writer, " node [colorscheme=set19];")?;
+        writeln!(writer, " rankdir={};", self.options.rank_dir.as_str())?;
+        if self.options.palette == Palette::Set19 {
+            writeln!(writer, " node [colorscheme=set19];")?;
+        }
 
         for (i, (_lineno, tree)) in self.instance.trees().iter().enumerate() {
-            let roots = self.roots.get(i).cloned().unwrap_or_default();
+            if self
+                .options
+                .trees
+                .as_ref()
+                .is_some_and(|trees| !trees.contains(&i))
+            {
+                continue;
+            }
+
+            let roots = self.coloring.roots(i);
             let name = format!("t{}", i + 1);
             if i > 0 {
                 writeln!(
@@ -205,8 +314,10 @@ impl<'a> ForestDotWriter<'a> {
             }
 
             writeln!(writer, "  subgraph {} {{", name)?;
-            tree.normalize_child_order();
-            self.recurse(writer, tree.clone(), &name, &roots)?;
+            // the layout renders a normalized copy, which keeps all node indices and thus the
+            // colors and roots computed above
+            let layout = TreeLayout::new(tree);
+            self.write_tree(writer, &layout, &name, &roots)?;
             writeln!(writer, "  }}")?;
         }
 
@@ -224,6 +335,7 @@ mod test {
     use crate::checks::checker::check_instance_and_solution;
 
     use super::*;
+    use pace26io::newick::NewickWriter;
 
     const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
     const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
@@ -253,4 +365,128 @@ mod test {
         writer.write(&mut buffer).unwrap();
         String::from_utf8(buffer).unwrap();
     }
+
+    fn render(options: &DotOptions) -> String {
+        let (instance, solution, forests) = check_instance_and_solution(
+            &PathBuf::from(PATH_INSTANCE),
+            &PathBuf::from(PATH_SOLUTION),
+            false,
+            true,
+        )
+        .unwrap();
+
+        let mut writer = ForestDotWriter::new(instance.as_ref().unwrap());
+        writer.color_leafs(&solution, &forests);
+        writer.set_options(options.clone());
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn options() {
+        let default = render(&DotOptions::default());
+        assert!(default.contains("rankdir=TB;"));
+        assert!(default.contains("colorscheme=set19"));
+        assert!(default.contains("[style=dashed]"));
+        assert!(default.contains("subgraph t2 {"));
+
+        let mut options = DotOptions::default();
+        options
+            .set_rank_dir(RankDir::LeftRight)
+            .set_palette(Palette::Generated)
+            .set_show_node_ids(false)
+            .set_show_cut_edges(false);
+        let output = render(&options);
+        assert!(output.contains("rankdir=LR;"));
+        assert!(!output.contains("colorscheme"));
+        assert!(!output.contains("dashed"));
+        assert!(!output.contains("triangle"));
+        assert!(
+            output
+                .lines()
+                .filter(|l| l.contains("v") && l.contains("[label="))
+                .all(|l| l.contains("label=\"\""))
+        );
+
+        let output = render(DotOptions::default().select_trees([1]));
+        assert!(!output.contains("subgraph t1 {"));
+        assert!(output.contains("subgraph t2 {"));
+    }
+
+    #[test]
+    fn select_leaves() {
+        let all = render(&DotOptions::default());
+        let output = render(DotOptions::default().select_leaves([Label(1)]));
+
+        let leaves = |output: &str| {
+            output
+                .lines()
+                .filter(|l| l.contains("shape=\"box\"") || l.contains("shape=\"triangle\"]"))
+                .count()
+        };
+        assert!(leaves(&output) > 0);
+        assert!(leaves(&output) < leaves(&all));
+        assert!(output.contains("l1 ["));
+
+        // without a solution, the whole tree is a single component
+        let instance = Instance::read(&PathBuf::from(PATH_INSTANCE), false).unwrap();
+        let instance_only = |options: &DotOptions| {
+            let mut writer = ForestDotWriter::new(&instance);
+            writer.set_options(options.clone());
+            let mut buffer: Vec<u8> = Vec::new();
+            writer.write(&mut buffer).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        assert_eq!(
+            instance_only(DotOptions::default().select_leaves([Label(1)])),
+            instance_only(&DotOptions::default())
+        );
+    }
+
+    #[test]
+    fn deep_caterpillar() {
+        let num_leaves = 50_000;
+        let mut tree = "(".repeat(num_leaves - 1);
+        tree.push('1');
+        for i in 2..=num_leaves {
+            tree.push_str(&format!(",{i})"));
+        }
+        let data = format!("#p 1 {num_leaves}\n{tree};\n");
+        let instance = Instance::read_from(data.as_bytes(), false).unwrap();
+
+        let mut writer = ForestDotWriter::new(&instance);
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write(&mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            output.lines().filter(|l| l.contains(" -> ")).count(),
+            2 * (num_leaves - 1)
+        );
+    }
+
+    #[test]
+    fn does_not_modify_instance() {
+        let instance = Instance::read(&PathBuf::from(PATH_INSTANCE), false).unwrap();
+        let newick = |instance: &Instance| -> Vec<String> {
+            instance
+                .trees()
+                .iter()
+                .map(|(_, t)| t.top_down().to_newick_string())
+                .collect()
+        };
+        let before = newick(&instance);
+        assert!(before.iter().any(|nw| nw.starts_with("((")));
+
+        let mut outputs = Vec::new();
+        for _ in 0..2 {
+            let mut writer = ForestDotWriter::new(&instance);
+            let mut buffer: Vec<u8> = Vec::new();
+            writer.write(&mut buffer).unwrap();
+            outputs.push(buffer);
+        }
+
+        assert_eq!(newick(&instance), before);
+        assert_eq!(outputs[0], outputs[1]);
+    }
 }
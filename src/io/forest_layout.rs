@@ -0,0 +1,175 @@
+use crate::checks::{bin_tree_with_parent::NodeCursor, flat_tree::FlatTree};
+use pace26io::binary_tree::NodeIdx;
+use std::collections::HashSet;
+
+/// Tidy drawing of a single tree shared by the vector writers. Leaves are spread evenly on a
+/// common baseline, inner nodes are centered above their children, and the level of a node is
+/// given by its height. Coordinates are in units of the distance between neighboring leaves
+/// and levels, respectively; nodes are indexed like the nodes of [`TreeLayout::tree`].
+pub(crate) struct TreeLayout {
+    tree: FlatTree,
+    x: Vec<f64>,
+    height: Vec<usize>,
+}
+
+impl TreeLayout {
+    /// Lays out a normalized copy of `root`; the tree itself is not modified
+    pub(crate) fn new(root: &NodeCursor) -> Self {
+        let ordered = root.clone_and_rebuild();
+        ordered.normalize_child_order();
+        let tree = FlatTree::new(&ordered);
+        let flipped = vec![false; tree.len()];
+        Self::with_flips(tree, &flipped)
+    }
+
+    /// Lays out `tree`, where the children of each node `u` with `flipped[u]` are swapped
+    pub(crate) fn with_flips(tree: FlatTree, flipped: &[bool]) -> Self {
+        let n = tree.len();
+        let mut x = vec![0.0; n];
+        let mut height = vec![0; n];
+
+        for (rank, u) in leaf_order(&tree, flipped).into_iter().enumerate() {
+            x[u] = rank as f64;
+        }
+
+        for u in (0..n).rev() {
+            if let Some((l, r)) = tree.node(u).children {
+                x[u] = (x[l] + x[r]) / 2.0;
+                height[u] = 1 + height[l].max(height[r]);
+            }
+        }
+
+        Self { tree, x, height }
+    }
+
+    pub(crate) fn tree(&self) -> &FlatTree {
+        &self.tree
+    }
+
+    /// Horizontal position of node `u`; the leftmost leaf is at 0
+    pub(crate) fn x(&self, u: usize) -> f64 {
+        self.x[u]
+    }
+
+    /// Level of node `u`; the root is at level 0 and all leaves are at [`TreeLayout::depth`]
+    pub(crate) fn level(&self, u: usize) -> usize {
+        self.height[self.tree.root()] - self.height[u]
+    }
+
+    pub(crate) fn width(&self) -> f64 {
+        self.x.iter().copied().fold(0.0, f64::max)
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.height.first().copied().unwrap_or(0)
+    }
+
+    /// Returns for each node whether it is connected to a leaf below it, i.e. whether the
+    /// path to some leaf does not pass through one of the component `roots`
+    pub(crate) fn reaches_leaf(&self, roots: &HashSet<NodeIdx>) -> Vec<bool> {
+        let mut reaches_leaf = vec![false; self.tree.len()];
+        for u in (0..self.tree.len()).rev() {
+            let node = self.tree.node(u);
+            reaches_leaf[u] = !roots.contains(&node.node_idx)
+                && match node.children {
+                    None => true,
+                    Some((l, r)) => reaches_leaf[l] || reaches_leaf[r],
+                };
+        }
+        reaches_leaf
+    }
+}
+
+/// Leaves of `tree` from left to right, where the children of each node `u` with
+/// `flipped[u]` are swapped
+pub(crate) fn leaf_order(tree: &FlatTree, flipped: &[bool]) -> Vec<usize> {
+    let mut order = Vec::new();
+    let mut stack = vec![tree.root()];
+    while let Some(u) = stack.pop() {
+        match tree.node(u).children {
+            None => order.push(u),
+            Some((l, r)) if flipped[u] => stack.extend([l, r]),
+            Some((l, r)) => stack.extend([r, l]),
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        checks::bin_tree_with_parent::BinTreeWithParentBuilder, io::instance_reader::Instance,
+    };
+    use pace26io::newick::{BinaryTreeParser, NewickWriter};
+    use std::path::PathBuf;
+
+    #[test]
+    fn leaves_on_baseline() {
+        let instance =
+            Instance::read(&PathBuf::from("testcases/valid/score10_n07l_lkc.in"), false).unwrap();
+        let (_, tree) = &instance.trees()[0];
+        let before = tree.top_down().to_newick_string();
+        let layout = TreeLayout::new(tree);
+        assert_eq!(tree.top_down().to_newick_string(), before);
+
+        let levels: HashSet<_> = layout
+            .tree()
+            .leaves()
+            .map(|(u, _)| layout.level(u))
+            .collect();
+        assert_eq!(levels, HashSet::from([layout.depth()]));
+        assert_eq!(layout.level(layout.tree().root()), 0);
+        assert_eq!(layout.width() as u32 + 1, instance.num_leaves());
+
+        for (u, node) in layout.tree().nodes().iter().enumerate() {
+            if let Some((l, r)) = node.children {
+                assert!(layout.x(l) < layout.x(u) && layout.x(u) < layout.x(r));
+                assert!(layout.level(u) < layout.level(l) && layout.level(u) < layout.level(r));
+            }
+        }
+    }
+
+    #[test]
+    fn reaches_leaf() {
+        let instance =
+            Instance::read(&PathBuf::from("testcases/valid/score10_n07l_lkc.in"), false).unwrap();
+        let (_, tree) = &instance.trees()[0];
+        let layout = TreeLayout::new(tree);
+        let flat = layout.tree();
+
+        assert!(layout.reaches_leaf(&HashSet::new()).iter().all(|&r| r));
+
+        // cutting both children of the root detaches the root
+        let (l, r) = flat.node(flat.root()).children.unwrap();
+        let roots = HashSet::from([flat.node(l).node_idx, flat.node(r).node_idx]);
+        let reaches = layout.reaches_leaf(&roots);
+        assert!(!reaches[flat.root()] && !reaches[l] && !reaches[r]);
+        assert_eq!(reaches.iter().filter(|&&r| !r).count(), 3);
+    }
+
+    #[test]
+    fn flips() {
+        let tree = BinTreeWithParentBuilder::default()
+            .parse_newick_from_str("((1,2),(3,(4,5)));", NodeIdx(6))
+            .unwrap();
+        let flat = FlatTree::new(&tree);
+        let labels = |order: Vec<usize>| -> Vec<u32> {
+            order
+                .into_iter()
+                .map(|u| flat.node(u).label.unwrap().0)
+                .collect()
+        };
+
+        let mut flipped = vec![false; flat.len()];
+        assert_eq!(labels(leaf_order(&flat, &flipped)), vec![1, 2, 3, 4, 5]);
+
+        flipped[flat.root()] = true;
+        flipped[1] = true;
+        assert_eq!(labels(leaf_order(&flat, &flipped)), vec![3, 4, 5, 2, 1]);
+
+        let layout = TreeLayout::with_flips(flat.clone(), &flipped);
+        assert_eq!(layout.x(flat.root()), 2.125);
+        assert_eq!(layout.x(1), 3.5);
+    }
+}
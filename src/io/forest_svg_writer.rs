@@ -0,0 +1,230 @@
+use crate::{
+    checks::bin_forest::*,
+    io::{instance_reader::Instance, solution_reader::Solution},
+};
+use pace26io::binary_tree::NodeIdx;
+use std::{collections::HashSet, io::Write};
+
+pub use super::forest_coloring::Palette;
+use super::{
+    forest_coloring::*,
+    forest_layout::TreeLayout,
+    svg::{self, DX, DY, EdgeStyle, LABEL_SPACE, MARGIN, NodeStyle},
+};
+
+/// Produce a visual representation of an instance (optionally together with a solution) as
+/// a standalone SVG image. In contrast to [`ForestDotWriter`](super::forest_dot_writer::ForestDotWriter),
+/// no external tools are required to obtain a picture.
+///
+/// Each tree is drawn as a tidy tree below the previous one: leaves are spread evenly on a
+/// common baseline, inner nodes are centered above their children, and the vertical position
+/// of a node is given by its height. Nodes and edges within a component of the solution share
+/// its color, edges cut by the solution are dashed, and edges into subtrees without any
+/// leaf of their component are dotted.
+///
+/// # Example
+/// ```
+/// use std::{path::PathBuf, io::stdout};
+/// use pace26checker::{checks::checker::check_instance_and_solution, io::forest_svg_writer::*};
+///
+/// const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
+/// const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
+///
+/// let (instance, solution, forests) = check_instance_and_solution(
+///         &PathBuf::from(PATH_INSTANCE),
+///         &PathBuf::from(PATH_SOLUTION),
+///         false,
+///         true,
+///     )
+///     .unwrap();
+///
+/// let mut writer = ForestSvgWriter::new(instance.as_ref().unwrap());
+/// writer.color_leafs(&solution, &forests);
+/// writer.set_palette(Palette::Generated);
+/// writer.write(&mut stdout().lock()).unwrap();
+/// ```
+pub struct ForestSvgWriter<'a> {
+    instance: &'a Instance,
+    coloring: ForestColoring,
+    palette: Palette,
+    trees: Option<HashSet<usize>>,
+}
+
+impl<'a> ForestSvgWriter<'a> {
+    pub fn new(instance: &'a Instance) -> Self {
+        Self {
+            instance,
+            coloring: ForestColoring::new(instance),
+            palette: Palette::default(),
+            trees: None,
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) -> &mut Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Restricts the output to the trees with the given (0-based) indices
+    pub fn select_trees(&mut self, trees: impl IntoIterator<Item = usize>) -> &mut Self {
+        self.trees = Some(trees.into_iter().collect());
+        self
+    }
+
+    pub fn color_leafs(&mut self, solution: &Solution, forests: &[BinForest]) {
+        self.coloring.color_leafs(self.instance, solution, forests);
+    }
+
+    /// Writes the SVG document. As with the Dot output, children are ordered by their
+    /// smallest leaf and the trees of the instance are not modified.
+    pub fn write(&mut self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        let layouts: Vec<_> = self
+            .instance
+            .trees()
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.trees.as_ref().is_none_or(|trees| trees.contains(i)))
+            .map(|(i, (_, tree))| (i, TreeLayout::new(tree)))
+            .collect();
+        let band = |layout: &TreeLayout| layout.depth() as f64 * DY + LABEL_SPACE + MARGIN;
+
+        let width = layouts
+            .iter()
+            .map(|(_, l)| l.width() * DX)
+            .fold(0.0, f64::max)
+            + 2.0 * MARGIN;
+        let height = layouts.iter().map(|(_, l)| band(l)).sum::<f64>() + MARGIN;
+
+        svg::write_header(writer, width, height, "")?;
+
+        let mut top = MARGIN;
+        for (i, layout) in &layouts {
+            writeln!(writer, " <g id=\"t{}\">", i + 1)?;
+            self.write_tree(writer, layout, &self.coloring.roots(*i), top)?;
+            writeln!(writer, " </g>")?;
+            top += band(layout);
+        }
+
+        writeln!(writer, "</svg>")?;
+        Ok(())
+    }
+
+    fn write_tree(
+        &self,
+        writer: &mut impl Write,
+        layout: &TreeLayout,
+        roots: &HashSet<NodeIdx>,
+        top: f64,
+    ) -> Result<(), std::io::Error> {
+        let tree = layout.tree();
+        let reaches_leaf = layout.reaches_leaf(roots);
+        let colors: Vec<_> = tree
+            .nodes()
+            .iter()
+            .map(|node| self.palette.css_color(self.coloring.color(node.node_idx)))
+            .collect();
+
+        svg::write_tree(
+            writer,
+            layout,
+            (MARGIN, top),
+            |u| EdgeStyle {
+                color: &colors[u],
+                width: 2.0,
+                dash: if roots.contains(&tree.node(u).node_idx) {
+                    Some("6 4")
+                } else if !reaches_leaf[u] {
+                    Some("2 3")
+                } else {
+                    None
+                },
+            },
+            |u| NodeStyle {
+                radius: NodeStyle::radius(tree.node(u).label.is_some()),
+                fill: &colors[u],
+                stroke: None,
+                tooltip: None,
+            },
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::checks::checker::check_instance_and_solution;
+
+    use super::*;
+    use pace26io::newick::NewickWriter;
+
+    const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
+    const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
+
+    #[test]
+    fn instance_only() {
+        let instance = Instance::read(&PathBuf::from(PATH_INSTANCE), false).unwrap();
+        let newick = |instance: &Instance| -> Vec<String> {
+            instance
+                .trees()
+                .iter()
+                .map(|(_, t)| t.top_down().to_newick_string())
+                .collect()
+        };
+        let before = newick(&instance);
+
+        let mut writer = ForestSvgWriter::new(&instance);
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write(&mut buffer).unwrap();
+        let svg = String::from_utf8(buffer).unwrap();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(
+            svg.matches("<text ").count(),
+            (instance.num_trees() * instance.num_leaves()) as usize
+        );
+        assert!(!svg.contains("stroke-dasharray"));
+        assert_eq!(newick(&instance), before);
+    }
+
+    #[test]
+    fn instance_and_solution() {
+        let (instance, solution, forests) = check_instance_and_solution(
+            &PathBuf::from(PATH_INSTANCE),
+            &PathBuf::from(PATH_SOLUTION),
+            false,
+            true,
+        )
+        .unwrap();
+
+        let mut writer = ForestSvgWriter::new(instance.as_ref().unwrap());
+        writer.color_leafs(&solution, &forests);
+        writer.set_palette(Palette::Generated);
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write(&mut buffer).unwrap();
+        let svg = String::from_utf8(buffer).unwrap();
+
+        assert!(svg.contains("stroke-dasharray=\"6 4\""));
+
+        // each component has its own color
+        let colors: HashSet<_> = svg
+            .lines()
+            .filter(|line| line.contains("r=\"5\""))
+            .filter_map(|line| line.split("fill=\"").nth(1))
+            .collect();
+        assert_eq!(colors.len(), solution.trees().len());
+
+        writer.select_trees([1]);
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write(&mut buffer).unwrap();
+        let svg = String::from_utf8(buffer).unwrap();
+        assert!(svg.contains("<g id=\"t2\">") && !svg.contains("<g id=\"t1\">"));
+        assert_eq!(
+            svg.matches("<text ").count(),
+            instance.as_ref().unwrap().num_leaves() as usize
+        );
+    }
+}
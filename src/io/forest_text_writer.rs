@@ -0,0 +1,354 @@
+use crate::{
+    checks::{bin_forest::*, bin_tree_with_parent::NodeCursor},
+    io::{instance_reader::Instance, solution_reader::Solution},
+};
+use pace26io::binary_tree::TreeWithNodeIdx;
+use std::io::Write;
+
+use super::{forest_coloring::*, forest_layout::TreeLayout};
+
+/// Number of columns between the vertical lines of a node and its children
+const STEP: usize = 2;
+
+/// Characters used to draw the trees
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// Box-drawing characters, e.g. `┬─┬── 1`
+    #[default]
+    Unicode,
+
+    /// Plain ASCII, e.g. `+-+-- 1`, for terminals without Unicode support
+    Ascii,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EdgeKind {
+    Normal,
+    /// Edge into the root of a component, i.e. cut by the solution
+    Cut,
+    /// Edge into a subtree without any leaf of its component
+    Detached,
+}
+
+impl Charset {
+    fn horizontal(self, kind: EdgeKind) -> char {
+        match (self, kind) {
+            (Charset::Unicode, EdgeKind::Normal) => '─',
+            (Charset::Unicode, EdgeKind::Cut) => '╌',
+            (Charset::Unicode, EdgeKind::Detached) => '┄',
+            (Charset::Ascii, EdgeKind::Normal) => '-',
+            (Charset::Ascii, EdgeKind::Cut) => '~',
+            (Charset::Ascii, EdgeKind::Detached) => '.',
+        }
+    }
+
+    fn branch(self) -> char {
+        match self {
+            Charset::Unicode => '┬',
+            Charset::Ascii => '+',
+        }
+    }
+
+    fn vertical(self) -> char {
+        match self {
+            Charset::Unicode => '│',
+            Charset::Ascii => '|',
+        }
+    }
+
+    fn corner(self) -> char {
+        match self {
+            Charset::Unicode => '└',
+            Charset::Ascii => '`',
+        }
+    }
+}
+
+/// Renders an instance (optionally together with a solution) as plain text, e.g. for
+/// debugging on machines without a graphical display. Each tree is drawn sideways with its
+/// root on the left and one leaf per line on the right:
+///
+/// ```text
+/// ┬─┬── 1 [c1]
+/// │ └── 2 [c1]
+/// └╌╌╌╌ 3 [c2]
+/// ```
+///
+/// Leaves are annotated with the (1-based) index of their solution tree, edges cut by the
+/// solution are drawn with `╌` (`~` in ASCII), and edges into subtrees without any leaf of
+/// their component with `┄` (`.` in ASCII).
+///
+/// # Example
+/// ```
+/// use std::{path::PathBuf, io::stdout};
+/// use pace26checker::{checks::checker::check_instance_and_solution, io::forest_text_writer::*};
+///
+/// const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
+/// const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
+///
+/// let (instance, solution, forests) = check_instance_and_solution(
+///         &PathBuf::from(PATH_INSTANCE),
+///         &PathBuf::from(PATH_SOLUTION),
+///         false,
+///         true,
+///     )
+///     .unwrap();
+///
+/// let mut writer = ForestTextWriter::new(instance.as_ref().unwrap());
+/// writer.color_leafs(&solution, &forests);
+/// writer.set_charset(Charset::Ascii);
+/// writer.write(&mut stdout().lock()).unwrap();
+/// ```
+pub struct ForestTextWriter<'a> {
+    instance: &'a Instance,
+    coloring: ForestColoring,
+    charset: Charset,
+    colored: bool,
+}
+
+impl<'a> ForestTextWriter<'a> {
+    pub fn new(instance: &'a Instance) -> Self {
+        Self {
+            instance,
+            coloring: ForestColoring::new(instance),
+            charset: Charset::default(),
+            colored: false,
+        }
+    }
+
+    pub fn set_charset(&mut self, charset: Charset) -> &mut Self {
+        self.charset = charset;
+        self
+    }
+
+    pub fn color_leafs(&mut self, solution: &Solution, forests: &[BinForest]) {
+        self.coloring.color_leafs(self.instance, solution, forests);
+        self.colored = true;
+    }
+
+    /// Writes all trees of the instance. As with the other writers, children are ordered by
+    /// their smallest leaf and the trees of the instance are not modified.
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        for (i, (lineno, tree)) in self.instance.trees().iter().enumerate() {
+            let layout = TreeLayout::new(tree);
+            let roots = self.coloring.roots(i);
+            let reaches_leaf = layout.reaches_leaf(&roots);
+            let flat = layout.tree();
+
+            writeln!(writer, "Tree {} (line {})", i + 1, lineno + 1)?;
+            let kind = |u: usize| {
+                if roots.contains(&flat.node(u).node_idx) {
+                    EdgeKind::Cut
+                } else if !reaches_leaf[u] {
+                    EdgeKind::Detached
+                } else {
+                    EdgeKind::Normal
+                }
+            };
+            let annotation = |u: usize| match self.coloring.color(flat.node(u).node_idx) {
+                MIXED_COLOR => String::new(),
+                color => format!(" [c{}]", color - 1),
+            };
+            write_layout(writer, &layout, self.charset, kind, annotation)?;
+        }
+
+        if self.colored {
+            writeln!(
+                writer,
+                "{} cut edge, {} edge without leaf of its component, [cK] leaf of the K-th solution tree",
+                self.charset.horizontal(EdgeKind::Cut),
+                self.charset.horizontal(EdgeKind::Detached),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Draws a single tree as described in [`ForestTextWriter`] (without annotations);
+/// the tree is not modified.
+pub fn write_tree(
+    writer: &mut impl Write,
+    tree: &NodeCursor,
+    charset: Charset,
+) -> Result<(), std::io::Error> {
+    let layout = TreeLayout::new(tree);
+    write_layout(
+        writer,
+        &layout,
+        charset,
+        |_| EdgeKind::Normal,
+        |_| String::new(),
+    )
+}
+
+/// Draws each component of a forest below a header with the node id of its root
+pub fn write_forest(
+    writer: &mut impl Write,
+    forest: &BinForest,
+    charset: Charset,
+) -> Result<(), std::io::Error> {
+    for (i, root) in forest.roots().iter().enumerate() {
+        writeln!(writer, "Component {} (root #{})", i + 1, root.node_idx().0)?;
+        write_tree(writer, root, charset)?;
+    }
+    Ok(())
+}
+
+/// Draws a tree onto a character grid with one row per leaf. An inner node shares the row
+/// of its left child, and its vertical line at column `STEP * depth` extends down to the row
+/// of its right child. Leaves are aligned in the rightmost column.
+fn write_layout(
+    writer: &mut impl Write,
+    layout: &TreeLayout,
+    charset: Charset,
+    kind: impl Fn(usize) -> EdgeKind,
+    annotation: impl Fn(usize) -> String,
+) -> Result<(), std::io::Error> {
+    let tree = layout.tree();
+    let n = tree.len();
+
+    let mut row = vec![0; n];
+    for (rank, (u, _)) in tree.leaves().enumerate() {
+        row[u] = rank;
+    }
+    for u in (0..n).rev() {
+        if let Some((l, _)) = tree.node(u).children {
+            row[u] = row[l];
+        }
+    }
+
+    let max_depth = tree.nodes().iter().map(|n| n.depth).max().unwrap_or(0);
+    let width = STEP * max_depth + 1;
+    let col = |u: usize| STEP * tree.node(u).depth;
+    let mut grid = vec![vec![' '; width]; tree.leaves().count()];
+
+    for (u, node) in tree.nodes().iter().enumerate() {
+        let line = &mut grid[row[u]];
+
+        // horizontal segment from the parent's vertical line to the node
+        let begin = node.parent.map_or(0, |p| col(p) + 1);
+        let end = if node.label.is_some() { width } else { col(u) };
+        line[begin..end].fill(charset.horizontal(kind(u)));
+
+        if let Some((_, r)) = node.children {
+            grid[row[u]][col(u)] = charset.branch();
+            for line in &mut grid[row[u] + 1..row[r]] {
+                line[col(u)] = charset.vertical();
+            }
+            grid[row[r]][col(u)] = charset.corner();
+        }
+    }
+
+    for (u, label) in tree.leaves() {
+        let line: String = grid[row[u]].iter().collect();
+        writeln!(writer, "{line} {}{}", label.0, annotation(u))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::checks::{
+        bin_tree_with_parent::BinTreeWithParentBuilder, checker::check_instance_and_solution,
+    };
+    use pace26io::{binary_tree::NodeIdx, newick::BinaryTreeParser};
+    use std::path::PathBuf;
+
+    const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
+    const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
+
+    fn render(newick: &str, charset: Charset) -> String {
+        let tree = BinTreeWithParentBuilder::default()
+            .parse_newick_from_str(newick, NodeIdx(8))
+            .unwrap();
+        let mut buffer: Vec<u8> = Vec::new();
+        write_tree(&mut buffer, &tree, charset).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn trees() {
+        assert_eq!(render("3;", Charset::Unicode), "─ 3\n");
+        assert_eq!(
+            render("(3,(2,1));", Charset::Unicode),
+            "┬─┬── 1\n│ └── 2\n└──── 3\n"
+        );
+        assert_eq!(
+            render("((4,5),(1,(2,3)));", Charset::Ascii),
+            concat!(
+                "+-+---- 1\n",
+                "| `-+-- 2\n",
+                "|   `-- 3\n",
+                "`-+---- 4\n",
+                "  `---- 5\n",
+            )
+        );
+    }
+
+    #[test]
+    fn forest() {
+        let host = BinTreeWithParentBuilder::default()
+            .parse_newick_from_str("((1,2),(3,4));", NodeIdx(5))
+            .unwrap();
+        let pattern = BinTreeWithParentBuilder::default()
+            .parse_newick_from_str("((1,2),3);", Default::default())
+            .unwrap();
+        let forest = BinForest::new(4)
+            .add_tree(host)
+            .unwrap()
+            .isolate_tree(&pattern)
+            .unwrap();
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_forest(&mut buffer, &forest, Charset::Ascii).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+
+        let root = forest.roots()[0].node_idx().0;
+        assert_eq!(
+            text,
+            format!(
+                "Component 1 (root #{root})\n+-+-- 1\n| `-- 2\n`---- 3\nComponent 2 (root #4)\n- 4\n"
+            )
+        );
+    }
+
+    #[test]
+    fn instance_and_solution() {
+        let (instance, solution, forests) = check_instance_and_solution(
+            &PathBuf::from(PATH_INSTANCE),
+            &PathBuf::from(PATH_SOLUTION),
+            false,
+            true,
+        )
+        .unwrap();
+        let instance = instance.unwrap();
+
+        let mut writer = ForestTextWriter::new(&instance);
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write(&mut buffer).unwrap();
+        let plain = String::from_utf8(buffer).unwrap();
+        assert!(!plain.contains('╌') && !plain.contains('['));
+
+        writer.color_leafs(&solution, &forests);
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write(&mut buffer).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+
+        // one line per leaf and tree, plus headers and the legend
+        let num_lines = instance.num_trees() * (instance.num_leaves() + 1) + 1;
+        assert_eq!(text.lines().count(), num_lines as usize);
+
+        // every leaf is annotated with its component, and every tree has cut edges
+        assert_eq!(
+            text.lines().filter(|line| line.ends_with(']')).count(),
+            (instance.num_trees() * instance.num_leaves()) as usize
+        );
+        for k in 1..=solution.num_trees() {
+            assert!(text.contains(&format!(" [c{k}]\n")));
+        }
+        assert!(text.split("Tree ").skip(1).all(|tree| tree.contains('╌')));
+    }
+}
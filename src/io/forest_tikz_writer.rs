@@ -0,0 +1,351 @@
+use crate::{
+    checks::bin_forest::*,
+    io::{instance_reader::Instance, solution_reader::Solution},
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::Write,
+};
+
+pub use super::forest_coloring::Palette;
+use super::{forest_coloring::*, forest_layout::TreeLayout};
+
+/// Horizontal distance between neighboring leaves (in cm)
+const DX: f64 = 0.5;
+/// Vertical distance between levels (in cm)
+const DY: f64 = 0.75;
+/// Vertical distance between the leaves of a tree and the root of the next one (in cm)
+const TREE_GAP: f64 = 1.5;
+
+/// Named TikZ styles used by [`ForestTikzWriter`]. Each element of the picture is drawn with
+/// the generic style of its kind and the style `pace c<i>` of its color index (see
+/// [`ForestColoring`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TikzStyle {
+    /// Inner nodes
+    Node,
+    /// Leaves; the style receives the label as argument
+    Leaf,
+    /// Roots of components (in addition to [`TikzStyle::Node`] or [`TikzStyle::Leaf`])
+    Root,
+    /// All edges
+    Edge,
+    /// Edges cut by the solution (in addition to [`TikzStyle::Edge`])
+    CutEdge,
+    /// Edges into subtrees without leaves of their component (in addition to [`TikzStyle::Edge`])
+    DetachedEdge,
+}
+
+impl TikzStyle {
+    const ALL: [TikzStyle; 6] = [
+        TikzStyle::Node,
+        TikzStyle::Leaf,
+        TikzStyle::Root,
+        TikzStyle::Edge,
+        TikzStyle::CutEdge,
+        TikzStyle::DetachedEdge,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TikzStyle::Node => "pace node",
+            TikzStyle::Leaf => "pace leaf",
+            TikzStyle::Root => "pace root",
+            TikzStyle::Edge => "pace edge",
+            TikzStyle::CutEdge => "pace cut edge",
+            TikzStyle::DetachedEdge => "pace detached edge",
+        }
+    }
+
+    pub fn default_definition(self) -> &'static str {
+        match self {
+            TikzStyle::Node => "circle, fill, inner sep=1.2pt",
+            TikzStyle::Leaf => "circle, fill, inner sep=1.8pt, label={below:\\scriptsize #1}",
+            TikzStyle::Root => "draw, fill=white, thick",
+            TikzStyle::Edge => "thick",
+            TikzStyle::CutEdge => "dashed",
+            TikzStyle::DetachedEdge => "dotted",
+        }
+    }
+}
+
+/// Produce a `tikzpicture` of an instance (optionally together with a solution) for
+/// inclusion into LaTeX documents; only `\usepackage{tikz}` is required. The trees are laid
+/// out as in [`ForestSvgWriter`](super::forest_svg_writer::ForestSvgWriter) and follow the
+/// semantics of [`ForestDotWriter`](super::forest_dot_writer::ForestDotWriter): components
+/// are colored, cut edges are dashed, and roots of components are marked.
+///
+/// All elements are drawn with the named styles of [`TikzStyle`]. Their definitions can be
+/// changed with [`ForestTikzWriter::set_style`]; alternatively, [`ForestTikzWriter::set_emit_styles`]
+/// omits all definitions, so that the document has to provide them (e.g. via `\tikzset`).
+///
+/// # Example
+/// ```
+/// use std::{path::PathBuf, io::stdout};
+/// use pace26checker::{checks::checker::check_instance_and_solution, io::forest_tikz_writer::*};
+///
+/// const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
+/// const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
+///
+/// let (instance, solution, forests) = check_instance_and_solution(
+///         &PathBuf::from(PATH_INSTANCE),
+///         &PathBuf::from(PATH_SOLUTION),
+///         false,
+///         true,
+///     )
+///     .unwrap();
+///
+/// let mut writer = ForestTikzWriter::new(instance.as_ref().unwrap());
+/// writer.color_leafs(&solution, &forests);
+/// writer.set_style(TikzStyle::CutEdge, "densely dashed, gray");
+/// writer.write(&mut stdout().lock()).unwrap();
+/// ```
+pub struct ForestTikzWriter<'a> {
+    instance: &'a Instance,
+    coloring: ForestColoring,
+    palette: Palette,
+    styles: HashMap<TikzStyle, String>,
+    emit_styles: bool,
+}
+
+impl<'a> ForestTikzWriter<'a> {
+    pub fn new(instance: &'a Instance) -> Self {
+        Self {
+            instance,
+            coloring: ForestColoring::new(instance),
+            palette: Palette::default(),
+            styles: HashMap::new(),
+            emit_styles: true,
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) -> &mut Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Replaces the definition of a style, e.g. `"draw, regular polygon, regular polygon
+    /// sides=3"` for [`TikzStyle::Root`] (which requires `\usetikzlibrary{shapes.geometric}`)
+    pub fn set_style(&mut self, style: TikzStyle, definition: impl Into<String>) -> &mut Self {
+        self.styles.insert(style, definition.into());
+        self
+    }
+
+    /// If disabled, the picture contains no style definitions (including the colors)
+    pub fn set_emit_styles(&mut self, emit: bool) -> &mut Self {
+        self.emit_styles = emit;
+        self
+    }
+
+    pub fn color_leafs(&mut self, solution: &Solution, forests: &[BinForest]) {
+        self.coloring.color_leafs(self.instance, solution, forests);
+    }
+
+    /// Writes the `tikzpicture` environment. As with the Dot output, children are ordered by
+    /// their smallest leaf and the trees of the instance are not modified.
+    pub fn write(&mut self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        let layouts: Vec<_> = self
+            .instance
+            .trees()
+            .iter()
+            .map(|(_, tree)| TreeLayout::new(tree))
+            .collect();
+
+        if self.emit_styles {
+            writeln!(writer, "\\begin{{tikzpicture}}[")?;
+            for style in TikzStyle::ALL {
+                let definition = self
+                    .styles
+                    .get(&style)
+                    .map_or(style.default_definition(), String::as_str);
+                writeln!(writer, "  {}/.style={{{definition}}},", style.name())?;
+            }
+            for color in self.used_colors(&layouts) {
+                writeln!(
+                    writer,
+                    "  pace c{color}/.style={{{}}},",
+                    self.tikz_color(color)
+                )?;
+            }
+            writeln!(writer, "]")?;
+        } else {
+            writeln!(writer, "\\begin{{tikzpicture}}")?;
+        }
+
+        let mut top = 0.0;
+        for (i, layout) in layouts.iter().enumerate() {
+            writeln!(writer, "  % tree {}", i + 1)?;
+            self.write_tree(writer, layout, i, top)?;
+            top -= layout.depth() as f64 * DY + TREE_GAP;
+        }
+
+        writeln!(writer, "\\end{{tikzpicture}}")?;
+        Ok(())
+    }
+
+    fn used_colors(&self, layouts: &[TreeLayout]) -> BTreeSet<u32> {
+        layouts
+            .iter()
+            .flat_map(|layout| layout.tree().nodes())
+            .map(|node| self.coloring.color(node.node_idx))
+            .collect()
+    }
+
+    /// Style definition selecting the color of color index `color`; hex colors are given as
+    /// RGB triples, all others are passed as color names to xcolor
+    fn tikz_color(&self, color: u32) -> String {
+        let css = self.palette.css_color(color);
+        match css
+            .strip_prefix('#')
+            .map(|hex| u32::from_str_radix(hex, 16))
+        {
+            Some(Ok(rgb)) if css.len() == 7 => format!(
+                "color={{rgb,255:red,{};green,{};blue,{}}}",
+                rgb >> 16,
+                (rgb >> 8) & 0xff,
+                rgb & 0xff
+            ),
+            _ => format!("color={css}"),
+        }
+    }
+
+    fn write_tree(
+        &self,
+        writer: &mut impl Write,
+        layout: &TreeLayout,
+        index: usize,
+        top: f64,
+    ) -> Result<(), std::io::Error> {
+        let tree = layout.tree();
+        let roots = self.coloring.roots(index);
+        let reaches_leaf = layout.reaches_leaf(&roots);
+        let name = |u: usize| match tree.node(u).label {
+            Some(l) => format!("t{}l{}", index + 1, l.0),
+            None => format!("t{}v{}", index + 1, tree.node(u).node_idx.0),
+        };
+
+        for (u, node) in tree.nodes().iter().enumerate() {
+            let x = layout.x(u) * DX;
+            let y = top - layout.level(u) as f64 * DY;
+            let kind = match node.label {
+                Some(l) => format!("{}={}", TikzStyle::Leaf.name(), l.0),
+                None => TikzStyle::Node.name().to_string(),
+            };
+            let root = if roots.contains(&node.node_idx) {
+                format!(", {}", TikzStyle::Root.name())
+            } else {
+                String::new()
+            };
+
+            writeln!(
+                writer,
+                "  \\node[{kind}, pace c{}{root}] ({}) at ({x},{y}) {{}};",
+                self.coloring.color(node.node_idx),
+                name(u)
+            )?;
+        }
+
+        for (u, node) in tree.nodes().iter().enumerate() {
+            let Some(p) = node.parent else {
+                continue;
+            };
+
+            let style = if roots.contains(&node.node_idx) {
+                format!(", {}", TikzStyle::CutEdge.name())
+            } else if !reaches_leaf[u] {
+                format!(", {}", TikzStyle::DetachedEdge.name())
+            } else {
+                String::new()
+            };
+
+            writeln!(
+                writer,
+                "  \\draw[{}, pace c{}{style}] ({}) -| ({});",
+                TikzStyle::Edge.name(),
+                self.coloring.color(node.node_idx),
+                name(p),
+                name(u)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::checks::checker::check_instance_and_solution;
+
+    use super::*;
+
+    const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
+    const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
+
+    fn render(configure: impl FnOnce(&mut ForestTikzWriter)) -> String {
+        let (instance, solution, forests) = check_instance_and_solution(
+            &PathBuf::from(PATH_INSTANCE),
+            &PathBuf::from(PATH_SOLUTION),
+            false,
+            true,
+        )
+        .unwrap();
+
+        let mut writer = ForestTikzWriter::new(instance.as_ref().unwrap());
+        writer.color_leafs(&solution, &forests);
+        configure(&mut writer);
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn instance_only() {
+        let instance = Instance::read(&PathBuf::from(PATH_INSTANCE), false).unwrap();
+        let mut writer = ForestTikzWriter::new(&instance);
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write(&mut buffer).unwrap();
+        let tikz = String::from_utf8(buffer).unwrap();
+
+        assert!(tikz.starts_with("\\begin{tikzpicture}["));
+        assert!(tikz.trim_end().ends_with("\\end{tikzpicture}"));
+        assert_eq!(
+            tikz.matches("\\node[pace leaf=").count(),
+            (instance.num_trees() * instance.num_leaves()) as usize
+        );
+        assert!(!tikz.contains(", pace cut edge]"));
+        assert!(!tikz.contains(", pace root]"));
+    }
+
+    #[test]
+    fn instance_and_solution() {
+        let tikz = render(|_| {});
+        assert!(tikz.contains("  pace cut edge/.style={dashed},"));
+        assert!(tikz.contains("  pace c2/.style={color={rgb,255:red,55;green,126;blue,184}},"));
+        assert!(tikz.contains(", pace cut edge]"));
+        assert!(tikz.contains(", pace root]"));
+        assert_eq!(
+            tikz.matches("\\draw[").count(),
+            tikz.matches("\\node[").count() - 2
+        );
+    }
+
+    #[test]
+    fn styles() {
+        let tikz = render(|writer| {
+            writer
+                .set_style(TikzStyle::CutEdge, "red, densely dashed")
+                .set_palette(Palette::Custom(vec!["gray".into(), "blue".into()]));
+        });
+        assert!(tikz.contains("  pace cut edge/.style={red, densely dashed},"));
+        assert!(tikz.contains("  pace c2/.style={color=blue},"));
+
+        let tikz = render(|writer| {
+            writer.set_emit_styles(false);
+        });
+        assert!(tikz.starts_with("\\begin{tikzpicture}\n"));
+        assert!(!tikz.contains("/.style"));
+        assert!(tikz.contains(", pace cut edge]"));
+    }
+}
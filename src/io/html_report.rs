@@ -0,0 +1,566 @@
+use crate::{
+    checks::{
+        bin_forest::BinForest,
+        checker::{CheckerError, check_inputs, claimed_score_mismatch},
+        flat_tree::FlatTree,
+        mismatch::Mismatch,
+    },
+    io::{
+        forest_svg_writer::ForestSvgWriter,
+        instance_reader::{Instance, InstanceInputVisitor},
+        limits::ReaderLimits,
+        mismatch_writer::MismatchWriter,
+        solution_reader::{Solution, SolutionInputVisitor},
+    },
+};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+pub use super::forest_coloring::Palette;
+
+/// Summary of an instance/solution check as a single HTML file without external assets,
+/// e.g. to be shared by organizers and solver authors. The report contains the header and
+/// stride metadata of both files, their digests (with feature `with_digest`), the score,
+/// a histogram of component sizes, all errors and warnings, and an SVG drawing of each
+/// instance tree with the cut edges of the solution. If the check fails due to a
+/// mismatch, the offending tree and component are drawn as by [`MismatchWriter`].
+///
+/// In contrast to [`check_instance_and_solution`](crate::checks::checker::check_instance_and_solution),
+/// the report collects all errors and warnings instead of stopping at the first one.
+///
+/// # Example
+/// ```
+/// use std::{io::stdout, path::Path};
+/// use pace26checker::io::html_report::HtmlReport;
+///
+/// let report = HtmlReport::check(
+///     Path::new("testcases/tiny/tiny01.in"),
+///     Path::new("testcases/tiny/tiny01.out"),
+/// );
+/// assert!(report.is_feasible());
+/// report.write(&mut stdout().lock()).unwrap();
+/// ```
+pub struct HtmlReport {
+    title: String,
+    palette: Palette,
+    instance: Option<Instance>,
+    solution: Option<Solution>,
+    forests: Option<Vec<BinForest>>,
+    mismatch: Option<Mismatch>,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl HtmlReport {
+    /// Reads and checks the instance and solution at the given paths
+    pub fn check(instance_path: &Path, solution_path: &Path) -> Self {
+        let title = format!("{} / {}", instance_path.display(), solution_path.display());
+        let open = |path: &Path| File::open(path).map(BufReader::new);
+
+        let mut report = match (open(instance_path), open(solution_path)) {
+            (Ok(instance), Ok(solution)) => Self::check_from(instance, solution),
+            (instance, solution) => {
+                let mut report = Self::empty();
+                for (path, error) in [
+                    (instance_path, instance.err()),
+                    (solution_path, solution.err()),
+                ] {
+                    if let Some(error) = error {
+                        report
+                            .errors
+                            .push(format!("Cannot open {}: {error}", path.display()));
+                    }
+                }
+                report
+            }
+        };
+        report.title = title;
+        report
+    }
+
+    /// Reads and checks an instance and a solution as
+    /// [`check_instance_and_solution_from`](crate::checks::checker::check_instance_and_solution_from)
+    /// does, but collects all messages of the readers
+    pub fn check_from(instance_reader: impl BufRead, solution_reader: impl BufRead) -> Self {
+        let limits = ReaderLimits::default();
+        let report = RefCell::new(Self::empty());
+
+        // reading errors are already among the messages of the visitor, unless they concern
+        // the input as a whole (e.g. a missing tree)
+        let outcome = check_inputs(
+            || {
+                let visitor = InstanceInputVisitor::process_with_limits(instance_reader, limits);
+                let num_errors = visitor.errors.len();
+                report
+                    .borrow_mut()
+                    .collect(&visitor.errors, &visitor.warnings);
+                Instance::from_visitor(visitor, false).map_err(|e| {
+                    report.borrow_mut().collect_error(num_errors, &e);
+                    e.into()
+                })
+            },
+            |num_leaves| {
+                let visitor =
+                    SolutionInputVisitor::process_with_limits(solution_reader, num_leaves, limits);
+                let num_errors = visitor.errors.len();
+                report
+                    .borrow_mut()
+                    .collect(&visitor.errors, &visitor.warnings);
+                match Solution::from_visitor(visitor, false) {
+                    Ok(solution) => Ok((solution, ())),
+                    Err(e) => {
+                        report.borrow_mut().collect_error(num_errors, &e);
+                        Err(e.into())
+                    }
+                }
+            },
+            false,
+            true,
+            &mut (),
+        );
+
+        let mut report = report.into_inner();
+        let solution = outcome.solution.map(|(solution, ())| solution);
+        if let Some(mismatch) = solution.as_ref().and_then(claimed_score_mismatch) {
+            report.warnings.push(mismatch.to_string());
+        }
+
+        match outcome.forests {
+            Ok(forests) => report.forests = Some(forests),
+            Err(CheckerError::InstanceReaderError(_) | CheckerError::SolutionReaderError(_)) => {}
+            Err(e) => {
+                if let (Some(instance), Some(solution)) = (&outcome.instance, &solution) {
+                    report.mismatch = Mismatch::from_error(instance, solution, &e);
+                }
+                report.errors.push(e.to_string());
+            }
+        }
+
+        report.instance = outcome.instance;
+        report.solution = solution;
+        report
+    }
+
+    fn empty() -> Self {
+        Self {
+            title: "Check report".to_string(),
+            palette: Palette::Generated,
+            instance: None,
+            solution: None,
+            forests: None,
+            mismatch: None,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn collect(&mut self, errors: &[impl ToString], warnings: &[impl ToString]) {
+        self.errors.extend(errors.iter().map(ToString::to_string));
+        self.warnings
+            .extend(warnings.iter().map(ToString::to_string));
+    }
+
+    /// Adds the error of a reader unless the visitor already reported `num_errors` errors
+    fn collect_error(&mut self, num_errors: usize, error: &impl ToString) {
+        if num_errors == 0 {
+            self.errors.push(error.to_string());
+        }
+    }
+
+    pub fn set_title(&mut self, title: impl Into<String>) -> &mut Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Palette of the tree drawings; defaults to [`Palette::Generated`]
+    pub fn set_palette(&mut self, palette: Palette) -> &mut Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Returns whether the solution was read and is a feasible solution of the instance
+    pub fn is_feasible(&self) -> bool {
+        self.forests.is_some()
+    }
+
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Writes the report as a standalone HTML document
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(writer, "<html lang=\"en\">")?;
+        writeln!(writer, "<head>")?;
+        writeln!(writer, "<meta charset=\"utf-8\">")?;
+        writeln!(writer, "<title>{}</title>", escape(&self.title))?;
+        writeln!(writer, "<style>{STYLE}</style>")?;
+        writeln!(writer, "</head>")?;
+        writeln!(writer, "<body>")?;
+        writeln!(writer, "<h1>{}</h1>", escape(&self.title))?;
+
+        let verdict = match (&self.solution, self.is_feasible()) {
+            (Some(solution), true) => format!(
+                "<p class=\"verdict ok\">Feasible solution with score {}</p>",
+                solution.num_trees()
+            ),
+            (Some(_), false) => "<p class=\"verdict fail\">Infeasible solution</p>".to_string(),
+            (None, _) => "<p class=\"verdict fail\">Not checked</p>".to_string(),
+        };
+        writeln!(writer, "{verdict}")?;
+
+        for (heading, class, messages) in [
+            ("Errors", "error", &self.errors),
+            ("Warnings", "warning", &self.warnings),
+        ] {
+            if messages.is_empty() {
+                continue;
+            }
+            writeln!(writer, "<h2>{heading}</h2>")?;
+            writeln!(writer, "<ul class=\"{class}\">")?;
+            for message in messages {
+                writeln!(writer, "<li>{}</li>", escape(message))?;
+            }
+            writeln!(writer, "</ul>")?;
+        }
+
+        if let Some(instance) = &self.instance {
+            self.write_instance(writer, instance)?;
+        }
+        if let Some(solution) = &self.solution {
+            self.write_solution(writer, solution)?;
+        }
+        #[cfg(feature = "with_digest")]
+        self.write_digests(writer)?;
+        if let Some(solution) = &self.solution {
+            write_histogram(writer, solution)?;
+        }
+        self.write_trees(writer)?;
+
+        writeln!(writer, "</body>")?;
+        writeln!(writer, "</html>")?;
+        Ok(())
+    }
+
+    fn write_instance(
+        &self,
+        writer: &mut impl Write,
+        instance: &Instance,
+    ) -> Result<(), std::io::Error> {
+        let mut rows = vec![
+            ("Trees".to_string(), instance.num_trees().to_string()),
+            ("Leaves".to_string(), instance.num_leaves().to_string()),
+        ];
+        if let Some((a, b)) = instance.approx() {
+            rows.push(("Approximation".to_string(), format!("a={a}, b={b}")));
+        }
+
+        writeln!(writer, "<h2>Instance</h2>")?;
+        write_table(writer, &rows)?;
+        write_stride_lines(writer, &instance.stride_lines)
+    }
+
+    fn write_solution(
+        &self,
+        writer: &mut impl Write,
+        solution: &Solution,
+    ) -> Result<(), std::io::Error> {
+        let mut rows = vec![("Score".to_string(), solution.num_trees().to_string())];
+        if let Some(claimed) = solution.metadata().score {
+            rows.push(("Claimed score".to_string(), claimed.to_string()));
+        }
+
+        writeln!(writer, "<h2>Solution</h2>")?;
+        write_table(writer, &rows)?;
+        write_stride_lines(writer, &solution.stride_lines)
+    }
+
+    #[cfg(feature = "with_digest")]
+    fn write_digests(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        use crate::digest::{
+            algo::{
+                digest_instance_with_version, digest_solution_with_version, trees_for_digest,
+                verify_instance_digest,
+            },
+            version::DigestVersion,
+        };
+
+        let mut rows = Vec::new();
+        if let Some(instance) = &self.instance {
+            let version = DigestVersion::LATEST;
+            let digest = digest_instance_with_version(
+                trees_for_digest(instance.trees()),
+                instance.num_leaves(),
+                instance.approx(),
+                version,
+            );
+            rows.push((
+                "Instance".to_string(),
+                format!("{digest} {}", digest.digest().explain()),
+            ));
+
+            if let Some(declared) = &instance.metadata().idigest {
+                let status = match verify_instance_digest(instance, declared) {
+                    Ok(_) => "verified".to_string(),
+                    Err(e) => e.to_string(),
+                };
+                rows.push((
+                    "Declared instance".to_string(),
+                    format!("{declared} ({status})"),
+                ));
+            }
+        }
+
+        if let (Some(solution), true) = (&self.solution, self.is_feasible()) {
+            let version = DigestVersion::LATEST;
+            let digest = digest_solution_with_version(
+                trees_for_digest(solution.trees()),
+                solution.num_trees() as u32,
+                version,
+            );
+            rows.push((
+                "Solution".to_string(),
+                format!("{digest} {}", digest.digest().explain()),
+            ));
+        }
+
+        if !rows.is_empty() {
+            writeln!(writer, "<h2>Digests</h2>")?;
+            write_table(writer, &rows)?;
+        }
+        Ok(())
+    }
+
+    fn write_trees(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        if let (Some(instance), Some(solution), Some(mismatch)) =
+            (&self.instance, &self.solution, &self.mismatch)
+        {
+            writeln!(writer, "<h2>Mismatch</h2>")?;
+            writeln!(writer, "<div class=\"figure\">")?;
+            MismatchWriter::new(instance, solution, mismatch).write_svg(writer)?;
+            writeln!(writer, "</div>")?;
+        }
+
+        let Some(instance) = &self.instance else {
+            return Ok(());
+        };
+
+        let mut svg = ForestSvgWriter::new(instance);
+        svg.set_palette(self.palette.clone());
+        if let (Some(solution), Some(forests)) = (&self.solution, &self.forests) {
+            svg.color_leafs(solution, forests);
+        }
+
+        writeln!(writer, "<h2>Trees</h2>")?;
+        for (i, (lineno, _)) in instance.trees().iter().enumerate() {
+            writeln!(writer, "<h3>Tree {} (line {})</h3>", i + 1, lineno + 1)?;
+            writeln!(writer, "<div class=\"figure\">")?;
+            svg.select_trees([i]);
+            svg.write(writer)?;
+            writeln!(writer, "</div>")?;
+        }
+        Ok(())
+    }
+}
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; color: #222; } \
+table { border-collapse: collapse; margin-bottom: 1em; } \
+td, th { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; } \
+.verdict { font-size: 1.3em; font-weight: bold; } \
+.ok { color: #2e7d32; } .fail, .error { color: #c62828; } .warning { color: #ef6c00; } \
+.bar { background: #377eb8; height: 0.9em; } \
+.figure { overflow-x: auto; }";
+
+fn write_table(writer: &mut impl Write, rows: &[(String, String)]) -> Result<(), std::io::Error> {
+    writeln!(writer, "<table>")?;
+    for (key, value) in rows {
+        writeln!(
+            writer,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            escape(key),
+            escape(value)
+        )?;
+    }
+    writeln!(writer, "</table>")
+}
+
+fn write_stride_lines(
+    writer: &mut impl Write,
+    stride_lines: &[(String, serde_json::Value)],
+) -> Result<(), std::io::Error> {
+    if stride_lines.is_empty() {
+        return Ok(());
+    }
+    let rows: Vec<_> = stride_lines
+        .iter()
+        .map(|(key, value)| (key.clone(), value.to_string()))
+        .collect();
+    writeln!(writer, "<h3>Stride metadata</h3>")?;
+    write_table(writer, &rows)
+}
+
+fn write_histogram(writer: &mut impl Write, solution: &Solution) -> Result<(), std::io::Error> {
+    let mut histogram = BTreeMap::new();
+    for (_, tree) in solution.trees() {
+        *histogram
+            .entry(FlatTree::new(tree).leaves().count())
+            .or_insert(0usize) += 1;
+    }
+    let max = histogram.values().copied().max().unwrap_or(1);
+
+    writeln!(writer, "<h2>Component sizes</h2>")?;
+    writeln!(writer, "<table>")?;
+    writeln!(
+        writer,
+        "<tr><th>Leaves</th><th>Components</th><th></th></tr>"
+    )?;
+    for (size, count) in histogram {
+        writeln!(
+            writer,
+            "<tr><td>{size}</td><td>{count}</td><td style=\"width: 20em\"><div class=\"bar\" style=\"width: {:.1}%\"></div></td></tr>",
+            100.0 * count as f64 / max as f64
+        )?;
+    }
+    writeln!(writer, "</table>")
+}
+
+/// Escapes text for use in HTML element content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::tests::test_instances_directory;
+
+    fn render(report: &HtmlReport) -> String {
+        let mut buffer: Vec<u8> = Vec::new();
+        report.write(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn feasible() {
+        let dir = test_instances_directory("tiny");
+        let report = HtmlReport::check(&dir.join("tiny01.in"), &dir.join("tiny01.out"));
+        assert!(report.is_feasible());
+        assert!(report.errors().is_empty());
+
+        let html = render(&report);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Feasible solution with score 4"));
+        assert!(html.contains(
+            "<tr><th>idigest</th><td>&quot;0010b172a28d0664d5521e1296fc3586&quot;</td></tr>"
+        ));
+        assert_eq!(html.matches("<svg ").count(), 2);
+        assert!(html.contains("stroke-dasharray=\"6 4\""));
+        // no external assets
+        assert!(!html.contains("src=") && !html.contains("href="));
+
+        // sizes 2, 1, 1, 2
+        assert!(html.contains("<tr><td>1</td><td>2</td>"));
+        assert!(html.contains("<tr><td>2</td><td>2</td>"));
+
+        #[cfg(feature = "with_digest")]
+        assert!(html.contains("0010b172a28d0664d5521e1296fc3586 (verified)"));
+    }
+
+    #[test]
+    fn infeasible() {
+        let dir = test_instances_directory("invalid");
+        let report = HtmlReport::check(
+            &dir.join("score1_sa2e2l7j.in"),
+            &dir.join("score1_sa2e2l7j.out"),
+        );
+        assert!(!report.is_feasible());
+        assert_eq!(report.errors().len(), 1);
+
+        // the report agrees with the checker
+        let error = crate::checks::checker::check_instance_and_solution(
+            &dir.join("score1_sa2e2l7j.in"),
+            &dir.join("score1_sa2e2l7j.out"),
+            false,
+            false,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(report.errors()[0], error.to_string());
+
+        let html = render(&report);
+        assert!(html.contains("Infeasible solution"));
+        assert!(html.contains("<h2>Mismatch</h2>"));
+        assert!(html.contains("Failed to match solution subtree"));
+    }
+
+    #[test]
+    fn deep_caterpillar() {
+        // far deeper than the stack of a test thread permits for recursion
+        let num_leaves = 50_000;
+        let mut tree = "(".repeat(num_leaves - 2);
+        tree.push('1');
+        for i in 2..num_leaves {
+            tree.push_str(&format!(",{i})"));
+        }
+        let instance =
+            format!("#p 2 {num_leaves}\n({tree},{num_leaves});\n({num_leaves},{tree});\n");
+        let solution = format!("{tree};\n{num_leaves};\n");
+
+        let report = HtmlReport::check_from(instance.as_bytes(), solution.as_bytes());
+        assert!(report.is_feasible(), "{:?}", report.errors());
+        let html = render(&report);
+        assert_eq!(html.matches("<svg ").count(), 2);
+        assert_eq!(html.matches("stroke-dasharray=\"6 4\"").count(), 4);
+    }
+
+    #[test]
+    fn warnings_and_errors() {
+        let instance = b"#p 1 3\n((1,2),3);\n#s name \"<b>\"\n";
+        let solution = b"#s score 1\n(1,2); \n3;\n";
+        let report = HtmlReport::check_from(&instance[..], &solution[..]);
+        assert!(report.is_feasible());
+        assert_eq!(report.warnings().len(), 2, "{:?}", report.warnings());
+        assert!(render(&report).contains("&quot;&lt;b&gt;&quot;"));
+
+        // the claimed score is compared even if the solution is infeasible
+        let report = HtmlReport::check_from(&instance[..], &b"#s score 2\n((1,3),2);\n"[..]);
+        assert!(!report.is_feasible());
+        assert!(
+            report
+                .warnings()
+                .iter()
+                .any(|w| w.contains("claims score 2")),
+            "{:?}",
+            report.warnings()
+        );
+
+        let report = HtmlReport::check_from(&b"#p 1 3\n((1,2),3;\n"[..], &solution[..]);
+        assert!(!report.is_feasible());
+        assert_eq!(report.errors().len(), 2, "{:?}", report.errors());
+        assert!(render(&report).contains("Not checked"));
+
+        let dir = test_instances_directory("tiny");
+        let report = HtmlReport::check(&dir.join("tiny01.in"), &dir.join("missing.out"));
+        assert!(report.errors()[0].starts_with("Cannot open"));
+    }
+}
@@ -1,4 +1,7 @@
 use crate::checks::{bin_tree_with_parent::BinTreeWithParentBuilder, lint_leaf_labels_coverage::*};
+use crate::io::limits::*;
+use crate::io::metadata::InstanceMetadata;
+use crate::io::newick_parser::*;
 
 use std::{
     fs::File,
@@ -7,7 +10,7 @@ use std::{
 };
 
 use pace26io::pace::parameters::tree_decomposition::TreeDecomposition;
-use pace26io::{binary_tree::NodeIdx, newick::*, pace::reader::*};
+use pace26io::{binary_tree::NodeIdx, pace::reader::*};
 use thiserror::Error;
 use tracing::{debug, error, warn};
 
@@ -21,12 +24,15 @@ pub enum InstanceReaderError {
     VisitorError(#[from] InstanceVisitorError),
     #[error("Warning while reading instance (paranoid mode): {0}")]
     VisitorWarning(#[from] InstanceVisitorWarning),
+    #[error("Instance rejected: {0}")]
+    LimitExceeded(LimitExceeded),
 }
 
 pub struct Instance {
     pub trees: Vec<(usize, Tree)>,
     pub approx: Option<(f64, u32)>,
     pub stride_lines: Vec<(String, serde_json::Value)>,
+    pub metadata: InstanceMetadata,
     pub tree_decomposition: Option<(usize, TreeDecomposition)>,
     pub num_leaves: u32,
 }
@@ -42,6 +48,7 @@ impl Clone for Instance {
         Self {
             trees,
             stride_lines: self.stride_lines.clone(),
+            metadata: self.metadata.clone(),
             num_leaves: self.num_leaves,
             tree_decomposition: self.tree_decomposition.clone(),
             approx: self.approx,
@@ -66,9 +73,30 @@ impl Instance {
         self.approx
     }
 
+    pub fn metadata(&self) -> &InstanceMetadata {
+        &self.metadata
+    }
+
+    /// Reads an instance subject to the default [`ReaderLimits`].
     pub fn read_from(reader: impl BufRead, paranoid: bool) -> Result<Self, InstanceReaderError> {
-        let mut visitor = InstanceInputVisitor::process(reader);
+        Self::read_from_with_limits(reader, paranoid, ReaderLimits::default())
+    }
 
+    pub fn read_from_with_limits(
+        reader: impl BufRead,
+        paranoid: bool,
+        limits: ReaderLimits,
+    ) -> Result<Self, InstanceReaderError> {
+        let visitor = InstanceInputVisitor::process_with_limits(reader, limits);
+        Self::from_visitor(visitor, paranoid)
+    }
+
+    /// Builds the instance from a completed visitor; errors and warnings are logged, and the
+    /// first one (warnings only in paranoid mode) is returned.
+    pub(crate) fn from_visitor(
+        mut visitor: InstanceInputVisitor,
+        paranoid: bool,
+    ) -> Result<Self, InstanceReaderError> {
         if !visitor.errors.is_empty() || !visitor.warnings.is_empty() {
             for w in &visitor.warnings {
                 warn!(" {w}");
@@ -79,7 +107,16 @@ impl Instance {
             }
 
             if !visitor.errors.is_empty() {
-                return Err(InstanceReaderError::VisitorError(visitor.errors.remove(0)));
+                // a limit hit aborts reading, so it is reported even if other errors came first
+                let first = visitor
+                    .errors
+                    .iter()
+                    .position(|e| matches!(e, InstanceVisitorError::LimitExceeded(_)))
+                    .unwrap_or(0);
+                return Err(match visitor.errors.swap_remove(first) {
+                    InstanceVisitorError::LimitExceeded(e) => InstanceReaderError::LimitExceeded(e),
+                    e => InstanceReaderError::VisitorError(e),
+                });
             }
 
             if paranoid {
@@ -89,9 +126,16 @@ impl Instance {
             }
         }
 
+        let Some((_, num_leaves)) = visitor.header else {
+            return Err(InstanceReaderError::VisitorError(
+                InstanceVisitorError::NoHeaderFound,
+            ));
+        };
+
         Ok(Self {
-            num_leaves: visitor.header.unwrap().1,
+            num_leaves,
             stride_lines: visitor.stride_lines,
+            metadata: visitor.metadata,
             trees: visitor.trees,
             tree_decomposition: visitor.tree_decomposition,
             approx: visitor.approx,
@@ -115,8 +159,12 @@ pub struct InstanceInputVisitor {
     pub header: Option<(u32, u32)>,
     pub trees: Vec<(usize, Tree)>,
     pub stride_lines: Vec<(String, serde_json::Value)>,
+    pub metadata: InstanceMetadata,
     pub tree_decomposition: Option<(usize, TreeDecomposition)>,
     pub approx: Option<(f64, u32)>,
+    pub limits: ReaderLimits,
+    /// Line number and value of the `#s idigest` stride line, if present
+    pub declared_idigest: Option<(usize, serde_json::Value)>,
     next_root: NodeIdx,
 }
 
@@ -128,10 +176,13 @@ pub enum InstanceVisitorError {
     #[error("No header found in the input")]
     NoHeaderFound,
 
+    #[error("Line {} contains a second header", lineno + 1)]
+    MultipleHeaders { lineno: usize },
+
     #[error("Line {} contains invalid Newick string: {newick_error}", lineno + 1)]
     InvalidNewick {
         lineno: usize,
-        newick_error: ParserError,
+        newick_error: NewickError,
     },
 
     #[error("Header indicates {expected} trees, but found {found}")]
@@ -160,6 +211,12 @@ pub enum InstanceVisitorError {
         source: serde_json::Error,
     },
 
+    #[error(transparent)]
+    LimitExceeded(#[from] LimitExceeded),
+
+    #[error(transparent)]
+    MalformedInput(#[from] MalformedInput),
+
     #[error(transparent)]
     PaceParserError(#[from] ReaderError),
 }
@@ -171,24 +228,59 @@ pub enum InstanceVisitorWarning {
 
     #[error("Param A of approx line {} has should be in range 1.0 <= a < 1.5. Found: a={}", param_a, lineno + 1)]
     ParamARange { param_a: f64, lineno: usize },
+
+    #[error("Stride line {} has value of unexpected type for key `{key}`: {message}", lineno + 1)]
+    InvalidMetadata {
+        lineno: usize,
+        key: String,
+        message: String,
+    },
+
+    #[cfg(feature = "with_digest")]
+    #[error("Line {} contains an idigest that is not a valid digest string", lineno + 1)]
+    MalformedIdigest { lineno: usize },
+
+    #[cfg(feature = "with_digest")]
+    #[error("Line {} declares idigest {declared}, but the instance has digest {computed}", lineno + 1)]
+    IdigestMismatch {
+        lineno: usize,
+        declared: crate::digest::version::VersionedInstanceDigest,
+        computed: crate::digest::version::VersionedInstanceDigest,
+    },
 }
 
 impl InstanceVisitor for InstanceInputVisitor {
-    fn visit_header(&mut self, _lineno: usize, num_trees: usize, num_leafs: usize) -> Action {
-        assert!(self.header.is_none()); // double headers should be caught by the parser
+    fn visit_header(&mut self, lineno: usize, num_trees: usize, num_leafs: usize) -> Action {
+        if self.header.is_some() {
+            // double headers should be caught by the parser
+            self.errors
+                .push(InstanceVisitorError::MultipleHeaders { lineno });
+            return Action::Terminate;
+        }
+
+        if let Err(e) = self.limits.check_header(num_trees, num_leafs) {
+            self.errors.push(e.into());
+            return Action::Terminate;
+        }
+
         self.header = Some((num_trees as u32, num_leafs as u32));
-        self.next_root = NodeIdx(1 + num_leafs as u32);
+        self.next_root = NodeIdx((num_leafs as u32).saturating_add(1));
         Action::Continue
     }
 
     fn visit_tree(&mut self, lineno: usize, line: &str) -> Action {
+        if let Err(e) = self.limits.check_num_trees(self.trees.len() + 1) {
+            self.errors.push(e.into());
+            return Action::Terminate;
+        }
+
         if self.header.is_none() {
             self.errors
                 .push(InstanceVisitorError::NoHeaderBeforeFirstTree { lineno });
         }
 
         let mut builder = BinTreeWithParentBuilder::default();
-        match builder.parse_newick_from_str(line, self.next_root) {
+        match parse_newick(&mut builder, line, self.next_root) {
             Ok(tree) => self.trees.push((lineno, tree)),
             Err(e) => {
                 self.errors.push(InstanceVisitorError::InvalidNewick {
@@ -198,7 +290,10 @@ impl InstanceVisitor for InstanceInputVisitor {
             }
         }
 
-        self.next_root.0 += self.header.map(|h| h.1.saturating_sub(1)).unwrap_or(0);
+        self.next_root.0 = self
+            .next_root
+            .0
+            .saturating_add(self.header.map(|h| h.1.saturating_sub(1)).unwrap_or(0));
 
         Action::Continue
     }
@@ -240,6 +335,16 @@ impl InstanceVisitor for InstanceInputVisitor {
     fn visit_stride_line(&mut self, lineno: usize, _line: &str, key: &str, value: &str) -> Action {
         match serde_json::from_str::<serde_json::Value>(value) {
             Ok(json_value) => {
+                if key == "idigest" {
+                    self.declared_idigest = Some((lineno, json_value.clone()));
+                }
+                if let Err(e) = self.metadata.insert(key, &json_value) {
+                    self.warnings.push(InstanceVisitorWarning::InvalidMetadata {
+                        lineno,
+                        key: key.to_string(),
+                        message: e.to_string(),
+                    });
+                }
                 self.stride_lines.push((key.to_string(), json_value));
             }
             Err(e) => {
@@ -260,17 +365,37 @@ impl InstanceVisitor for InstanceInputVisitor {
 
 impl InstanceInputVisitor {
     pub fn process(reader: impl BufRead) -> InstanceInputVisitor {
-        let mut visitor = InstanceInputVisitor::default();
+        Self::process_with_limits(reader, ReaderLimits::default())
+    }
+
+    pub fn process_with_limits(reader: impl BufRead, limits: ReaderLimits) -> InstanceInputVisitor {
+        let mut visitor = InstanceInputVisitor {
+            limits,
+            ..Default::default()
+        };
         let mut instance_reader = InstanceReader::new(&mut visitor);
 
-        if let Err(e) = instance_reader.read(reader) {
-            visitor
-                .errors
-                .push(InstanceVisitorError::PaceParserError(e));
+        if let Err(e) = instance_reader.read(LimitedReader::new(reader, limits)) {
+            let error = match &e {
+                ReaderError::IO(io) => LimitExceeded::from_io_error(io)
+                    .map(InstanceVisitorError::LimitExceeded)
+                    .or_else(|| MalformedInput::from_io_error(io).map(Into::into))
+                    .unwrap_or(InstanceVisitorError::PaceParserError(e)),
+                _ => InstanceVisitorError::PaceParserError(e),
+            };
+            visitor.errors.push(error);
 
             return visitor;
         }
 
+        if matches!(
+            visitor.errors.last(),
+            Some(InstanceVisitorError::LimitExceeded(_))
+        ) {
+            // reading was terminated early, so further checks are meaningless
+            return visitor;
+        }
+
         if let Some((num_trees, num_leaves)) = visitor.header {
             if num_trees as usize != visitor.trees.len() {
                 visitor
@@ -299,8 +424,51 @@ impl InstanceInputVisitor {
             visitor.errors.push(InstanceVisitorError::NoHeaderFound);
         }
 
+        #[cfg(feature = "with_digest")]
+        if visitor.errors.is_empty() {
+            visitor.verify_idigest();
+        }
+
         visitor
     }
+
+    /// Compares the declared `#s idigest` (if any) with the digest of the instance read.
+    /// The digest is computed with the scheme of the version declared.
+    #[cfg(feature = "with_digest")]
+    fn verify_idigest(&mut self) {
+        use crate::digest::{
+            algo::{digest_instance_with_version, trees_for_digest},
+            version::VersionedInstanceDigest,
+        };
+
+        let (Some((lineno, declared)), Some((_, num_leaves))) =
+            (&self.declared_idigest, self.header)
+        else {
+            return;
+        };
+        let lineno = *lineno;
+
+        let Some(declared) = declared
+            .as_str()
+            .and_then(|d| VersionedInstanceDigest::try_from(d).ok())
+        else {
+            self.warnings
+                .push(InstanceVisitorWarning::MalformedIdigest { lineno });
+            return;
+        };
+
+        let trees = trees_for_digest(&self.trees);
+        let computed =
+            digest_instance_with_version(trees, num_leaves, self.approx, declared.version());
+
+        if computed != declared {
+            self.warnings.push(InstanceVisitorWarning::IdigestMismatch {
+                lineno,
+                declared,
+                computed,
+            });
+        }
+    }
 }
 
 #[cfg(test)]
@@ -345,6 +513,160 @@ mod tests {
         };
     }
 
+    #[cfg(feature = "with_digest")]
+    #[test]
+    fn idigest() {
+        let data = std::fs::read_to_string("testcases/tiny/tiny01.in").unwrap();
+        let visitor = InstanceInputVisitor::process(data.as_bytes());
+        assert!(visitor.errors.is_empty());
+        assert!(visitor.warnings.is_empty(), "{:?}", visitor.warnings);
+        assert_eq!(visitor.declared_idigest.map(|(lineno, _)| lineno), Some(0));
+
+        // the digest does not depend on the order of trees and children
+        let reordered = data.replace(
+            "(((5,6),(3,4)),(1,2));\n(((((4,2),1),5),3),6);",
+            "(6,(3,(5,(1,(2,4)))));\n((2,1),((4,3),(6,5)));",
+        );
+        assert!(Instance::read_from(reordered.as_bytes(), true).is_ok());
+
+        // without an approx line, both versions agree on the digest itself
+        let versioned = data.replace("\"0010b172", "\"010010b172");
+        assert!(Instance::read_from(versioned.as_bytes(), true).is_ok());
+    }
+
+    #[cfg(feature = "with_digest")]
+    #[test]
+    fn idigest_mismatch() {
+        let data = std::fs::read_to_string("testcases/tiny/tiny01.in").unwrap();
+        let modified = data.replace("(((5,6),(3,4)),(1,2));", "(((1,6),(3,4)),(5,2));");
+        let visitor = InstanceInputVisitor::process(modified.as_bytes());
+        assert!(visitor.errors.is_empty(), "{:?}", visitor.errors);
+        assert!(matches!(
+            visitor.warnings[..],
+            [InstanceVisitorWarning::IdigestMismatch { lineno: 0, .. }]
+        ));
+
+        assert!(Instance::read_from(modified.as_bytes(), false).is_ok());
+        assert!(matches!(
+            Instance::read_from(modified.as_bytes(), true),
+            Err(InstanceReaderError::VisitorWarning(
+                InstanceVisitorWarning::IdigestMismatch { .. }
+            ))
+        ));
+
+        for malformed in [
+            data.replace("0010b172", "0010b17"),
+            data.replace("\"0010b172", "\"7f0010b172"),
+        ] {
+            let visitor = InstanceInputVisitor::process(malformed.as_bytes());
+            assert!(matches!(
+                visitor.warnings[..],
+                [InstanceVisitorWarning::MalformedIdigest { lineno: 0 }]
+            ));
+        }
+    }
+
+    #[test]
+    fn metadata() {
+        let instance = Instance::read(Path::new("testcases/tiny/tiny01.in"), true)
+            .ok()
+            .unwrap();
+        let metadata = instance.metadata();
+        assert_eq!(metadata.name.as_deref(), Some("tiny01"));
+        assert_eq!(
+            metadata.desc.as_deref(),
+            Some("Example shown on https://pacechallenge.org")
+        );
+        assert_eq!(
+            metadata.idigest.as_deref(),
+            Some("0010b172a28d0664d5521e1296fc3586")
+        );
+        assert!(metadata.extra.is_empty());
+    }
+
+    assert_raises_warning!(
+        metadata_wrong_type,
+        b"#s seed 5\n#s name 5\n#p 1 2\n(1,2);",
+        InstanceVisitorWarning::InvalidMetadata { lineno: 1, .. }
+    );
+
+    #[test]
+    fn limit_leaves() {
+        let err = Instance::read_from(&b"#p 2 4000000000\n"[..], false)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            InstanceReaderError::LimitExceeded(LimitExceeded::Leaves {
+                found: 4000000000,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn limit_trees() {
+        let limits = ReaderLimits {
+            max_trees: 1,
+            ..Default::default()
+        };
+
+        let err = Instance::read_from_with_limits(&b"#p 2 2\n(1,2);\n(1,2);\n"[..], false, limits)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            InstanceReaderError::LimitExceeded(LimitExceeded::Trees { found: 2, limit: 1 })
+        ));
+
+        // the header may lie about the number of trees
+        let err = Instance::read_from_with_limits(&b"#p 1 2\n(1,2);\n(1,2);\n"[..], false, limits)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            InstanceReaderError::LimitExceeded(LimitExceeded::Trees { limit: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn limit_line_length() {
+        let limits = ReaderLimits {
+            max_line_length: 16,
+            ..Default::default()
+        };
+
+        let data = b"#p 1 4\n((1,2),(3,4));\n";
+        assert!(Instance::read_from_with_limits(&data[..], false, limits).is_ok());
+
+        let data = b"#p 1 8\n(((1,2),(3,4)),((5,6),(7,8)));\n";
+        let err = Instance::read_from_with_limits(&data[..], false, limits)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            InstanceReaderError::LimitExceeded(LimitExceeded::LineLength { lineno: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn limit_after_parse_error() {
+        let limits = ReaderLimits {
+            max_trees: 1,
+            ..Default::default()
+        };
+
+        // the invalid tree in line 2 precedes the surplus tree
+        let data = b"#p 1 2\n(1,2;\n(1,2);\n(1,2);\n";
+        let err = Instance::read_from_with_limits(&data[..], false, limits)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            InstanceReaderError::LimitExceeded(LimitExceeded::Trees { limit: 1, .. })
+        ));
+    }
+
     assert_raises_error!(
         missing_tree,
         b"#p 2 2\n(1,2);",
@@ -412,6 +734,21 @@ mod tests {
         InstanceVisitorError::InvalidNewick { lineno: 2, .. }
     );
 
+    assert_raises_error!(
+        label_overflow,
+        b"#p 1 2\n(1,4294967296);",
+        InstanceVisitorError::InvalidNewick {
+            lineno: 1,
+            newick_error: NewickError::LabelOverflow { offset: 3 }
+        }
+    );
+
+    assert_raises_error!(
+        missing_key,
+        b"#p 1 2\n#s\n(1,2);",
+        InstanceVisitorError::MalformedInput(MalformedInput::MissingKey { lineno: 1 })
+    );
+
     assert_raises_error!(
         multiple_approx_lines,
         b"#a 1.23 42\n#p 1 1\n#a 1.2 35\n(0,1);\n();",
@@ -0,0 +1,594 @@
+use crate::checks::{
+    bin_forest::{BinForest, IsolationStep},
+    checker::IsolationObserver,
+};
+use pace26io::binary_tree::*;
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use super::{
+    forest_layout::TreeLayout,
+    svg::{self, DX, DY, EdgeStyle, LABEL_SPACE, MARGIN, NodeStyle, TITLE_SPACE},
+};
+
+/// Nodes of the subtree isolated in this step
+const ISOLATED: &str = svg::BLUE;
+/// Nodes of the siblings cut off in this step
+const CUT: &str = svg::RED;
+const NEUTRAL: &str = svg::GRAY;
+
+/// File format of the frames written by [`IsolationFrameWriter`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// GraphViz Dot, e.g. to be rendered with `dot -Tpng`
+    #[default]
+    Dot,
+    /// Standalone SVG images
+    Svg,
+}
+
+impl FrameFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            FrameFormat::Dot => "dot",
+            FrameFormat::Svg => "svg",
+        }
+    }
+}
+
+/// Role of a node in a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marking {
+    Plain,
+    Isolated,
+    Cut,
+}
+
+impl Marking {
+    fn color(self) -> &'static str {
+        match self {
+            Marking::Plain => NEUTRAL,
+            Marking::Isolated => ISOLATED,
+            Marking::Cut => CUT,
+        }
+    }
+}
+
+/// Records the intermediate forests of a check as numbered files `frame_0001.dot`,
+/// `frame_0002.dot`, ... in a directory, one frame after each isolation of a solution tree
+/// within an instance tree. This is intended for teaching and debugging the checker.
+///
+/// Each frame shows the forest of one instance tree after the isolation: the isolated subtree
+/// is drawn in blue, the siblings cut off while contracting paths towards it in red, and all
+/// other nodes in gray. As in [`ForestDotWriter`](super::forest_dot_writer::ForestDotWriter),
+/// edges into roots of the forest are dashed and the roots themselves are drawn as triangles
+/// (or outlined in SVG); inner nodes are labeled with their node ids. If a solution tree
+/// cannot be isolated, a final frame shows the forest right before the failed attempt with
+/// all nodes in gray.
+///
+/// # Example
+/// ```
+/// use std::{fs::File, io::BufReader};
+/// use pace26checker::{
+///     checks::checker::check_instance_and_solution_observed_from,
+///     io::{isolation_frame_writer::*, limits::ReaderLimits},
+/// };
+///
+/// let dir = std::env::temp_dir().join(format!("pace26_frames_doc_{}", std::process::id()));
+/// let mut frames = IsolationFrameWriter::new(&dir);
+/// frames.set_format(FrameFormat::Svg);
+///
+/// check_instance_and_solution_observed_from(
+///     BufReader::new(File::open("testcases/tiny/tiny01.in").unwrap()),
+///     BufReader::new(File::open("testcases/tiny/tiny01.out").unwrap()),
+///     false,
+///     false,
+///     ReaderLimits::default(),
+///     &mut frames,
+/// )
+/// .unwrap();
+///
+/// assert!(frames.frame_path(1).is_file());
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub struct IsolationFrameWriter {
+    directory: PathBuf,
+    format: FrameFormat,
+    num_frames: usize,
+    /// Drawing of the current forest, which is written once more if the next step fails
+    current: Option<Frame>,
+}
+
+impl IsolationFrameWriter {
+    /// Frames are written into `directory`, which is created if necessary. Frames of an
+    /// earlier run (i.e. files named like frames in the current format) are removed before
+    /// the first frame is written; other files are left untouched.
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            format: FrameFormat::default(),
+            num_frames: 0,
+            current: None,
+        }
+    }
+
+    pub fn set_format(&mut self, format: FrameFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    /// Number of frames written so far
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    /// Path of the `index`-th frame (1-based)
+    pub fn frame_path(&self, index: usize) -> PathBuf {
+        self.directory
+            .join(format!("frame_{index:04}.{}", self.format.extension()))
+    }
+}
+
+impl IsolationFrameWriter {
+    /// Writes `frame` as the `num_frames`-th frame
+    fn write_frame(&self, frame: &Frame) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(&self.directory)?;
+        if self.num_frames == 1 {
+            self.remove_stale_frames()?;
+        }
+
+        let mut writer = BufWriter::new(File::create(self.frame_path(self.num_frames))?);
+        match self.format {
+            FrameFormat::Dot => frame.write_dot(&mut writer)?,
+            FrameFormat::Svg => frame.write_svg(&mut writer)?,
+        }
+        writer.flush()
+    }
+
+    /// Removes all frames in the directory, e.g. those of a longer earlier run that would
+    /// otherwise appear to continue the current one
+    fn remove_stale_frames(&self) -> Result<(), std::io::Error> {
+        let extension = format!(".{}", self.format.extension());
+        for entry in std::fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let is_frame = name
+                .to_str()
+                .and_then(|name| name.strip_prefix("frame_"))
+                .and_then(|name| name.strip_suffix(extension.as_str()))
+                .is_some_and(|index| {
+                    !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())
+                });
+            if is_frame && entry.file_type()?.is_file() {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl IsolationObserver for IsolationFrameWriter {
+    fn on_begin(&mut self, _inst_lineno: usize, forest: &BinForest) -> Result<(), std::io::Error> {
+        self.current = Some(Frame::new(String::new(), forest, None));
+        Ok(())
+    }
+
+    fn on_isolate(
+        &mut self,
+        inst_lineno: usize,
+        sol_lineno: usize,
+        forest: &BinForest,
+        step: &IsolationStep,
+    ) -> Result<(), std::io::Error> {
+        self.num_frames += 1;
+
+        let title = format!(
+            "Frame {}: isolated solution tree in line {} from instance tree in line {}",
+            self.num_frames,
+            sol_lineno + 1,
+            inst_lineno + 1
+        );
+        let frame = Frame::new(title, forest, Some(step));
+        self.write_frame(&frame)?;
+        self.current = Some(frame);
+        Ok(())
+    }
+
+    fn on_mismatch(&mut self, inst_lineno: usize, sol_lineno: usize) -> Result<(), std::io::Error> {
+        let Some(mut frame) = self.current.take() else {
+            return Ok(());
+        };
+        self.num_frames += 1;
+
+        frame.title = format!(
+            "Frame {}: solution tree in line {} cannot be isolated from instance tree in line {}",
+            self.num_frames,
+            sol_lineno + 1,
+            inst_lineno + 1
+        );
+        for tree in &mut frame.trees {
+            tree.marking.fill(Marking::Plain);
+        }
+        self.write_frame(&frame)
+    }
+}
+
+/// Drawing of a forest, where each tree is a maximal subtree containing no other tree, i.e.
+/// roots of the forest nested within such a subtree are drawn in place
+struct Frame {
+    title: String,
+    trees: Vec<FrameTree>,
+}
+
+struct FrameTree {
+    layout: TreeLayout,
+    marking: Vec<Marking>,
+    is_root: Vec<bool>,
+}
+
+impl Frame {
+    /// Marks the isolated subtree and the cut siblings of `step`, if any
+    fn new(title: String, forest: &BinForest, step: Option<&IsolationStep>) -> Self {
+        let roots: HashSet<NodeIdx> = forest.roots().iter().map(|r| r.node_idx()).collect();
+        let cut: HashSet<NodeIdx> = step.iter().flat_map(|s| s.cut.iter().copied()).collect();
+        let isolated = step.map(|s| s.root);
+
+        // roots reachable from another root are drawn as part of that root's tree
+        let mut nested = HashSet::new();
+        for root in forest.roots() {
+            let mut stack = vec![root.clone()];
+            while let Some(u) = stack.pop() {
+                if let Some((l, r)) = u.children() {
+                    for child in [l, r] {
+                        if roots.contains(&child.node_idx()) {
+                            nested.insert(child.node_idx());
+                        } else {
+                            stack.push(child);
+                        }
+                    }
+                }
+            }
+        }
+
+        let trees = forest
+            .roots()
+            .iter()
+            .filter(|r| !nested.contains(&r.node_idx()))
+            .map(|root| {
+                let layout = TreeLayout::new(root);
+                let tree = layout.tree();
+
+                // nodes are stored in pre-order, so the parent's marking is already known
+                let mut marking = vec![Marking::Plain; tree.len()];
+                for (u, node) in tree.nodes().iter().enumerate() {
+                    marking[u] = if Some(node.node_idx) == isolated {
+                        Marking::Isolated
+                    } else if cut.contains(&node.node_idx) {
+                        Marking::Cut
+                    } else if roots.contains(&node.node_idx) {
+                        Marking::Plain
+                    } else {
+                        node.parent.map_or(Marking::Plain, |p| marking[p])
+                    };
+                }
+
+                let is_root = tree
+                    .nodes()
+                    .iter()
+                    .map(|node| roots.contains(&node.node_idx))
+                    .collect();
+
+                FrameTree {
+                    layout,
+                    marking,
+                    is_root,
+                }
+            })
+            .collect();
+
+        Self { title, trees }
+    }
+
+    fn write_dot(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        writeln!(writer, "digraph Frame {{")?;
+        writeln!(writer, " label=\"{}\";", self.title)?;
+        writeln!(writer, " labelloc=t;")?;
+
+        for (i, frame_tree) in self.trees.iter().enumerate() {
+            let tree = frame_tree.layout.tree();
+            let name = |u: usize| match tree.node(u).label {
+                Some(l) => format!("f{}l{}", i + 1, l.0),
+                None => format!("f{}v{}", i + 1, tree.node(u).node_idx.0),
+            };
+
+            writeln!(writer, "  subgraph f{} {{", i + 1)?;
+            for (u, node) in tree.nodes().iter().enumerate() {
+                let color = frame_tree.marking[u].color();
+                let (label, shape) = match node.label {
+                    Some(l) => (l.0.to_string(), "box"),
+                    None => (node.node_idx.0.to_string(), "ellipse"),
+                };
+                let shape = if frame_tree.is_root[u] {
+                    "triangle"
+                } else {
+                    shape
+                };
+                writeln!(
+                    writer,
+                    "  {} [label=\"{label}\", color=\"{color}\", shape=\"{shape}\"]",
+                    name(u)
+                )?;
+
+                if let Some(p) = node.parent {
+                    writeln!(
+                        writer,
+                        "  {} -> {} [color=\"{color}\"{}];",
+                        name(p),
+                        name(u),
+                        if frame_tree.is_root[u] {
+                            ", style=dashed"
+                        } else {
+                            ""
+                        }
+                    )?;
+                }
+            }
+            writeln!(writer, "  }}")?;
+        }
+
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+
+    fn write_svg(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        let tree_width = |t: &FrameTree| (t.layout.width() * DX).max(DX);
+        let width =
+            self.trees.iter().map(tree_width).sum::<f64>() + (self.trees.len() + 1) as f64 * MARGIN;
+        let height = self
+            .trees
+            .iter()
+            .map(|t| t.layout.depth() as f64 * DY)
+            .fold(0.0, f64::max)
+            + TITLE_SPACE
+            + LABEL_SPACE
+            + 2.0 * MARGIN;
+
+        svg::write_header(writer, width, height, "")?;
+        writeln!(
+            writer,
+            " <text x=\"{}\" y=\"{}\">{}</text>",
+            width / 2.0,
+            MARGIN,
+            self.title
+        )?;
+
+        let top = MARGIN + TITLE_SPACE;
+        let mut left = MARGIN;
+        for (i, frame_tree) in self.trees.iter().enumerate() {
+            let tree = frame_tree.layout.tree();
+
+            writeln!(writer, " <g id=\"f{}\">", i + 1)?;
+            svg::write_tree(
+                writer,
+                &frame_tree.layout,
+                (left, top),
+                |u| EdgeStyle {
+                    color: frame_tree.marking[u].color(),
+                    width: 2.0,
+                    dash: frame_tree.is_root[u].then_some("6 4"),
+                },
+                |u| NodeStyle {
+                    radius: NodeStyle::radius(tree.node(u).label.is_some()),
+                    fill: frame_tree.marking[u].color(),
+                    stroke: frame_tree.is_root[u].then_some(("black", 1.5)),
+                    tooltip: None,
+                },
+            )?;
+            writeln!(writer, " </g>")?;
+
+            left += tree_width(frame_tree) + MARGIN;
+        }
+
+        writeln!(writer, "</svg>")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::checks::checker::{
+        CheckerError, check_instance_and_anytime_solution_from,
+        check_instance_and_solution_observed_from,
+    };
+    use crate::io::{limits::ReaderLimits, tests::TempDir};
+
+    const INSTANCE: &[u8] = b"#p 2 4\n((1,2),(3,4));\n(((1,2),3),4);\n";
+    const SOLUTION: &[u8] = b"((1,2),4);\n3;\n";
+
+    fn record(temp: &TempDir, format: FrameFormat) -> IsolationFrameWriter {
+        let mut frames = IsolationFrameWriter::new(temp.path());
+        frames.set_format(format);
+        check_instance_and_solution_observed_from(
+            INSTANCE,
+            SOLUTION,
+            false,
+            false,
+            ReaderLimits::default(),
+            &mut frames,
+        )
+        .unwrap();
+        frames
+    }
+
+    #[test]
+    fn dot_frames() {
+        let temp = TempDir::new("frames_dot");
+        let frames = record(&temp, FrameFormat::Dot);
+
+        // one frame per instance tree and solution tree
+        assert_eq!(frames.num_frames(), 4);
+        let read = |i: usize| std::fs::read_to_string(frames.frame_path(i)).unwrap();
+        let contents: Vec<_> = (1..=4).map(read).collect();
+        assert!(!frames.frame_path(5).exists());
+
+        assert!(contents[0].starts_with("digraph Frame {"));
+        assert!(contents[0].contains(
+            "label=\"Frame 1: isolated solution tree in line 1 from instance tree in line 2\";"
+        ));
+        assert!(contents[3].contains("in line 2 from instance tree in line 3\";"));
+
+        // isolating ((1,2),4) in ((1,2),(3,4)) cuts off leaf 3
+        assert!(contents[0].contains(&format!(
+            "f2l3 [label=\"3\", color=\"{CUT}\", shape=\"triangle\"]"
+        )));
+        assert!(contents[0].contains(&format!(
+            "f1l4 [label=\"4\", color=\"{ISOLATED}\", shape=\"box\"]"
+        )));
+
+        // afterwards, 3 is isolated without cutting anything
+        assert!(contents[1].contains(&format!(
+            "f2l3 [label=\"3\", color=\"{ISOLATED}\", shape=\"triangle\"]"
+        )));
+        assert!(!contents[1].contains(CUT));
+    }
+
+    #[test]
+    fn svg_frames() {
+        let temp = TempDir::new("frames_svg");
+        let frames = record(&temp, FrameFormat::Svg);
+        assert_eq!(frames.num_frames(), 4);
+        assert_eq!(frames.frame_path(2), temp.path().join("frame_0002.svg"));
+
+        for i in 1..=4 {
+            let svg = std::fs::read_to_string(frames.frame_path(i)).unwrap();
+            assert!(svg.starts_with("<svg "));
+            assert!(svg.trim_end().ends_with("</svg>"));
+            // every leaf is drawn exactly once
+            assert_eq!(svg.matches("r=\"5\"").count(), 4);
+        }
+    }
+
+    #[test]
+    fn stale_frames() {
+        let temp = TempDir::new("frames_stale");
+        assert_eq!(record(&temp, FrameFormat::Dot).num_frames(), 4);
+        std::fs::write(temp.path().join("notes.txt"), "keep").unwrap();
+
+        // a shorter run does not leave the frames of the earlier one behind
+        let mut frames = IsolationFrameWriter::new(temp.path());
+        let result = check_instance_and_solution_observed_from(
+            INSTANCE,
+            &b"3;\n((1,4),2);\n"[..],
+            false,
+            false,
+            ReaderLimits::default(),
+            &mut frames,
+        );
+        assert!(result.is_err());
+        assert_eq!(frames.num_frames(), 2);
+        assert!(frames.frame_path(2).is_file());
+        assert!(!frames.frame_path(3).exists());
+        assert!(temp.path().join("notes.txt").is_file());
+
+        // the directory is created on every write, even if it vanished in between
+        std::fs::remove_dir_all(temp.path()).unwrap();
+        assert_eq!(record(&temp, FrameFormat::Dot).num_frames(), 4);
+        assert!(temp.path().join("frame_0004.dot").is_file());
+    }
+
+    #[test]
+    fn anytime_frames() {
+        let temp = TempDir::new("frames_anytime");
+        let mut frames = IsolationFrameWriter::new(temp.path());
+
+        // only the last complete solution, consisting of four singletons, is checked
+        let stream = [SOLUTION, b"1;\n2;\n3;\n4;\n"].concat();
+        let (_, solution, _, summary) = check_instance_and_anytime_solution_from(
+            INSTANCE,
+            &stream[..],
+            None,
+            false,
+            false,
+            ReaderLimits::default(),
+            &mut frames,
+        )
+        .unwrap();
+        assert_eq!(summary.num_solutions, 2);
+        assert_eq!(solution.num_trees(), 4);
+        assert_eq!(frames.num_frames(), 8);
+    }
+
+    #[test]
+    fn observer_errors() {
+        // frames cannot be written below a regular file
+        let temp = TempDir::new("frames_file");
+        let file = temp.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let mut frames = IsolationFrameWriter::new(file.join("frames"));
+        let result = check_instance_and_solution_observed_from(
+            INSTANCE,
+            SOLUTION,
+            false,
+            false,
+            ReaderLimits::default(),
+            &mut frames,
+        );
+
+        assert!(matches!(result, Err(CheckerError::Io(_))));
+    }
+
+    #[test]
+    fn mismatch_frame() {
+        let temp = TempDir::new("frames_mismatch");
+        let mut frames = IsolationFrameWriter::new(temp.path());
+
+        // 3 is isolated from ((1,2),(3,4)), but ((1,4),2) disagrees with the remaining tree
+        let result = check_instance_and_solution_observed_from(
+            INSTANCE,
+            &b"3;\n((1,4),2);\n"[..],
+            false,
+            false,
+            ReaderLimits::default(),
+            &mut frames,
+        );
+        assert!(matches!(result, Err(CheckerError::Mismatch { .. })));
+
+        assert_eq!(frames.num_frames(), 2);
+        let last = std::fs::read_to_string(frames.frame_path(2)).unwrap();
+
+        assert!(last.contains(
+            "label=\"Frame 2: solution tree in line 2 cannot be isolated from instance tree in line 2\";"
+        ));
+        assert!(!last.contains(ISOLATED) && !last.contains(CUT));
+        // the forest before the failed attempt, with 3 already isolated
+        assert!(last.contains(&format!(
+            "f1l3 [label=\"3\", color=\"{NEUTRAL}\", shape=\"triangle\"]"
+        )));
+    }
+
+    #[test]
+    fn mismatch_before_first_isolation() {
+        let temp = TempDir::new("frames_first");
+        let mut frames = IsolationFrameWriter::new(temp.path());
+
+        let result = check_instance_and_solution_observed_from(
+            INSTANCE,
+            &b"((1,3),(2,4));\n"[..],
+            false,
+            false,
+            ReaderLimits::default(),
+            &mut frames,
+        );
+        assert!(matches!(result, Err(CheckerError::Mismatch { .. })));
+
+        // the unmodified instance tree
+        assert_eq!(frames.num_frames(), 1);
+        let dot = std::fs::read_to_string(frames.frame_path(1)).unwrap();
+        assert!(dot.contains("cannot be isolated from instance tree in line 2"));
+    }
+}
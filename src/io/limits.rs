@@ -0,0 +1,413 @@
+use std::io::{BufRead, Read};
+
+use thiserror::Error;
+
+/// Upper bounds on the size of untrusted inputs. All limits are checked while reading, i.e.
+/// before memory proportional to the declared (or actual) size is allocated, and before a
+/// line is handed to the Newick parser.
+///
+/// The default values are generous enough for all instances of the challenge; use
+/// [`ReaderLimits::unlimited`] to disable all checks for trusted inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReaderLimits {
+    /// Maximum number of leaves declared in the header
+    pub max_leaves: u32,
+
+    /// Maximum number of trees declared in the header or contained in the input
+    pub max_trees: u32,
+
+    /// Maximum length of any line in bytes (excluding the line break)
+    pub max_line_length: usize,
+
+    /// Maximum length of a stride line (`#s ...`) in bytes
+    pub max_stride_line_length: usize,
+
+    /// Maximum number of bytes read from the input
+    pub max_total_bytes: u64,
+
+    /// Maximum number of nested parentheses in a tree line. Parsing and checking do not
+    /// recurse, so deep trees are only limited by the line length; this limit is disabled
+    /// by default.
+    pub max_nesting_depth: usize,
+}
+
+impl Default for ReaderLimits {
+    fn default() -> Self {
+        Self {
+            max_leaves: 1 << 20,
+            max_trees: 1 << 10,
+            max_line_length: 1 << 26,
+            max_stride_line_length: 1 << 20,
+            max_total_bytes: 1 << 32,
+            max_nesting_depth: usize::MAX,
+        }
+    }
+}
+
+impl ReaderLimits {
+    pub fn unlimited() -> Self {
+        Self {
+            max_leaves: u32::MAX,
+            max_trees: u32::MAX,
+            max_line_length: usize::MAX,
+            max_stride_line_length: usize::MAX,
+            max_total_bytes: u64::MAX,
+            max_nesting_depth: usize::MAX,
+        }
+    }
+
+    pub(crate) fn check_header(
+        &self,
+        num_trees: usize,
+        num_leaves: usize,
+    ) -> Result<(), LimitExceeded> {
+        if num_leaves > self.max_leaves as usize {
+            return Err(LimitExceeded::Leaves {
+                found: num_leaves,
+                limit: self.max_leaves,
+            });
+        }
+
+        self.check_num_trees(num_trees)
+    }
+
+    pub(crate) fn check_num_trees(&self, num_trees: usize) -> Result<(), LimitExceeded> {
+        if num_trees > self.max_trees as usize {
+            return Err(LimitExceeded::Trees {
+                found: num_trees,
+                limit: self.max_trees,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Violations of [`ReaderLimits`]
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum LimitExceeded {
+    #[error("Input declares {found} leaves, but at most {limit} are allowed")]
+    Leaves { found: usize, limit: u32 },
+
+    #[error("Input declares or contains {found} trees, but at most {limit} are allowed")]
+    Trees { found: usize, limit: u32 },
+
+    #[error("Line {} exceeds the maximum length of {limit} bytes", lineno + 1)]
+    LineLength { lineno: usize, limit: usize },
+
+    #[error("Stride line {} exceeds the maximum length of {limit} bytes", lineno + 1)]
+    StrideLineLength { lineno: usize, limit: usize },
+
+    #[error("Input exceeds the maximum size of {limit} bytes")]
+    TotalBytes { limit: u64 },
+
+    #[error("Tree in line {} exceeds the maximum nesting depth of {limit}", lineno + 1)]
+    NestingDepth { lineno: usize, limit: usize },
+}
+
+impl LimitExceeded {
+    /// Recovers a violation reported by [`LimitedReader`] through an IO error.
+    pub fn from_io_error(err: &std::io::Error) -> Option<Self> {
+        err.get_ref()?.downcast_ref::<LimitExceeded>().cloned()
+    }
+}
+
+impl From<LimitExceeded> for std::io::Error {
+    fn from(value: LimitExceeded) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
+
+/// Malformed lines that the underlying parser cannot handle gracefully; they are rejected by
+/// [`LimitedReader`] independently of the [`ReaderLimits`].
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum MalformedInput {
+    #[error("Line {} is a stride or parameter line without key", lineno + 1)]
+    MissingKey { lineno: usize },
+}
+
+impl MalformedInput {
+    /// Recovers a malformed line reported by [`LimitedReader`] through an IO error.
+    pub fn from_io_error(err: &std::io::Error) -> Option<Self> {
+        err.get_ref()?.downcast_ref::<MalformedInput>().cloned()
+    }
+}
+
+impl From<MalformedInput> for std::io::Error {
+    fn from(value: MalformedInput) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, value)
+    }
+}
+
+/// Adapter that enforces the line-based [`ReaderLimits`] on an underlying reader. Input is
+/// buffered at most one line at a time; a line exceeding the limit is rejected without
+/// reading it completely. Violations are reported as [`std::io::Error`] that can be
+/// converted back using [`LimitExceeded::from_io_error`] (or [`MalformedInput::from_io_error`]
+/// for malformed lines).
+pub struct LimitedReader<R> {
+    inner: R,
+    limits: ReaderLimits,
+    line: Vec<u8>,
+    pos: usize,
+    lineno: usize,
+    total_bytes: u64,
+}
+
+impl<R: BufRead> LimitedReader<R> {
+    pub fn new(inner: R, limits: ReaderLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            line: Vec::new(),
+            pos: 0,
+            lineno: 0,
+            total_bytes: 0,
+        }
+    }
+
+    /// Reads the next line (including its line break) into the internal buffer.
+    fn read_line(&mut self) -> std::io::Result<()> {
+        self.line.clear();
+        self.pos = 0;
+
+        loop {
+            let buf = self.inner.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+
+            let (take, complete) = match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => (i + 1, true),
+                None => (buf.len(), false),
+            };
+
+            let content_length = self.line.len() + take - complete as usize;
+            if content_length > self.limits.max_line_length {
+                return Err(LimitExceeded::LineLength {
+                    lineno: self.lineno,
+                    limit: self.limits.max_line_length,
+                }
+                .into());
+            }
+
+            self.total_bytes += take as u64;
+            if self.total_bytes > self.limits.max_total_bytes {
+                return Err(LimitExceeded::TotalBytes {
+                    limit: self.limits.max_total_bytes,
+                }
+                .into());
+            }
+
+            self.line.extend_from_slice(&buf[..take]);
+            self.inner.consume(take);
+
+            let is_stride_line = self.line.trim_ascii_start().starts_with(b"#s");
+            if is_stride_line && content_length > self.limits.max_stride_line_length {
+                return Err(LimitExceeded::StrideLineLength {
+                    lineno: self.lineno,
+                    limit: self.limits.max_stride_line_length,
+                }
+                .into());
+            }
+
+            if complete {
+                break;
+            }
+        }
+
+        if !self.line.is_empty() {
+            self.validate_line()?;
+            self.lineno += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Rejects lines that would make the parser panic or that are nested too deeply.
+    /// Classification follows [`pace26io::pace::reader::InstanceReader`]; lines that are not
+    /// valid UTF-8 are left to the parser, which reports them as IO errors.
+    fn validate_line(&self) -> std::io::Result<()> {
+        let Ok(line) = std::str::from_utf8(&self.line) else {
+            return Ok(());
+        };
+        let content = line.trim();
+        let lineno = self.lineno;
+
+        if content.starts_with("#s") || content.starts_with("#x") {
+            // the parser splits the line at byte 3 without further checks
+            if content.len() < 3 || !content.is_char_boundary(3) {
+                return Err(MalformedInput::MissingKey { lineno }.into());
+            }
+        } else if !content.starts_with('#') && content.ends_with(';') {
+            let mut depth = 0usize;
+            for b in content.bytes() {
+                if b == b'(' {
+                    depth += 1;
+                    if depth > self.limits.max_nesting_depth {
+                        return Err(LimitExceeded::NestingDepth {
+                            lineno,
+                            limit: self.limits.max_nesting_depth,
+                        }
+                        .into());
+                    }
+                } else if b == b')' {
+                    depth = depth.saturating_sub(1);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for LimitedReader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.pos >= self.line.len() {
+            self.read_line()?;
+        }
+        Ok(&self.line[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.line.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(data: &[u8], limits: ReaderLimits) -> std::io::Result<Vec<String>> {
+        LimitedReader::new(data, limits).lines().collect()
+    }
+
+    #[test]
+    fn passes_through() {
+        let data = b"#p 2 3\n(1,(2,3));\r\n\n((1,2),3);";
+        assert_eq!(
+            read_all(data, ReaderLimits::default()).unwrap(),
+            vec!["#p 2 3", "(1,(2,3));", "", "((1,2),3);"]
+        );
+
+        let mut buffer = Vec::new();
+        LimitedReader::new(&data[..], ReaderLimits::default())
+            .read_to_end(&mut buffer)
+            .unwrap();
+        assert_eq!(&buffer, data);
+    }
+
+    #[test]
+    fn line_length() {
+        let limits = ReaderLimits {
+            max_line_length: 6,
+            ..Default::default()
+        };
+
+        assert!(read_all(b"123456\n123456", limits).is_ok());
+
+        let err = read_all(b"123456\n1234567\n", limits).unwrap_err();
+        assert_eq!(
+            LimitExceeded::from_io_error(&err),
+            Some(LimitExceeded::LineLength {
+                lineno: 1,
+                limit: 6
+            })
+        );
+    }
+
+    #[test]
+    fn line_length_small_buffer() {
+        // the line exceeds the limit only after several refills of the inner reader
+        let limits = ReaderLimits {
+            max_line_length: 100,
+            ..Default::default()
+        };
+        let data = vec![b'1'; 1000];
+        let inner = std::io::BufReader::with_capacity(8, &data[..]);
+
+        let err = LimitedReader::new(inner, limits)
+            .lines()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_err();
+        assert!(matches!(
+            LimitExceeded::from_io_error(&err),
+            Some(LimitExceeded::LineLength { lineno: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn stride_line_length() {
+        let limits = ReaderLimits {
+            max_stride_line_length: 10,
+            ..Default::default()
+        };
+
+        assert!(read_all(b"# this comment is long\n#s a \"b\"\n", limits).is_ok());
+
+        let err = read_all(b"#s a \"bcdefgh\"\n", limits).unwrap_err();
+        assert!(matches!(
+            LimitExceeded::from_io_error(&err),
+            Some(LimitExceeded::StrideLineLength { lineno: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let limits = ReaderLimits {
+            max_nesting_depth: 2,
+            ..Default::default()
+        };
+
+        assert!(read_all(b"((1,2),(3,4));\n# (((\n", limits).is_ok());
+
+        let err = read_all(b"((1,2),(3,4));\n(((1,2),3),4);\n", limits).unwrap_err();
+        assert_eq!(
+            LimitExceeded::from_io_error(&err),
+            Some(LimitExceeded::NestingDepth {
+                lineno: 1,
+                limit: 2
+            })
+        );
+    }
+
+    #[test]
+    fn missing_key() {
+        let limits = ReaderLimits::default();
+        assert!(read_all(b"#s k 1\n#sk\n#x k\n", limits).is_ok());
+
+        for line in ["#s", " #x \n", "#s\u{e9}1 2"] {
+            let err = read_all(line.as_bytes(), limits).unwrap_err();
+            assert_eq!(
+                MalformedInput::from_io_error(&err),
+                Some(MalformedInput::MissingKey { lineno: 0 })
+            );
+            assert_eq!(LimitExceeded::from_io_error(&err), None);
+        }
+    }
+
+    #[test]
+    fn total_bytes() {
+        let limits = ReaderLimits {
+            max_total_bytes: 10,
+            ..Default::default()
+        };
+
+        assert!(read_all(b"1234\n1234\n", limits).is_ok());
+
+        let err = read_all(b"1234\n1234\n1", limits).unwrap_err();
+        assert_eq!(
+            LimitExceeded::from_io_error(&err),
+            Some(LimitExceeded::TotalBytes { limit: 10 })
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+/// Typed view of the stride lines (`#s key value`) of an instance. Known keys are
+/// deserialized into dedicated fields; all other keys are kept in `extra`.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use pace26checker::io::instance_reader::Instance;
+///
+/// let instance = Instance::read(Path::new("testcases/tiny/tiny01.in"), false).unwrap();
+/// assert_eq!(instance.metadata().name.as_deref(), Some("tiny01"));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstanceMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idigest: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl InstanceMetadata {
+    /// Adds a single stride line. If a known key has a value of the wrong type, an error is
+    /// returned and the metadata remains unchanged. Repeated keys overwrite earlier values.
+    pub fn insert(
+        &mut self,
+        key: &str,
+        value: &serde_json::Value,
+    ) -> Result<(), serde_json::Error> {
+        let line: Self = deserialize_single(key, value)?;
+
+        self.name = line.name.or(self.name.take());
+        self.desc = line.desc.or(self.desc.take());
+        self.idigest = line.idigest.or(self.idigest.take());
+        self.extra.extend(line.extra);
+
+        Ok(())
+    }
+}
+
+/// Typed view of the stride lines (`#s key value`) that solvers print into their output.
+/// Known keys are deserialized into dedicated fields; all other keys are kept in `extra`.
+///
+/// # Example
+/// ```
+/// use pace26checker::io::solution_reader::Solution;
+///
+/// let output = b"#s solver \"greedy\"\n#s score 1\n((1,2),3);\n";
+/// let solution = Solution::read_from(&output[..], 3, false).unwrap();
+/// assert_eq!(solution.metadata().solver.as_deref(), Some("greedy"));
+/// assert_eq!(solution.metadata().score, Some(1));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SolutionMetadata {
+    /// Name of the solver
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solver: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+
+    /// Number of trees claimed by the solver
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<u32>,
+
+    /// Running time in seconds as measured by the solver
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl SolutionMetadata {
+    /// Adds a single stride line; see [`InstanceMetadata::insert`].
+    pub fn insert(
+        &mut self,
+        key: &str,
+        value: &serde_json::Value,
+    ) -> Result<(), serde_json::Error> {
+        let line: Self = deserialize_single(key, value)?;
+
+        self.solver = line.solver.or(self.solver.take());
+        self.version = line.version.or(self.version.take());
+        self.score = line.score.or(self.score);
+        self.time = line.time.or(self.time);
+        self.seed = line.seed.or(self.seed);
+        self.extra.extend(line.extra);
+
+        Ok(())
+    }
+}
+
+/// Deserializes the object `{key: value}`, i.e. runs the type checks of `T` for one key.
+pub(crate) fn deserialize_single<T: DeserializeOwned>(
+    key: &str,
+    value: &serde_json::Value,
+) -> Result<T, serde_json::Error> {
+    let mut object = serde_json::Map::new();
+    object.insert(key.to_string(), value.clone());
+    serde_json::from_value(serde_json::Value::Object(object))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn insert() {
+        let mut metadata = InstanceMetadata::default();
+        metadata.insert("name", &json!("tiny01")).unwrap();
+        metadata.insert("desc", &json!("example")).unwrap();
+        metadata
+            .insert("author", &json!({"first": "Jane"}))
+            .unwrap();
+        metadata.insert("name", &json!("tiny02")).unwrap();
+
+        assert_eq!(metadata.name.as_deref(), Some("tiny02"));
+        assert_eq!(metadata.desc.as_deref(), Some("example"));
+        assert_eq!(metadata.idigest, None);
+        assert_eq!(metadata.extra["author"], json!({"first": "Jane"}));
+
+        assert!(metadata.insert("idigest", &json!(1234)).is_err());
+        assert!(metadata.insert("desc", &json!(null)).is_ok());
+        assert_eq!(metadata.desc.as_deref(), Some("example"));
+        assert_eq!(metadata.idigest, None);
+    }
+
+    #[test]
+    fn solution_insert() {
+        let mut metadata = SolutionMetadata::default();
+        metadata.insert("solver", &json!("greedy")).unwrap();
+        metadata.insert("version", &json!("1.0")).unwrap();
+        metadata.insert("score", &json!(17)).unwrap();
+        metadata.insert("time", &json!(1.5)).unwrap();
+        metadata.insert("seed", &json!(42)).unwrap();
+        metadata.insert("threads", &json!(4)).unwrap();
+
+        assert_eq!(metadata.solver.as_deref(), Some("greedy"));
+        assert_eq!(metadata.version.as_deref(), Some("1.0"));
+        assert_eq!(metadata.score, Some(17));
+        assert_eq!(metadata.time, Some(1.5));
+        assert_eq!(metadata.seed, Some(42));
+        assert_eq!(metadata.extra["threads"], json!(4));
+
+        assert!(metadata.insert("score", &json!(-1)).is_err());
+        assert!(metadata.insert("time", &json!("1s")).is_err());
+        assert!(metadata.insert("seed", &json!(1.5)).is_err());
+        assert_eq!(metadata.score, Some(17));
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let json = json!({"name": "a", "idigest": "00", "seed": 3});
+        let metadata: InstanceMetadata = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(metadata.name.as_deref(), Some("a"));
+        assert_eq!(metadata.extra["seed"], json!(3));
+        assert_eq!(serde_json::to_value(&metadata).unwrap(), json);
+
+        let json = json!({"solver": "a", "score": 3, "time": 0.25});
+        let metadata: SolutionMetadata = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(metadata.score, Some(3));
+        assert_eq!(serde_json::to_value(&metadata).unwrap(), json);
+    }
+}
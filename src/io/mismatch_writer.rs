@@ -0,0 +1,382 @@
+use crate::{
+    checks::mismatch::{Mismatch, Restriction, clusters},
+    io::{instance_reader::Instance, solution_reader::Solution},
+};
+use pace26io::binary_tree::Label;
+use std::{collections::HashSet, io::Write};
+
+use super::{
+    forest_layout::TreeLayout,
+    svg::{self, DX, DY, EdgeStyle, LABEL_SPACE, MARGIN, NodeStyle, TITLE_SPACE},
+};
+
+const MIN_PANEL_WIDTH: f64 = 140.0;
+
+/// Leaves of the component and edges of its spanning subtree
+const HIGHLIGHT: &str = svg::BLUE;
+/// Nodes at which the instance tree and the component disagree
+const CONFLICT: &str = svg::RED;
+const NEUTRAL: &str = svg::GRAY;
+
+/// Role of a node in the picture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marking {
+    Plain,
+    Highlighted,
+    Conflict,
+}
+
+/// Drawing of one of the two trees together with the marking of each node and whether the
+/// edge into each node belongs to the spanning subtree of the component
+struct Panel {
+    title: String,
+    layout: TreeLayout,
+    marking: Vec<Marking>,
+    spanned: Vec<bool>,
+}
+
+impl Panel {
+    fn name(&self, prefix: &str, u: usize) -> String {
+        match self.layout.tree().node(u).label {
+            Some(l) => format!("{prefix}l{}", l.0),
+            None => format!("{prefix}v{u}"),
+        }
+    }
+}
+
+/// Visualizes a [`Mismatch`], i.e. a failed check, as the offending instance tree next to
+/// the component that could not be isolated from it. In the instance tree, the leaves of the
+/// component and the edges spanning them are highlighted; in both trees, the nodes at which
+/// the topologies disagree (or the nodes shared with other components) are marked.
+///
+/// In contrast to [`ForestDotWriter`](super::forest_dot_writer::ForestDotWriter), this
+/// writer requires neither a feasible solution nor the forests of a successful check.
+///
+/// # Example
+/// ```
+/// use std::{path::Path, io::stdout};
+/// use pace26checker::checks::{forest_checker::*, mismatch::Mismatch};
+/// use pace26checker::io::{instance_reader::Instance, solution_reader::Solution, mismatch_writer::*};
+///
+/// let instance = Instance::read(Path::new("testcases/invalid/score1_sa2e2l7j.in"), false).unwrap();
+/// let solution = Solution::read(Path::new("testcases/invalid/score1_sa2e2l7j.out"), 6, false).unwrap();
+///
+/// let error = IsolateTreeChecker::default().check(&instance, &solution).err().unwrap();
+/// let mismatch = Mismatch::from_error(&instance, &solution, &error).unwrap();
+///
+/// let writer = MismatchWriter::new(&instance, &solution, &mismatch);
+/// writer.write_svg(&mut stdout().lock()).unwrap();
+/// ```
+pub struct MismatchWriter {
+    panels: [Panel; 2],
+    conflict_reason: &'static str,
+}
+
+impl MismatchWriter {
+    pub fn new(instance: &Instance, solution: &Solution, mismatch: &Mismatch) -> Self {
+        let leaves: HashSet<Label> = mismatch.leaves().iter().copied().collect();
+
+        let (inst_lineno, tree) = &instance.trees()[mismatch.tree()];
+        let layout = TreeLayout::new(tree);
+        let conflicts: HashSet<_> = mismatch.tree_conflicts().iter().copied().collect();
+        let restriction = Restriction::new(layout.tree(), instance.num_leaves() as usize, |l| {
+            leaves.contains(&l)
+        });
+        let total = leaves.len();
+        let marking = layout
+            .tree()
+            .nodes()
+            .iter()
+            .map(|node| {
+                if conflicts.contains(&node.node_idx) {
+                    Marking::Conflict
+                } else if node.label.is_some_and(|l| leaves.contains(&l)) {
+                    Marking::Highlighted
+                } else {
+                    Marking::Plain
+                }
+            })
+            .collect();
+        let spanned = restriction
+            .count
+            .iter()
+            .map(|&c| 0 < c && c < total)
+            .collect();
+        let tree_panel = Panel {
+            title: format!("Tree {} (line {})", mismatch.tree() + 1, inst_lineno + 1),
+            layout,
+            marking,
+            spanned,
+        };
+
+        let (sol_lineno, component) = &solution.trees()[mismatch.component()];
+        let layout = TreeLayout::new(component);
+        let conflicts: HashSet<&[Label]> = mismatch
+            .component_conflicts()
+            .iter()
+            .map(Vec::as_slice)
+            .collect();
+        let marking = clusters(layout.tree())
+            .iter()
+            .zip(layout.tree().nodes())
+            .map(|(cluster, node)| {
+                if conflicts.contains(cluster.as_slice()) {
+                    Marking::Conflict
+                } else if node.label.is_some() {
+                    Marking::Highlighted
+                } else {
+                    Marking::Plain
+                }
+            })
+            .collect();
+        let spanned = vec![true; layout.tree().len()];
+        let component_panel = Panel {
+            title: format!(
+                "Component {} (line {})",
+                mismatch.component() + 1,
+                sol_lineno + 1
+            ),
+            layout,
+            marking,
+            spanned,
+        };
+
+        let conflict_reason = if mismatch.is_topological() {
+            "topologies disagree"
+        } else {
+            "shared with another component"
+        };
+
+        Self {
+            panels: [tree_panel, component_panel],
+            conflict_reason,
+        }
+    }
+
+    /// Writes both trees side by side as standalone SVG image
+    pub fn write_svg(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        // leave enough room for the titles next to small trees
+        let widths = self
+            .panels
+            .each_ref()
+            .map(|p| (p.layout.width() * DX).max(MIN_PANEL_WIDTH));
+        let width = widths[0] + widths[1] + 4.0 * MARGIN;
+        let height = self
+            .panels
+            .iter()
+            .map(|p| p.layout.depth() as f64 * DY)
+            .fold(0.0, f64::max)
+            + TITLE_SPACE
+            + LABEL_SPACE
+            + 2.0 * MARGIN;
+
+        svg::write_header(
+            writer,
+            width,
+            height,
+            " .title { font-weight: bold; text-anchor: start; }",
+        )?;
+
+        let mut left = MARGIN;
+        for (i, panel) in self.panels.iter().enumerate() {
+            let tree = panel.layout.tree();
+
+            writeln!(writer, " <g id=\"{}\">", ["tree", "component"][i])?;
+            writeln!(
+                writer,
+                "  <text class=\"title\" x=\"{left}\" y=\"{}\">{}</text>",
+                MARGIN, panel.title
+            )?;
+
+            svg::write_tree(
+                writer,
+                &panel.layout,
+                (left, MARGIN + TITLE_SPACE),
+                |u| {
+                    if panel.spanned[u] {
+                        EdgeStyle {
+                            color: HIGHLIGHT,
+                            width: 2.5,
+                            dash: None,
+                        }
+                    } else {
+                        EdgeStyle {
+                            color: NEUTRAL,
+                            width: 1.5,
+                            dash: None,
+                        }
+                    }
+                },
+                |u| {
+                    let radius = NodeStyle::radius(tree.node(u).label.is_some());
+                    match panel.marking[u] {
+                        Marking::Conflict => NodeStyle {
+                            radius: 7.0,
+                            fill: "white",
+                            stroke: Some((CONFLICT, 3.0)),
+                            tooltip: Some(self.conflict_reason),
+                        },
+                        Marking::Highlighted => NodeStyle {
+                            radius,
+                            fill: HIGHLIGHT,
+                            stroke: None,
+                            tooltip: None,
+                        },
+                        Marking::Plain => NodeStyle {
+                            radius,
+                            fill: NEUTRAL,
+                            stroke: None,
+                            tooltip: None,
+                        },
+                    }
+                },
+            )?;
+            writeln!(writer, " </g>")?;
+
+            left += widths[i] + 2.0 * MARGIN;
+        }
+
+        writeln!(writer, "</svg>")?;
+        Ok(())
+    }
+
+    /// Writes both trees side by side in GraphViz Dot format
+    pub fn write_dot(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        writeln!(writer, "digraph Mismatch {{")?;
+        writeln!(writer, " edge [arrowhead=none];")?;
+
+        for (i, panel) in self.panels.iter().enumerate() {
+            let prefix = ["t", "c"][i];
+            let tree = panel.layout.tree();
+
+            writeln!(writer, " subgraph cluster_{prefix} {{")?;
+            writeln!(writer, "  label=\"{}\";", panel.title)?;
+
+            for (u, node) in tree.nodes().iter().enumerate() {
+                let shape = match node.label {
+                    Some(l) => format!("label=\"{}\", shape=box", l.0),
+                    None => "label=\"\", shape=circle, width=0.15".to_string(),
+                };
+                let style = match panel.marking[u] {
+                    Marking::Conflict => {
+                        format!(
+                            ", color=\"{CONFLICT}\", penwidth=3, tooltip=\"{}\"",
+                            self.conflict_reason
+                        )
+                    }
+                    Marking::Highlighted => {
+                        format!(", style=filled, fillcolor=\"{HIGHLIGHT}\", fontcolor=white")
+                    }
+                    Marking::Plain => String::new(),
+                };
+                writeln!(writer, "  {} [{shape}{style}];", panel.name(prefix, u))?;
+            }
+
+            for (u, node) in tree.nodes().iter().enumerate() {
+                let Some(p) = node.parent else {
+                    continue;
+                };
+                let style = if panel.spanned[u] {
+                    format!(" [color=\"{HIGHLIGHT}\", penwidth=2]")
+                } else {
+                    format!(" [color=\"{NEUTRAL}\"]")
+                };
+                writeln!(
+                    writer,
+                    "  {} -> {}{style};",
+                    panel.name(prefix, p),
+                    panel.name(prefix, u)
+                )?;
+            }
+            writeln!(writer, " }}")?;
+        }
+
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::checks::{
+        checker::check_instance_and_solution,
+        forest_checker::{ForestChecker, IsolateTreeChecker},
+    };
+    use std::path::Path;
+
+    const PATH_INSTANCE: &str = "testcases/invalid/score1_sa2e2l7j.in";
+    const PATH_SOLUTION: &str = "testcases/invalid/score1_sa2e2l7j.out";
+
+    #[test]
+    fn failed_check() {
+        let error = check_instance_and_solution(
+            Path::new(PATH_INSTANCE),
+            Path::new(PATH_SOLUTION),
+            false,
+            false,
+        )
+        .err()
+        .unwrap();
+
+        let instance = Instance::read(Path::new(PATH_INSTANCE), false).unwrap();
+        let solution = Solution::read(Path::new(PATH_SOLUTION), instance.num_leaves(), false)
+            .ok()
+            .unwrap();
+        let mismatch = Mismatch::from_error(&instance, &solution, &error).unwrap();
+        let writer = MismatchWriter::new(&instance, &solution, &mismatch);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write_svg(&mut buffer).unwrap();
+        let svg = String::from_utf8(buffer).unwrap();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("<g id=\"tree\">") && svg.contains("<g id=\"component\">"));
+        assert_eq!(
+            svg.matches(&format!("stroke=\"{CONFLICT}\"")).count(),
+            mismatch.tree_conflicts().len() + mismatch.component_conflicts().len()
+        );
+
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write_dot(&mut buffer).unwrap();
+        let dot = String::from_utf8(buffer).unwrap();
+        assert!(dot.contains("subgraph cluster_t {") && dot.contains("subgraph cluster_c {"));
+        assert!(mismatch.is_topological());
+        assert!(dot.contains("tooltip=\"topologies disagree\""));
+        assert_eq!(
+            dot.matches("style=filled").count(),
+            2 * mismatch.leaves().len()
+        );
+    }
+
+    #[test]
+    fn overlap() {
+        let instance = Instance::read_from("#p 1 4\n((1,2),(3,4));\n".as_bytes(), false).unwrap();
+        let solution = Solution::read_from("(1,3);\n(2,4);\n".as_bytes(), 4, false)
+            .ok()
+            .unwrap();
+        let error = IsolateTreeChecker::default()
+            .check(&instance, &solution)
+            .err()
+            .unwrap();
+        let mismatch = Mismatch::from_error(&instance, &solution, &error).unwrap();
+
+        let writer = MismatchWriter::new(&instance, &solution, &mismatch);
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write_dot(&mut buffer).unwrap();
+        let dot = String::from_utf8(buffer).unwrap();
+
+        // the shared nodes of the instance tree are marked, the component itself is fine
+        assert_eq!(dot.matches("penwidth=3").count(), 3);
+        assert_eq!(
+            dot.matches("tooltip=\"shared with another component\"")
+                .count(),
+            3
+        );
+        assert_eq!(dot.matches("style=filled").count(), 4);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write_svg(&mut buffer).unwrap();
+        let svg = String::from_utf8(buffer).unwrap();
+        assert!(!svg.contains("topologies disagree"));
+    }
+}
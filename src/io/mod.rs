@@ -1,6 +1,20 @@
+pub mod component_dot_writer;
+pub mod forest_coloring;
 pub mod forest_dot_writer;
+mod forest_layout;
+pub mod forest_svg_writer;
+pub mod forest_text_writer;
+pub mod forest_tikz_writer;
+pub mod html_report;
 pub mod instance_reader;
+pub mod isolation_frame_writer;
+pub mod limits;
+pub mod metadata;
+pub mod mismatch_writer;
+pub mod newick_parser;
 pub mod solution_reader;
+mod svg;
+pub mod tanglegram_writer;
 
 #[cfg(test)]
 pub(crate) mod tests {
@@ -18,26 +32,65 @@ pub(crate) mod tests {
 
         let mut result = Vec::new();
 
-        for f in dir.read_dir().unwrap() {
-            if let Ok(file) = f {
-                let input_path = file.path();
-                if input_path.extension() != Some(OsStr::new("in")) {
-                    continue;
-                }
+        for file in dir.read_dir().unwrap().flatten() {
+            let input_path = file.path();
+            if input_path.extension() != Some(OsStr::new("in")) {
+                continue;
+            }
 
-                let output_path = {
-                    let mut output_path = input_path.clone();
-                    output_path.set_extension("out");
+            let output_path = {
+                let mut output_path = input_path.clone();
+                output_path.set_extension("out");
 
-                    output_path.exists().then(|| output_path)
-                };
+                output_path.exists().then_some(output_path)
+            };
 
-                result.push((input_path, output_path));
-            }
+            result.push((input_path, output_path));
         }
 
-        assert!(result.len() > 0);
+        assert!(!result.is_empty());
 
         result
     }
+
+    /// Fresh directory below the system's temporary directory; it is removed on drop, so it
+    /// does not leak if an assertion fails
+    pub(crate) struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub(crate) fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("pace26_{name}_{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Minimal xorshift generator to keep the tests deterministic and dependency-free
+    pub(crate) struct Rng(pub u64);
+
+    impl Rng {
+        pub(crate) fn next(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound.max(1) as u64) as usize
+        }
+
+        pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+            for i in (1..items.len()).rev() {
+                items.swap(i, self.next(i + 1));
+            }
+        }
+    }
 }
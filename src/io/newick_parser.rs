@@ -0,0 +1,217 @@
+use pace26io::binary_tree::*;
+use std::iter::{Enumerate, Peekable};
+use std::str::Chars;
+use thiserror::Error;
+
+/// Errors of [`parse_newick`]. Offsets count characters (not bytes) from the start of the
+/// input, as in [`pace26io::newick::ParserError`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum NewickError {
+    #[error("unexpected end of token stream")]
+    UnexpectedEnd,
+
+    #[error("unexpected character {character} at {offset}")]
+    UnexpectedChar { character: char, offset: usize },
+
+    #[error("label at {offset} exceeds the range of leaf labels")]
+    LabelOverflow { offset: usize },
+
+    #[error("Expected begin of node definition, i.e. label or opening parenthesis, at {offset}")]
+    ExpectedNodeBegin { offset: usize },
+
+    #[error("Expected comma at {offset}")]
+    ExpectedComma { offset: usize },
+
+    #[error("Expected closing parenthesis at {offset}")]
+    ExpectedClosing { offset: usize },
+
+    #[error("Expected end of expression, i.e. ';', at {offset}")]
+    ExpectedEnd { offset: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    ParOpen,
+    ParClose,
+    Comma,
+    Semicolon,
+    Number(u32),
+}
+
+struct Lexer<'a> {
+    input: Peekable<Enumerate<Chars<'a>>>,
+}
+
+impl Lexer<'_> {
+    fn next_token(&mut self) -> Result<(usize, Token), NewickError> {
+        let (offset, c) = self.input.next().ok_or(NewickError::UnexpectedEnd)?;
+
+        let token = match c {
+            '(' => Token::ParOpen,
+            ')' => Token::ParClose,
+            ',' => Token::Comma,
+            ';' => Token::Semicolon,
+            '0'..='9' => {
+                let mut number = c as u32 - '0' as u32;
+                while let Some((_, c)) = self.input.next_if(|(_, c)| c.is_ascii_digit()) {
+                    number = number
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(c as u32 - '0' as u32))
+                        .ok_or(NewickError::LabelOverflow { offset })?;
+                }
+                Token::Number(number)
+            }
+            character => return Err(NewickError::UnexpectedChar { character, offset }),
+        };
+
+        Ok((offset, token))
+    }
+
+    fn expect(
+        &mut self,
+        expected: Token,
+        error: impl FnOnce(usize) -> NewickError,
+    ) -> Result<(), NewickError> {
+        match self.next_token()? {
+            (_, token) if token == expected => Ok(()),
+            (offset, _) => Err(error(offset)),
+        }
+    }
+}
+
+/// Parses a tree in Newick format, e.g. `((1,2),3);`. Equivalent to
+/// [`pace26io::newick::BinaryTreeParser::parse_newick_from_str`], i.e. inner nodes are
+/// numbered in pre-order starting with `root_id`, but the parser uses an explicit stack rather
+/// than recursion. Hence, the nesting depth of the input is only bounded by the available
+/// memory. Labels exceeding `u32` are rejected rather than overflowing.
+pub fn parse_newick<B: TreeBuilder>(
+    builder: &mut B,
+    text: &str,
+    root_id: NodeIdx,
+) -> Result<B::Node, NewickError> {
+    let mut lexer = Lexer {
+        input: text.chars().enumerate().peekable(),
+    };
+
+    // inner nodes on the path from the root to the current node with their left subtree,
+    // if already complete
+    let mut path: Vec<(NodeIdx, Option<B::Node>)> = Vec::new();
+    let mut next_id = root_id;
+
+    loop {
+        let mut subtree = match lexer.next_token()? {
+            (_, Token::ParOpen) => {
+                path.push((next_id, None));
+                next_id = next_id.incremented();
+                continue;
+            }
+            (_, Token::Number(label)) => builder.new_leaf(Label(label)),
+            (offset, _) => return Err(NewickError::ExpectedNodeBegin { offset }),
+        };
+
+        // ascend while the completed subtree is a right child
+        loop {
+            match path.pop() {
+                None => {
+                    lexer.expect(Token::Semicolon, |offset| NewickError::ExpectedEnd {
+                        offset,
+                    })?;
+                    return Ok(builder.make_root(subtree));
+                }
+
+                Some((id, None)) => {
+                    path.push((id, Some(subtree)));
+                    lexer.expect(Token::Comma, |offset| NewickError::ExpectedComma { offset })?;
+                    break;
+                }
+
+                Some((id, Some(left))) => {
+                    lexer.expect(Token::ParClose, |offset| NewickError::ExpectedClosing {
+                        offset,
+                    })?;
+                    subtree = builder.new_inner(id, left, subtree);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::bin_tree_with_parent::{BinTreeWithParentBuilder, NodeCursor};
+    use pace26io::newick::{BinaryTreeParser, NewickWriter};
+
+    fn parse(text: &str) -> Result<NodeCursor, NewickError> {
+        parse_newick(&mut BinTreeWithParentBuilder::default(), text, NodeIdx(10))
+    }
+
+    #[test]
+    fn same_as_recursive_parser() {
+        for text in [
+            "1;",
+            "(1,2);",
+            "((1,2),3);",
+            "(1,(2,3));",
+            "((4,(1,5)),(3,2));",
+        ] {
+            let tree = parse(text).unwrap();
+            let expected = BinTreeWithParentBuilder::default()
+                .parse_newick_from_str(text, NodeIdx(10))
+                .unwrap();
+
+            assert_eq!(tree.to_newick_string(), text);
+            let ids = |t: &NodeCursor| t.top_down().dfs().map(|u| u.node_idx()).collect::<Vec<_>>();
+            assert_eq!(ids(&tree), ids(&expected));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("").err(), Some(NewickError::UnexpectedEnd));
+        assert_eq!(parse("((1,2),3)").err(), Some(NewickError::UnexpectedEnd));
+        assert_eq!(
+            parse("(1,x);").err(),
+            Some(NewickError::UnexpectedChar {
+                character: 'x',
+                offset: 3
+            })
+        );
+        assert_eq!(
+            parse("(1,,2);").err(),
+            Some(NewickError::ExpectedNodeBegin { offset: 3 })
+        );
+        assert_eq!(
+            parse("(1;2);").err(),
+            Some(NewickError::ExpectedComma { offset: 2 })
+        );
+        assert_eq!(
+            parse("(1,2,3);").err(),
+            Some(NewickError::ExpectedClosing { offset: 4 })
+        );
+        assert_eq!(
+            parse("(1,2));").err(),
+            Some(NewickError::ExpectedEnd { offset: 5 })
+        );
+        assert!(parse("(4294967295,1);").is_ok());
+        assert_eq!(
+            parse("(1,4294967296);").err(),
+            Some(NewickError::LabelOverflow { offset: 3 })
+        );
+    }
+
+    #[test]
+    fn deep_caterpillar() {
+        let depth = 100_000;
+        let mut text = "(".repeat(depth);
+        text.push('1');
+        for i in 2..=depth + 1 {
+            text.push_str(&format!(",{i})"));
+        }
+        text.push(';');
+
+        let tree = parse(&text).unwrap();
+        let deepest = tree.top_down().dfs().map(|u| u.depth()).max();
+        assert_eq!(deepest, Some(depth));
+    }
+}
@@ -1,13 +1,17 @@
 use std::{
+    collections::HashSet,
     fs::File,
     io::{BufRead, BufReader},
     path::Path,
 };
 
-use pace26io::{newick::*, pace::reader::*};
+use pace26io::pace::reader::*;
 use tracing::{debug, error, warn};
 
 use crate::checks::{bin_tree_with_parent::BinTreeWithParentBuilder, lint_leaf_labels_coverage::*};
+use crate::io::limits::*;
+use crate::io::metadata::SolutionMetadata;
+use crate::io::newick_parser::*;
 use thiserror::Error;
 
 pub type Tree = crate::checks::bin_tree_with_parent::NodeCursor;
@@ -25,11 +29,15 @@ pub enum SolutionReaderError {
 
     #[error("No tree was found in solution")]
     EmptySolution,
+
+    #[error("Solution rejected: {0}")]
+    LimitExceeded(LimitExceeded),
 }
 
 pub struct Solution {
     pub trees: Vec<(usize, Tree)>,
     pub stride_lines: Vec<(String, serde_json::Value)>,
+    pub metadata: SolutionMetadata,
 }
 
 impl Solution {
@@ -41,13 +49,163 @@ impl Solution {
         &self.trees
     }
 
+    pub fn metadata(&self) -> &SolutionMetadata {
+        &self.metadata
+    }
+
+    /// Reads a solution subject to the default [`ReaderLimits`].
     pub fn read_from(
         reader: impl BufRead,
         num_leaves: u32,
         paranoid: bool,
     ) -> Result<Self, SolutionReaderError> {
-        let mut visitor = SolutionInputVisitor::process(reader, num_leaves);
+        Self::read_from_with_limits(reader, num_leaves, paranoid, ReaderLimits::default())
+    }
+
+    pub fn read_from_with_limits(
+        reader: impl BufRead,
+        num_leaves: u32,
+        paranoid: bool,
+        limits: ReaderLimits,
+    ) -> Result<Self, SolutionReaderError> {
+        let visitor = SolutionInputVisitor::process_with_limits(reader, num_leaves, limits);
+        Self::from_visitor(visitor, paranoid)
+    }
+
+    /// Reads a stream of consecutive solutions as produced by anytime/heuristic solvers and
+    /// returns the last complete one, i.e. the last solution that covers all leaves.
+    ///
+    /// A new solution starts after each line that is equal to `separator` (if provided)
+    /// and with each tree that repeats a leaf of the current solution. If the stream ends
+    /// in an incomplete tree (e.g., since the solver was killed while printing it), this
+    /// tree is discarded. Lines before the first tree (e.g., stride lines) are shared by
+    /// all solutions.
+    pub fn read_last_complete_from(
+        reader: impl BufRead,
+        num_leaves: u32,
+        separator: Option<&str>,
+        paranoid: bool,
+    ) -> Result<(Self, AnytimeSummary), SolutionReaderError> {
+        Self::read_last_complete_from_with_limits(
+            reader,
+            num_leaves,
+            separator,
+            paranoid,
+            ReaderLimits::default(),
+        )
+    }
+
+    pub fn read_last_complete_from_with_limits(
+        reader: impl BufRead,
+        num_leaves: u32,
+        separator: Option<&str>,
+        paranoid: bool,
+        limits: ReaderLimits,
+    ) -> Result<(Self, AnytimeSummary), SolutionReaderError> {
+        let reader_error = |e: std::io::Error| match (
+            LimitExceeded::from_io_error(&e),
+            MalformedInput::from_io_error(&e),
+        ) {
+            (Some(limit), _) => SolutionReaderError::LimitExceeded(limit),
+            (_, Some(malformed)) => SolutionReaderError::VisitorError(malformed.into()),
+            _ => SolutionReaderError::Io(e),
+        };
+        let parser_error = |e: ReaderError| {
+            error!(" {e}");
+            SolutionReaderError::VisitorError(SolutionVisitorError::PaceParserError(e))
+        };
+
+        let mut reader = LimitedReader::new(reader, limits);
+        let mut candidates = AnytimeCandidates::new(num_leaves);
+        let mut seen_labels = HashSet::new();
+        let mut pending_split = false;
+        // last non-empty line and where it starts in the buffer it was appended to
+        let mut last_line: Option<(usize, String, usize)> = None;
+
+        let mut line = String::new();
+        for lineno in 0.. {
+            line.clear();
+            if reader.read_line(&mut line).map_err(reader_error)? == 0 {
+                break;
+            }
+            if !line.ends_with('\n') {
+                line.push('\n');
+            }
+
+            let content = line.trim();
+            if content.is_empty() {
+                // keep the line numbering of the candidate intact
+                candidates.buffer().push(b'\n');
+                continue;
+            }
+
+            if separator == Some(content) {
+                pending_split |= !seen_labels.is_empty();
+                last_line = Some((lineno, content.to_string(), candidates.buffer().len()));
+                candidates.buffer().push(b'\n');
+                continue;
+            }
+
+            if !content.starts_with('#') && content.ends_with(';') {
+                let labels = leaf_labels_in(content);
+                if pending_split || labels.iter().any(|l| seen_labels.contains(l)) {
+                    candidates.finish().map_err(parser_error)?;
+                    seen_labels.clear();
+                    pending_split = false;
+                }
+                candidates.begin_tree(lineno);
+                seen_labels.extend(labels);
+            }
+
+            last_line = Some((lineno, content.to_string(), candidates.buffer().len()));
+            candidates.buffer().extend_from_slice(line.as_bytes());
+        }
 
+        let mut discarded_line = None;
+        if let Some((lineno, content, offset)) = last_line {
+            // only lines terminated by `;` passed the checks of `LimitedReader`, and all
+            // others cannot be parsed anyway
+            let is_incomplete_tree = !content.starts_with('#')
+                && separator != Some(content.as_str())
+                && (!content.ends_with(';')
+                    || parse_newick(
+                        &mut BinTreeWithParentBuilder::default(),
+                        &content,
+                        Default::default(),
+                    )
+                    .is_err());
+
+            if is_incomplete_tree {
+                debug!("Discard incomplete tree in line {}", lineno + 1);
+                discarded_line = Some(lineno);
+                candidates.buffer().truncate(offset);
+            }
+        }
+        candidates.finish().map_err(parser_error)?;
+
+        let summary = AnytimeSummary {
+            num_solutions: candidates.num_solutions,
+            discarded_line,
+        };
+        debug!(
+            "Found {} complete solutions in stream of {} candidates",
+            summary.num_solutions, candidates.num_candidates
+        );
+
+        // report the last complete solution, or, if there is none, the last attempt
+        let visitor = candidates
+            .last_complete
+            .or(candidates.last_attempt)
+            .unwrap_or_default();
+
+        let solution = Self::from_visitor(visitor, paranoid)?;
+        Ok((solution, summary))
+    }
+
+    pub(crate) fn from_visitor(
+        mut visitor: SolutionInputVisitor,
+        paranoid: bool,
+    ) -> Result<Self, SolutionReaderError> {
         for w in &visitor.warnings {
             warn!(" {w}");
         }
@@ -56,6 +214,16 @@ impl Solution {
             error!(" {e}");
         }
 
+        // a limit hit aborts reading, so it is reported even if other errors came first
+        if let Some(index) = visitor
+            .errors
+            .iter()
+            .position(|e| matches!(e, SolutionVisitorError::LimitExceeded(_)))
+            && let SolutionVisitorError::LimitExceeded(e) = visitor.errors.swap_remove(index)
+        {
+            return Err(SolutionReaderError::LimitExceeded(e));
+        }
+
         if !visitor.found_tree_line {
             error!(" {}", SolutionReaderError::EmptySolution);
             return Err(SolutionReaderError::EmptySolution);
@@ -76,6 +244,7 @@ impl Solution {
         Ok(Self {
             trees: std::mem::take(&mut visitor.trees),
             stride_lines: visitor.stride_lines,
+            metadata: visitor.metadata,
         })
     }
 
@@ -93,6 +262,7 @@ pub struct SolutionInputVisitor {
     pub warnings: Vec<SolutionVisitorWarning>,
     pub trees: Vec<(usize, Tree)>,
     pub stride_lines: Vec<(String, serde_json::Value)>,
+    pub metadata: SolutionMetadata,
     pub found_tree_line: bool,
 }
 
@@ -101,7 +271,7 @@ pub enum SolutionVisitorError {
     #[error("Line {} contains invalid Newick string: {newick_error}", lineno + 1)]
     InvalidNewick {
         lineno: usize,
-        newick_error: pace26io::newick::ParserError,
+        newick_error: NewickError,
     },
 
     #[error("Solution has invalid leaves: {0}")]
@@ -114,6 +284,12 @@ pub enum SolutionVisitorError {
         source: serde_json::Error,
     },
 
+    #[error(transparent)]
+    LimitExceeded(#[from] LimitExceeded),
+
+    #[error(transparent)]
+    MalformedInput(#[from] MalformedInput),
+
     #[error(transparent)]
     PaceParserError(#[from] pace26io::pace::reader::ReaderError),
 }
@@ -131,6 +307,13 @@ pub enum SolutionVisitorWarning {
 
     #[error("Line {} contains an instance header, but solutions should not provide one", lineno + 1)]
     FoundHeader { lineno: usize },
+
+    #[error("Stride line {} has value of unexpected type for key `{key}`: {message}", lineno + 1)]
+    InvalidMetadata {
+        lineno: usize,
+        key: String,
+        message: String,
+    },
 }
 
 impl InstanceVisitor for SolutionInputVisitor {
@@ -143,7 +326,7 @@ impl InstanceVisitor for SolutionInputVisitor {
     fn visit_tree(&mut self, lineno: usize, line: &str) -> Action {
         self.found_tree_line = true;
         let mut builder = BinTreeWithParentBuilder::default();
-        match builder.parse_newick_from_str(line, Default::default()) {
+        match parse_newick(&mut builder, line, Default::default()) {
             Ok(tree) => self.trees.push((lineno, tree)),
             Err(e) => {
                 self.errors.push(SolutionVisitorError::InvalidNewick {
@@ -177,6 +360,13 @@ impl InstanceVisitor for SolutionInputVisitor {
     fn visit_stride_line(&mut self, lineno: usize, _line: &str, key: &str, value: &str) -> Action {
         match serde_json::from_str::<serde_json::Value>(value) {
             Ok(json_value) => {
+                if let Err(e) = self.metadata.insert(key, &json_value) {
+                    self.warnings.push(SolutionVisitorWarning::InvalidMetadata {
+                        lineno,
+                        key: key.to_string(),
+                        message: e.to_string(),
+                    });
+                }
                 self.stride_lines.push((key.to_string(), json_value));
             }
             Err(e) => {
@@ -191,27 +381,203 @@ impl InstanceVisitor for SolutionInputVisitor {
 
 impl SolutionInputVisitor {
     pub fn process(reader: impl BufRead, num_leaves: u32) -> SolutionInputVisitor {
+        Self::process_with_limits(reader, num_leaves, ReaderLimits::default())
+    }
+
+    pub fn process_with_limits(
+        reader: impl BufRead,
+        num_leaves: u32,
+        limits: ReaderLimits,
+    ) -> SolutionInputVisitor {
         let mut visitor = SolutionInputVisitor::default();
         let mut solution_reader = InstanceReader::new(&mut visitor);
 
-        if let Err(e) = solution_reader.read(reader) {
-            visitor
-                .errors
-                .push(SolutionVisitorError::PaceParserError(e));
+        if let Err(e) = solution_reader.read(LimitedReader::new(reader, limits)) {
+            let error = match &e {
+                ReaderError::IO(io) => LimitExceeded::from_io_error(io)
+                    .map(SolutionVisitorError::LimitExceeded)
+                    .or_else(|| MalformedInput::from_io_error(io).map(Into::into))
+                    .unwrap_or(SolutionVisitorError::PaceParserError(e)),
+                _ => SolutionVisitorError::PaceParserError(e),
+            };
+            visitor.errors.push(error);
 
             return visitor;
         }
 
+        visitor.lint_leaf_labels(num_leaves);
+        visitor
+    }
+
+    fn lint_leaf_labels(&mut self, num_leaves: u32) {
         if let Err(e) = assert_leaf_labels_are_within_range(
-            visitor.trees.iter().map(|(_, t)| t.top_down()),
+            self.trees.iter().map(|(_, t)| t.top_down()),
             num_leaves,
         ) {
-            visitor
-                .errors
-                .push(SolutionVisitorError::InvalidLeafLabels(e));
+            self.errors.push(SolutionVisitorError::InvalidLeafLabels(e));
         }
+    }
+}
 
-        visitor
+//////////////////////////////////////////////////////////////////
+
+/// Summary of a stream of solutions read by [`Solution::read_last_complete_from`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnytimeSummary {
+    /// Number of complete solutions found in the stream.
+    pub num_solutions: usize,
+
+    /// Line of the trailing incomplete tree, if one was discarded.
+    pub discarded_line: Option<usize>,
+}
+
+/// Consecutive candidate solutions of a stream. Only the lines of the current candidate are
+/// buffered; each candidate is parsed once it ends, and only the last complete one is kept.
+struct AnytimeCandidates {
+    num_leaves: u32,
+
+    /// Lines before the first tree, which are shared by all candidates
+    shared: Vec<u8>,
+
+    /// Lines of the current candidate, which begins at line `start` with a tree
+    current: Option<(usize, Vec<u8>)>,
+
+    last_complete: Option<SolutionInputVisitor>,
+
+    /// Last candidate with a tree line (or the only one), reported if none is complete
+    last_attempt: Option<SolutionInputVisitor>,
+
+    num_solutions: usize,
+    num_candidates: usize,
+}
+
+impl AnytimeCandidates {
+    fn new(num_leaves: u32) -> Self {
+        Self {
+            num_leaves,
+            shared: Vec::new(),
+            current: None,
+            last_complete: None,
+            last_attempt: None,
+            num_solutions: 0,
+            num_candidates: 0,
+        }
+    }
+
+    /// Buffer receiving the next line
+    fn buffer(&mut self) -> &mut Vec<u8> {
+        match &mut self.current {
+            Some((_, lines)) => lines,
+            None => &mut self.shared,
+        }
+    }
+
+    /// Called for each tree line; begins a candidate unless one is in progress
+    fn begin_tree(&mut self, lineno: usize) {
+        self.current.get_or_insert_with(|| (lineno, Vec::new()));
+    }
+
+    /// Parses the current candidate (together with the shared lines) and ends it
+    fn finish(&mut self) -> Result<(), ReaderError> {
+        let mut visitor = SolutionInputVisitor::default();
+        let (start, lines) = self.current.take().unwrap_or_default();
+
+        for (offset, input) in [(0, &self.shared), (start, &lines)] {
+            let mut shifted = ShiftedVisitor {
+                inner: &mut visitor,
+                offset,
+            };
+            InstanceReader::new(&mut shifted)
+                .read(&input[..])
+                .map_err(|e| shift_reader_error(e, offset))?;
+        }
+        visitor.lint_leaf_labels(self.num_leaves);
+
+        self.num_candidates += 1;
+        if visitor.found_tree_line && visitor.errors.is_empty() {
+            self.num_solutions += 1;
+            self.last_complete = Some(visitor);
+        } else if visitor.found_tree_line || self.last_attempt.is_none() {
+            self.last_attempt = Some(visitor);
+        }
+        Ok(())
+    }
+}
+
+/// Extracts all numbers in a Newick string without fully parsing it
+fn leaf_labels_in(newick: &str) -> Vec<u32> {
+    newick
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|x| x.parse().ok())
+        .collect()
+}
+
+/// Forwards the events of a part of a stream that begins at line `offset` to a
+/// [`SolutionInputVisitor`], such that it reports the line numbers of the whole stream
+struct ShiftedVisitor<'a> {
+    inner: &'a mut SolutionInputVisitor,
+    offset: usize,
+}
+
+impl InstanceVisitor for ShiftedVisitor<'_> {
+    fn visit_header(&mut self, lineno: usize, num_trees: usize, num_leafs: usize) -> Action {
+        self.inner
+            .visit_header(lineno + self.offset, num_trees, num_leafs)
+    }
+
+    fn visit_tree(&mut self, lineno: usize, line: &str) -> Action {
+        self.inner.visit_tree(lineno + self.offset, line)
+    }
+
+    fn visit_line_with_extra_whitespace(&mut self, lineno: usize, line: &str) -> Action {
+        self.inner
+            .visit_line_with_extra_whitespace(lineno + self.offset, line)
+    }
+
+    fn visit_unrecognized_hash_line(&mut self, lineno: usize, line: &str) -> Action {
+        self.inner
+            .visit_unrecognized_hash_line(lineno + self.offset, line)
+    }
+
+    fn visit_unrecognized_line(&mut self, lineno: usize, line: &str) -> Action {
+        self.inner
+            .visit_unrecognized_line(lineno + self.offset, line)
+    }
+
+    fn visit_stride_line(&mut self, lineno: usize, line: &str, key: &str, value: &str) -> Action {
+        self.inner
+            .visit_stride_line(lineno + self.offset, line, key, value)
+    }
+}
+
+/// Counterpart of [`ShiftedVisitor`] for the errors of the parser
+fn shift_reader_error(error: ReaderError, offset: usize) -> ReaderError {
+    match error {
+        ReaderError::InvalidHeaderLine { lineno } => ReaderError::InvalidHeaderLine {
+            lineno: lineno + offset,
+        },
+        ReaderError::InvalidStrideLine { lineno } => ReaderError::InvalidStrideLine {
+            lineno: lineno + offset,
+        },
+        ReaderError::InvalidParameterLine { lineno } => ReaderError::InvalidParameterLine {
+            lineno: lineno + offset,
+        },
+        ReaderError::InvalidApproxLine { lineno } => ReaderError::InvalidApproxLine {
+            lineno: lineno + offset,
+        },
+        ReaderError::UnknownParameter { lineno, key } => ReaderError::UnknownParameter {
+            lineno: lineno + offset,
+            key,
+        },
+        ReaderError::InvalidJSON { lineno, err } => ReaderError::InvalidJSON {
+            lineno: lineno + offset,
+            err,
+        },
+        ReaderError::MultipleHeaders { lineno0, lineno1 } => ReaderError::MultipleHeaders {
+            lineno0: lineno0 + offset,
+            lineno1: lineno1 + offset,
+        },
+        ReaderError::IO(e) => ReaderError::IO(e),
     }
 }
 
@@ -243,6 +609,95 @@ mod tests {
         };
     }
 
+    #[test]
+    fn anytime_trailing_line_is_not_parsed_unchecked() {
+        // a trailing line without `;` bypasses the checks of `LimitedReader`
+        let data = format!("(1,2);\n{}", "7".repeat(100));
+        let (solution, summary) =
+            Solution::read_last_complete_from(data.as_bytes(), 2, None, false).unwrap();
+        assert_eq!(solution.num_trees(), 1);
+        assert_eq!(summary.discarded_line, Some(1));
+    }
+
+    assert_raises_warning!(
+        metadata_wrong_type,
+        b"#s solver \"greedy\"\n#s score \"two\"\n(1,2);",
+        2,
+        SolutionVisitorWarning::InvalidMetadata { lineno: 1, .. }
+    );
+
+    #[test]
+    fn limit_total_bytes() {
+        let limits = ReaderLimits {
+            max_total_bytes: 8,
+            ..Default::default()
+        };
+
+        assert!(Solution::read_from_with_limits(&b"(1,2);\n"[..], 2, false, limits).is_ok());
+
+        let err = Solution::read_from_with_limits(&b"(1,2);\n3;\n4;\n"[..], 4, false, limits)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            SolutionReaderError::LimitExceeded(LimitExceeded::TotalBytes { limit: 8 })
+        ));
+
+        let err = Solution::read_last_complete_from_with_limits(
+            &b"(1,2);\n3;\n4;\n"[..],
+            4,
+            None,
+            false,
+            limits,
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(
+            err,
+            SolutionReaderError::LimitExceeded(LimitExceeded::TotalBytes { limit: 8 })
+        ));
+    }
+
+    #[test]
+    fn limit_after_parse_error() {
+        let limits = ReaderLimits {
+            max_total_bytes: 12,
+            ..Default::default()
+        };
+
+        // the invalid leaf label precedes the limit hit
+        let err = Solution::read_from_with_limits(&b"(1,9);\n3;\n4;\n"[..], 4, false, limits)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            SolutionReaderError::LimitExceeded(LimitExceeded::TotalBytes { limit: 12 })
+        ));
+
+        // the limit is hit before the first tree
+        let err =
+            Solution::read_from_with_limits(&b"#s name \"solver\"\n(1,2);\n"[..], 2, false, limits)
+                .err()
+                .unwrap();
+        assert!(matches!(
+            err,
+            SolutionReaderError::LimitExceeded(LimitExceeded::TotalBytes { limit: 12 })
+        ));
+    }
+
+    #[test]
+    fn anytime_malformed_line() {
+        let err = Solution::read_last_complete_from(&b"(1,2);\n#s\n(1,2);\n"[..], 2, None, false)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            SolutionReaderError::VisitorError(SolutionVisitorError::MalformedInput(
+                MalformedInput::MissingKey { lineno: 1 }
+            ))
+        ));
+    }
+
     assert_raises_warning!(
         found_header,
         b"# comment\n#p 1 2\n(1,2);\n(1,2);",
@@ -278,6 +733,23 @@ mod tests {
         SolutionVisitorError::InvalidNewick { lineno: 2, .. }
     );
 
+    assert_raises_error!(
+        label_overflow,
+        b"(1,4294967296);",
+        2,
+        SolutionVisitorError::InvalidNewick {
+            lineno: 0,
+            newick_error: NewickError::LabelOverflow { offset: 3 }
+        }
+    );
+
+    assert_raises_error!(
+        missing_key,
+        b"(1,2);\n#x",
+        2,
+        SolutionVisitorError::MalformedInput(MalformedInput::MissingKey { lineno: 1 })
+    );
+
     assert_raises_error!(
         invalid_stride,
         b"# comment\n#s key: invalid json\n();",
@@ -285,6 +757,114 @@ mod tests {
         SolutionVisitorError::JsonSyntaxError { lineno: 1, .. }
     );
 
+    #[test]
+    fn anytime_last_complete() {
+        // the second solution is complete, the third one is cut off
+        let data = b"#s solver \"test\"\n(5,3);\n6;\n4;\n(1,2);\n# improved\n(3,5);\n(4,6);\n(1,2);\n(1,(2,3";
+        let (solution, summary) =
+            Solution::read_last_complete_from(&data[..], 6, None, true).unwrap();
+
+        assert_eq!(summary.num_solutions, 2);
+        assert_eq!(summary.discarded_line, Some(9));
+        assert_eq!(solution.num_trees(), 3);
+        assert_eq!(solution.trees()[0].0, 6);
+        assert_eq!(solution.stride_lines.len(), 1);
+    }
+
+    #[test]
+    fn anytime_incomplete_coverage() {
+        // the second solution misses leaf 6 and hence the first is reported
+        let data = b"(5,3);\n6;\n4;\n(1,2);\n(3,5);\n(1,2);\n4;\n";
+        let (solution, summary) =
+            Solution::read_last_complete_from(&data[..], 6, None, false).unwrap();
+
+        assert_eq!(summary.num_solutions, 1);
+        assert_eq!(summary.discarded_line, None);
+        assert_eq!(solution.num_trees(), 4);
+    }
+
+    #[test]
+    fn anytime_separator() {
+        let data = b"(1,2);\n---\n2;\n---\n1;\n";
+
+        // without separator, the two partial solutions are merged into one
+        let (solution, summary) =
+            Solution::read_last_complete_from(&data[..], 2, None, false).unwrap();
+        assert_eq!(summary.num_solutions, 2);
+        assert_eq!(solution.num_trees(), 2);
+
+        // with separator, only the first solution is complete
+        let (solution, summary) =
+            Solution::read_last_complete_from(&data[..], 2, Some("---"), false).unwrap();
+        assert_eq!(summary.num_solutions, 1);
+        assert_eq!(solution.num_trees(), 1);
+    }
+
+    #[test]
+    fn anytime_long_stream() {
+        let mut data = String::from("#s solver \"test\"\n");
+        for score in (1..=1000).rev() {
+            data += &format!("#s score {score}\n(1,2);\n3;\n\n");
+        }
+        let (solution, summary) =
+            Solution::read_last_complete_from(data.as_bytes(), 3, None, false).unwrap();
+
+        assert_eq!(summary.num_solutions, 1000);
+        assert_eq!(solution.num_trees(), 2);
+        assert_eq!(solution.stride_lines.len(), 2);
+
+        // line numbers refer to the whole stream, not to the candidate
+        assert_eq!(solution.trees()[0].0, 3998);
+        data += "(1,2);\n();\n# end\n";
+        assert!(matches!(
+            Solution::read_last_complete_from(data.as_bytes(), 3, None, false),
+            Ok((
+                _,
+                AnytimeSummary {
+                    num_solutions: 1000,
+                    discarded_line: None
+                }
+            ))
+        ));
+        data += "(1,2);\n3;\n#x";
+        assert!(matches!(
+            Solution::read_last_complete_from(data.as_bytes(), 3, None, false),
+            Err(SolutionReaderError::VisitorError(
+                SolutionVisitorError::MalformedInput(MalformedInput::MissingKey { lineno: 4006 })
+            ))
+        ));
+    }
+
+    #[test]
+    fn anytime_no_complete_solution() {
+        assert!(matches!(
+            Solution::read_last_complete_from(&b"(1,2);\n(1,"[..], 3, None, false),
+            Err(SolutionReaderError::VisitorError(
+                SolutionVisitorError::InvalidLeafLabels(..)
+            ))
+        ));
+
+        assert!(matches!(
+            Solution::read_last_complete_from(&b"# nothing\n"[..], 3, None, false),
+            Err(SolutionReaderError::EmptySolution)
+        ));
+    }
+
+    #[test]
+    fn anytime_single_solution() {
+        for (i, s) in test_instances("valid") {
+            let instance = Instance::read(&i, false).unwrap();
+            let data = std::fs::read(s.unwrap()).unwrap();
+            let solution = Solution::read_from(&data[..], instance.num_leaves, false).unwrap();
+            let (anytime, summary) =
+                Solution::read_last_complete_from(&data[..], instance.num_leaves, None, false)
+                    .unwrap();
+
+            assert_eq!(summary.num_solutions, 1);
+            assert_eq!(solution.num_trees(), anytime.num_trees());
+        }
+    }
+
     #[test]
     fn valid_solutions() {
         for (i, s) in test_instances("valid") {
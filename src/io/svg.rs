@@ -0,0 +1,124 @@
+use std::io::Write;
+
+use super::forest_layout::TreeLayout;
+
+/// Horizontal distance between neighboring leaves
+pub(crate) const DX: f64 = 30.0;
+/// Vertical distance between levels
+pub(crate) const DY: f64 = 40.0;
+pub(crate) const MARGIN: f64 = 20.0;
+/// Vertical space reserved for a title above the trees
+pub(crate) const TITLE_SPACE: f64 = 24.0;
+/// Vertical space reserved for the leaf labels below each tree
+pub(crate) const LABEL_SPACE: f64 = 24.0;
+
+/// Colors of the Brewer scheme `set19` used to mark nodes
+pub(crate) const BLUE: &str = "#377eb8";
+pub(crate) const RED: &str = "#e41a1c";
+pub(crate) const GRAY: &str = "#999999";
+
+/// Appearance of the edge into a node
+pub(crate) struct EdgeStyle<'a> {
+    pub color: &'a str,
+    pub width: f64,
+    /// Value of `stroke-dasharray`, if any
+    pub dash: Option<&'a str>,
+}
+
+/// Appearance of a node
+pub(crate) struct NodeStyle<'a> {
+    pub radius: f64,
+    pub fill: &'a str,
+    /// Color and width of the outline, if any
+    pub stroke: Option<(&'a str, f64)>,
+    pub tooltip: Option<&'a str>,
+}
+
+impl NodeStyle<'_> {
+    /// Radius of leaves and inner nodes, respectively
+    pub(crate) fn radius(is_leaf: bool) -> f64 {
+        if is_leaf { 5.0 } else { 3.0 }
+    }
+}
+
+/// Writes the opening tag of a standalone SVG image together with the default text style;
+/// `style` is appended to the style sheet
+pub(crate) fn write_header(
+    writer: &mut impl Write,
+    width: f64,
+    height: f64,
+    style: &str,
+) -> Result<(), std::io::Error> {
+    writeln!(
+        writer,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+    )?;
+    writeln!(
+        writer,
+        " <style>text {{ font: 11px sans-serif; text-anchor: middle; }}{style}</style>"
+    )
+}
+
+/// Draws the tree of `layout` with its root at level `top` and its leftmost leaf at `left`.
+/// Edges are drawn first, so that nodes are drawn on top of them; leaves are labeled below.
+pub(crate) fn write_tree<'a>(
+    writer: &mut impl Write,
+    layout: &TreeLayout,
+    (left, top): (f64, f64),
+    edge_style: impl Fn(usize) -> EdgeStyle<'a>,
+    node_style: impl Fn(usize) -> NodeStyle<'a>,
+) -> Result<(), std::io::Error> {
+    let tree = layout.tree();
+    let pos = |u: usize| (left + layout.x(u) * DX, top + layout.level(u) as f64 * DY);
+
+    for (u, node) in tree.nodes().iter().enumerate() {
+        let Some(p) = node.parent else {
+            continue;
+        };
+
+        let (xp, yp) = pos(p);
+        let (x, y) = pos(u);
+        let EdgeStyle { color, width, dash } = edge_style(u);
+        let dash = dash
+            .map(|d| format!(" stroke-dasharray=\"{d}\""))
+            .unwrap_or_default();
+        writeln!(
+            writer,
+            "  <path d=\"M {xp} {yp} H {x} V {y}\" fill=\"none\" stroke=\"{color}\" stroke-width=\"{width}\"{dash}/>"
+        )?;
+    }
+
+    for (u, node) in tree.nodes().iter().enumerate() {
+        let (x, y) = pos(u);
+        let NodeStyle {
+            radius,
+            fill,
+            stroke,
+            tooltip,
+        } = node_style(u);
+        let stroke = stroke
+            .map(|(color, width)| format!(" stroke=\"{color}\" stroke-width=\"{width}\""))
+            .unwrap_or_default();
+        match tooltip {
+            Some(tooltip) => writeln!(
+                writer,
+                "  <circle cx=\"{x}\" cy=\"{y}\" r=\"{radius}\" fill=\"{fill}\"{stroke}><title>{tooltip}</title></circle>"
+            )?,
+            None => writeln!(
+                writer,
+                "  <circle cx=\"{x}\" cy=\"{y}\" r=\"{radius}\" fill=\"{fill}\"{stroke}/>"
+            )?,
+        }
+
+        if let Some(label) = node.label {
+            writeln!(
+                writer,
+                "  <text x=\"{x}\" y=\"{}\">{}</text>",
+                y + LABEL_SPACE - 6.0,
+                label.0
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,570 @@
+use crate::{
+    checks::{bin_forest::*, flat_tree::FlatTree},
+    io::{instance_reader::Instance, solution_reader::Solution},
+};
+use pace26io::binary_tree::Label;
+use std::io::Write;
+use thiserror::Error;
+
+pub use super::forest_coloring::Palette;
+use super::{
+    forest_coloring::*,
+    forest_layout::{TreeLayout, leaf_order},
+};
+
+/// Upper bound on the number of alternating optimization rounds
+const MAX_ROUNDS: usize = 16;
+
+/// Vertical distance between neighboring leaves
+const DY: f64 = 20.0;
+/// Horizontal distance between levels
+const DX: f64 = 24.0;
+const MARGIN: f64 = 20.0;
+/// Horizontal space between the leaves of both trees, including their labels
+const GAP: f64 = 160.0;
+const LABEL_SPACE: f64 = 24.0;
+
+#[derive(Debug, Error)]
+pub enum TanglegramError {
+    #[error("Instance has no tree with index {index} (it has {num_trees} trees)")]
+    NoSuchTree { index: usize, num_trees: usize },
+
+    #[error("A tanglegram requires two distinct trees")]
+    SameTree,
+}
+
+/// One tree of the tanglegram together with the chosen child orders
+struct Side {
+    tree: FlatTree,
+    flipped: Vec<bool>,
+}
+
+impl Side {
+    fn new(tree: FlatTree) -> Self {
+        let flipped = vec![false; tree.len()];
+        Self { tree, flipped }
+    }
+
+    /// Position of each leaf (indexed by label) from top to bottom
+    fn positions(&self, num_leaves: usize) -> Vec<usize> {
+        let mut positions = vec![0; num_leaves + 1];
+        for (pos, u) in leaf_order(&self.tree, &self.flipped)
+            .into_iter()
+            .enumerate()
+        {
+            positions[self.tree.node(u).label.unwrap().0 as usize] = pos;
+        }
+        positions
+    }
+
+    /// Chooses the child order of every node such that the number of crossings with the
+    /// fixed leaf positions `other` of the opposite tree is minimized. Swapping the children
+    /// of a node only changes the relative order of the leaves below the two children, so
+    /// the decisions are independent and the result is optimal for the given `other`.
+    fn optimize(&mut self, other: &[usize]) {
+        // sorted positions (in the opposite tree) of the leaves below each pending subtree
+        let mut below: Vec<Vec<usize>> = vec![Vec::new(); self.tree.len()];
+
+        for u in (0..self.tree.len()).rev() {
+            let node = self.tree.node(u);
+            match node.children {
+                None => below[u] = vec![other[node.label.unwrap().0 as usize]],
+                Some((l, r)) => {
+                    let left = std::mem::take(&mut below[l]);
+                    let right = std::mem::take(&mut below[r]);
+
+                    // pairs (a, b) with a below `l` and b below `r` that cross if `l` is on top
+                    let mut crossing = 0;
+                    let mut j = 0;
+                    for &a in &left {
+                        while j < right.len() && right[j] < a {
+                            j += 1;
+                        }
+                        crossing += j;
+                    }
+
+                    self.flipped[u] = 2 * crossing > left.len() * right.len();
+                    below[u] = merge(left, right);
+                }
+            }
+        }
+    }
+}
+
+fn merge(a: Vec<usize>, b: Vec<usize>) -> Vec<usize> {
+    let mut result = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] <= b[j] {
+            result.push(a[i]);
+            i += 1;
+        } else {
+            result.push(b[j]);
+            j += 1;
+        }
+    }
+    result.extend_from_slice(&a[i..]);
+    result.extend_from_slice(&b[j..]);
+    result
+}
+
+/// Number of pairs of connecting lines that cross, i.e. inversions of `other` listed in the
+/// leaf order of the first tree
+fn count_crossings(first: &Side, other: &[usize]) -> usize {
+    let sequence: Vec<usize> = leaf_order(&first.tree, &first.flipped)
+        .into_iter()
+        .map(|u| other[first.tree.node(u).label.unwrap().0 as usize])
+        .collect();
+
+    // Fenwick tree over positions counting the leaves seen so far
+    let mut fenwick = vec![0usize; sequence.len() + 1];
+    let mut crossings = 0;
+    for (seen, &pos) in sequence.iter().enumerate() {
+        let mut smaller = 0;
+        let mut i = pos;
+        while i > 0 {
+            smaller += fenwick[i];
+            i &= i - 1;
+        }
+        crossings += seen - smaller;
+
+        let mut i = pos + 1;
+        while i < fenwick.len() {
+            fenwick[i] += 1;
+            i += i & i.wrapping_neg();
+        }
+    }
+    crossings
+}
+
+/// Draws two trees of an instance as a tanglegram: the trees face each other with their
+/// leaves in two columns, and lines connect the leaves with equal labels. The child orders
+/// are chosen to reduce the number of crossing lines; starting from the normalized trees, we
+/// alternately fix one tree and optimally reorder the other one until no further
+/// improvement is found. This heuristic is not guaranteed to find the minimum.
+///
+/// If a solution is given (see [`TanglegramWriter::color_leafs`]), connecting lines and tree
+/// edges are colored by component and cut edges are dashed. The output is available as SVG
+/// and in GraphViz Dot format.
+///
+/// # Example
+/// ```
+/// use std::{path::PathBuf, io::stdout};
+/// use pace26checker::{checks::checker::check_instance_and_solution, io::tanglegram_writer::*};
+///
+/// const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
+/// const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
+///
+/// let (instance, solution, forests) = check_instance_and_solution(
+///         &PathBuf::from(PATH_INSTANCE),
+///         &PathBuf::from(PATH_SOLUTION),
+///         false,
+///         true,
+///     )
+///     .unwrap();
+///
+/// let mut writer = TanglegramWriter::new(instance.as_ref().unwrap(), 0, 1).unwrap();
+/// writer.color_leafs(&solution, &forests);
+/// writer.write_svg(&mut stdout().lock()).unwrap();
+/// ```
+pub struct TanglegramWriter<'a> {
+    instance: &'a Instance,
+    trees: [usize; 2],
+    sides: [Side; 2],
+    coloring: ForestColoring,
+    palette: Palette,
+}
+
+impl<'a> TanglegramWriter<'a> {
+    /// Prepares the tanglegram of the trees with (0-based) indices `left` and `right` and
+    /// computes their child orders; the trees of the instance are not modified
+    pub fn new(instance: &'a Instance, left: usize, right: usize) -> Result<Self, TanglegramError> {
+        let num_trees = instance.trees().len();
+        if let Some(&index) = [left, right].iter().find(|&&i| i >= num_trees) {
+            return Err(TanglegramError::NoSuchTree { index, num_trees });
+        }
+        if left == right {
+            return Err(TanglegramError::SameTree);
+        }
+
+        let side = |index: usize| {
+            let ordered = instance.trees()[index].1.clone_and_rebuild();
+            ordered.normalize_child_order();
+            Side::new(FlatTree::new(&ordered))
+        };
+
+        let mut writer = Self {
+            instance,
+            trees: [left, right],
+            sides: [side(left), side(right)],
+            coloring: ForestColoring::new(instance),
+            palette: Palette::default(),
+        };
+        writer.minimize_crossings();
+        Ok(writer)
+    }
+
+    /// Alternately reorders one tree optimally w.r.t. the other one. Each step is optimal
+    /// for the fixed tree, so the number of crossings never increases.
+    fn minimize_crossings(&mut self) {
+        let num_leaves = self.instance.num_leaves() as usize;
+        let mut best = self.crossings();
+
+        for _ in 0..MAX_ROUNDS {
+            let positions = self.sides[0].positions(num_leaves);
+            self.sides[1].optimize(&positions);
+            let positions = self.sides[1].positions(num_leaves);
+            self.sides[0].optimize(&positions);
+
+            let crossings = self.crossings();
+            if crossings >= best {
+                break;
+            }
+            best = crossings;
+        }
+    }
+
+    /// Number of crossing pairs of connecting lines in the chosen layout
+    pub fn crossings(&self) -> usize {
+        let positions = self.sides[1].positions(self.instance.num_leaves() as usize);
+        count_crossings(&self.sides[0], &positions)
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) -> &mut Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn color_leafs(&mut self, solution: &Solution, forests: &[BinForest]) {
+        self.coloring.color_leafs(self.instance, solution, forests);
+    }
+
+    fn layouts(&self) -> [TreeLayout; 2] {
+        self.sides
+            .each_ref()
+            .map(|side| TreeLayout::with_flips(side.tree.clone(), &side.flipped))
+    }
+
+    /// Writes the tanglegram as standalone SVG image
+    pub fn write_svg(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        let layouts = self.layouts();
+
+        let left_leaves = MARGIN + layouts[0].depth() as f64 * DX;
+        let right_leaves = left_leaves + GAP;
+        let width = right_leaves + layouts[1].depth() as f64 * DX + MARGIN;
+        let height = 2.0 * MARGIN + layouts[0].width() * DY;
+
+        // horizontal position of node `u` in the `side`-th tree
+        let x = |side: usize, u: usize| {
+            let offset = (layouts[side].depth() - layouts[side].level(u)) as f64 * DX;
+            if side == 0 {
+                left_leaves - offset
+            } else {
+                right_leaves + offset
+            }
+        };
+        let y = |side: usize, u: usize| MARGIN + layouts[side].x(u) * DY;
+
+        writeln!(
+            writer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+        )?;
+        writeln!(
+            writer,
+            " <style>text {{ font: 11px sans-serif; dominant-baseline: middle; }}</style>"
+        )?;
+
+        // connecting lines below the trees
+        writeln!(writer, " <g id=\"connections\">")?;
+        let mut right_leaf = vec![0; self.instance.num_leaves() as usize + 1];
+        for (v, l) in layouts[1].tree().leaves() {
+            right_leaf[l.0 as usize] = v;
+        }
+        for (u, label) in layouts[0].tree().leaves() {
+            let v = right_leaf[label.0 as usize];
+            let color = self.coloring.color(label.into());
+            writeln!(
+                writer,
+                "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"1.5\" stroke-opacity=\"0.8\"/>",
+                left_leaves + LABEL_SPACE,
+                y(0, u),
+                right_leaves - LABEL_SPACE,
+                y(1, v),
+                self.line_color(color)
+            )?;
+        }
+        writeln!(writer, " </g>")?;
+
+        for (side, layout) in layouts.iter().enumerate() {
+            let tree = layout.tree();
+            let roots = self.coloring.roots(self.trees[side]);
+            let reaches_leaf = layout.reaches_leaf(&roots);
+
+            writeln!(writer, " <g id=\"t{}\">", self.trees[side] + 1)?;
+            for (u, node) in tree.nodes().iter().enumerate() {
+                let Some(p) = node.parent else {
+                    continue;
+                };
+                let style = if roots.contains(&node.node_idx) {
+                    " stroke-dasharray=\"6 4\""
+                } else if !reaches_leaf[u] {
+                    " stroke-dasharray=\"2 3\""
+                } else {
+                    ""
+                };
+                writeln!(
+                    writer,
+                    "  <path d=\"M {} {} V {} H {}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"{style}/>",
+                    x(side, p),
+                    y(side, p),
+                    y(side, u),
+                    x(side, u),
+                    self.palette.css_color(self.coloring.color(node.node_idx))
+                )?;
+            }
+
+            for (u, node) in tree.nodes().iter().enumerate() {
+                let color = self.palette.css_color(self.coloring.color(node.node_idx));
+                let radius = if node.label.is_some() { 4 } else { 2 };
+                writeln!(
+                    writer,
+                    "  <circle cx=\"{}\" cy=\"{}\" r=\"{radius}\" fill=\"{color}\"/>",
+                    x(side, u),
+                    y(side, u)
+                )?;
+                if let Some(label) = node.label {
+                    let (text_x, anchor) = if side == 0 {
+                        (x(side, u) + 8.0, "start")
+                    } else {
+                        (x(side, u) - 8.0, "end")
+                    };
+                    writeln!(
+                        writer,
+                        "  <text x=\"{text_x}\" y=\"{}\" text-anchor=\"{anchor}\">{}</text>",
+                        y(side, u),
+                        label.0
+                    )?;
+                }
+            }
+            writeln!(writer, " </g>")?;
+        }
+
+        writeln!(writer, "</svg>")?;
+        Ok(())
+    }
+
+    /// Connecting lines of leaves without component are drawn in neutral gray
+    fn line_color(&self, color: u32) -> String {
+        if color == MIXED_COLOR {
+            "#999999".to_string()
+        } else {
+            self.palette.css_color(color)
+        }
+    }
+
+    /// Writes the tanglegram in GraphViz Dot format. The leaves of each tree are chained by
+    /// invisible edges to keep the chosen order.
+    pub fn write_dot(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        let layouts = self.layouts();
+        let sides = ["a", "b"];
+        let name = |side: usize, tree: &FlatTree, u: usize| match tree.node(u).label {
+            Some(l) => format!("{}l{}", sides[side], l.0),
+            None => format!("{}v{}", sides[side], tree.node(u).node_idx.0),
+        };
+
+        writeln!(writer, "digraph Tanglegram {{")?;
+        writeln!(writer, " rankdir=LR;")?;
+        writeln!(writer, " edge [arrowhead=none];")?;
+        if self.palette == Palette::Set19 {
+            writeln!(writer, " node [colorscheme=set19];")?;
+            writeln!(writer, " edge [colorscheme=set19];")?;
+        }
+
+        for (side, layout) in layouts.iter().enumerate() {
+            let tree = layout.tree();
+            let roots = self.coloring.roots(self.trees[side]);
+            let reaches_leaf = layout.reaches_leaf(&roots);
+
+            writeln!(writer, " subgraph t{} {{", self.trees[side] + 1)?;
+            for (u, node) in tree.nodes().iter().enumerate() {
+                let color = self.palette.dot_color(self.coloring.color(node.node_idx));
+                match node.label {
+                    Some(l) => writeln!(
+                        writer,
+                        "  {} [label=\"{}\", color={color}, shape=box];",
+                        name(side, tree, u),
+                        l.0
+                    )?,
+                    None => writeln!(
+                        writer,
+                        "  {} [label=\"\", color={color}, shape=point];",
+                        name(side, tree, u)
+                    )?,
+                }
+            }
+
+            for (u, node) in tree.nodes().iter().enumerate() {
+                let Some(p) = node.parent else {
+                    continue;
+                };
+                let style = if roots.contains(&node.node_idx) {
+                    ", style=dashed"
+                } else if !reaches_leaf[u] {
+                    ", style=dotted"
+                } else {
+                    ""
+                };
+                // the second tree grows from right to left
+                let (from, to) = if side == 0 { (p, u) } else { (u, p) };
+                writeln!(
+                    writer,
+                    "  {} -> {} [color={}{style}];",
+                    name(side, tree, from),
+                    name(side, tree, to),
+                    self.palette.dot_color(self.coloring.color(node.node_idx))
+                )?;
+            }
+
+            let order: Vec<_> = leaf_order(tree, &self.sides[side].flipped)
+                .into_iter()
+                .map(|u| name(side, tree, u))
+                .collect();
+            writeln!(writer, "  {{rank=same; {};}}", order.join("; "))?;
+            writeln!(writer, "  {} [style=invis];", order.join(" -> "))?;
+            writeln!(writer, " }}")?;
+        }
+
+        for label in 1..=self.instance.num_leaves() {
+            let color = self.coloring.color(Label(label).into());
+            let color = if color == MIXED_COLOR {
+                "gray60".to_string()
+            } else {
+                self.palette.dot_color(color)
+            };
+            writeln!(
+                writer,
+                " {}l{label} -> {}l{label} [color={color}];",
+                sides[0], sides[1]
+            )?;
+        }
+
+        writeln!(writer, "}}")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use crate::{checks::checker::check_instance_and_solution, io::tests::test_instances};
+
+    use super::*;
+    use std::collections::HashSet;
+
+    const PATH_INSTANCE: &str = "testcases/valid/score10_n07l_lkc.in";
+    const PATH_SOLUTION: &str = "testcases/valid/score10_n07l_lkc.out";
+
+    /// Counts crossings by brute force over all pairs of labels
+    fn brute_force_crossings(writer: &TanglegramWriter) -> usize {
+        let n = writer.instance.num_leaves() as usize;
+        let a = writer.sides[0].positions(n);
+        let b = writer.sides[1].positions(n);
+        let mut crossings = 0;
+        for x in 1..=n {
+            for y in x + 1..=n {
+                if (a[x] < a[y]) != (b[x] < b[y]) {
+                    crossings += 1;
+                }
+            }
+        }
+        crossings
+    }
+
+    #[test]
+    fn errors() {
+        let instance = Instance::read(&PathBuf::from(PATH_INSTANCE), false).unwrap();
+        assert!(matches!(
+            TanglegramWriter::new(&instance, 0, 2).err().unwrap(),
+            TanglegramError::NoSuchTree {
+                index: 2,
+                num_trees: 2
+            }
+        ));
+        assert!(matches!(
+            TanglegramWriter::new(&instance, 1, 1).err().unwrap(),
+            TanglegramError::SameTree
+        ));
+    }
+
+    #[test]
+    fn crossings() {
+        // identical trees can be drawn without crossings
+        let instance = Instance::read_from(
+            "#p 2 6\n(((1,6),(3,4)),(5,2));\n((2,5),((4,3),(6,1)));\n".as_bytes(),
+            false,
+        )
+        .unwrap();
+        let writer = TanglegramWriter::new(&instance, 0, 1).unwrap();
+        assert_eq!(writer.crossings(), 0);
+
+        for (path, _) in test_instances("valid") {
+            let instance = Instance::read(&path, false).unwrap();
+            let mut initial = TanglegramWriter::new(&instance, 0, 1).unwrap();
+            let optimized = initial.crossings();
+            assert_eq!(optimized, brute_force_crossings(&initial));
+
+            for side in &mut initial.sides {
+                side.flipped.fill(false);
+            }
+            assert!(optimized <= initial.crossings(), "{path:?}");
+        }
+    }
+
+    #[test]
+    fn instance_and_solution() {
+        let (instance, solution, forests) = check_instance_and_solution(
+            &PathBuf::from(PATH_INSTANCE),
+            &PathBuf::from(PATH_SOLUTION),
+            false,
+            true,
+        )
+        .unwrap();
+        let instance = instance.as_ref().unwrap();
+
+        let mut writer = TanglegramWriter::new(instance, 0, 1).unwrap();
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write_svg(&mut buffer).unwrap();
+        let uncolored = String::from_utf8(buffer).unwrap();
+        assert_eq!(
+            uncolored.matches("<line ").count(),
+            instance.num_leaves() as usize
+        );
+        assert_eq!(uncolored.matches("stroke=\"#999999\"").count(), 20);
+
+        writer.color_leafs(&solution, &forests);
+        writer.set_palette(Palette::Generated);
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write_svg(&mut buffer).unwrap();
+        let svg = String::from_utf8(buffer).unwrap();
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains("stroke-dasharray=\"6 4\""));
+
+        // connecting lines are colored by component
+        let line_colors: HashSet<_> = svg
+            .lines()
+            .filter(|line| line.contains("<line "))
+            .filter_map(|line| line.split("stroke=\"").nth(1))
+            .collect();
+        assert_eq!(line_colors.len(), solution.trees().len());
+
+        let mut buffer: Vec<u8> = Vec::new();
+        writer.write_dot(&mut buffer).unwrap();
+        let dot = String::from_utf8(buffer).unwrap();
+        assert!(dot.starts_with("digraph Tanglegram {"));
+        assert!(dot.contains("style=dashed"));
+        for label in 1..=instance.num_leaves() {
+            assert!(dot.contains(&format!(" al{label} -> bl{label} [")));
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod checks;
 pub mod io;
+pub mod suite;
 
 #[cfg(feature = "with_digest")]
 pub mod digest;
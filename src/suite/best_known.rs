@@ -0,0 +1,171 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+
+use thiserror::Error;
+use tracing::debug;
+
+#[derive(Debug, Error)]
+pub enum BestKnownError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Line {} of best-known file is not of the form '{{instance}} {{score}}'", lineno + 1)]
+    InvalidLine { lineno: usize },
+
+    #[error("Line {} of best-known file repeats instance {name}", lineno + 1)]
+    DuplicateInstance { lineno: usize, name: String },
+}
+
+/// Best known scores of a benchmark suite, keyed by instance name (i.e. the file stem of
+/// the instance file).
+///
+/// The file format is line-based: each line contains an instance name followed by its
+/// best known score, separated by whitespace. Empty lines and lines starting with `#` are
+/// ignored.
+///
+/// # Example
+/// ```text
+/// # instance      score
+/// tiny01          4
+/// score10_n07l_lkc 10
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BestKnownScores {
+    scores: BTreeMap<String, u32>,
+}
+
+impl BestKnownScores {
+    pub fn get(&self, instance: &str) -> Option<u32> {
+        self.scores.get(instance).copied()
+    }
+
+    /// Stores `score` for `instance` if it improves on the known score (or none is known).
+    /// Returns `true` if the stored value changed.
+    pub fn update(&mut self, instance: &str, score: u32) -> bool {
+        match self.scores.get_mut(instance) {
+            Some(best) if *best <= score => false,
+            Some(best) => {
+                *best = score;
+                true
+            }
+            None => {
+                self.scores.insert(instance.to_string(), score);
+                true
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.scores.iter().map(|(k, v)| (k.as_str(), *v))
+    }
+
+    pub fn read_from(reader: impl BufRead) -> Result<Self, BestKnownError> {
+        let mut scores = BTreeMap::new();
+
+        for (lineno, line) in reader.lines().enumerate() {
+            let line = line?;
+            let content = line.trim();
+            if content.is_empty() || content.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = content.split_whitespace();
+            let (Some(name), Some(score), None) = (parts.next(), parts.next(), parts.next()) else {
+                return Err(BestKnownError::InvalidLine { lineno });
+            };
+
+            let Ok(score) = score.parse::<u32>() else {
+                return Err(BestKnownError::InvalidLine { lineno });
+            };
+
+            if scores.insert(name.to_string(), score).is_some() {
+                return Err(BestKnownError::DuplicateInstance {
+                    lineno,
+                    name: name.to_string(),
+                });
+            }
+        }
+
+        Ok(Self { scores })
+    }
+
+    pub fn read(path: &Path) -> Result<Self, BestKnownError> {
+        debug!("Read best known scores from {path:?}");
+        let file = File::open(path)?;
+        Self::read_from(BufReader::new(file))
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        for (name, score) in self.iter() {
+            writeln!(writer, "{name} {score}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<(String, u32)> for BestKnownScores {
+    fn from_iter<T: IntoIterator<Item = (String, u32)>>(iter: T) -> Self {
+        let mut result = Self::default();
+        for (name, score) in iter {
+            result.update(&name, score);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_and_write() {
+        let data = b"# comment\n\ninst_a 3\n  inst_b\t17 \n";
+        let best = BestKnownScores::read_from(&data[..]).unwrap();
+
+        assert_eq!(best.len(), 2);
+        assert_eq!(best.get("inst_a"), Some(3));
+        assert_eq!(best.get("inst_b"), Some(17));
+        assert_eq!(best.get("inst_c"), None);
+
+        let mut buffer = Vec::new();
+        best.write_to(&mut buffer).unwrap();
+        assert_eq!(BestKnownScores::read_from(&buffer[..]).unwrap(), best);
+    }
+
+    #[test]
+    fn invalid_lines() {
+        for data in [&b"inst_a\n"[..], b"inst_a 3 4\n", b"inst_a -3\n"] {
+            assert!(matches!(
+                BestKnownScores::read_from(data),
+                Err(BestKnownError::InvalidLine { lineno: 0 })
+            ));
+        }
+
+        assert!(matches!(
+            BestKnownScores::read_from(&b"inst_a 3\ninst_a 4\n"[..]),
+            Err(BestKnownError::DuplicateInstance { lineno: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn update() {
+        let mut best = BestKnownScores::default();
+        assert!(best.update("a", 5));
+        assert!(!best.update("a", 5));
+        assert!(!best.update("a", 6));
+        assert!(best.update("a", 4));
+        assert_eq!(best.get("a"), Some(4));
+    }
+}
@@ -0,0 +1,403 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::debug;
+
+use crate::digest::{
+    digest_output::SolutionDigest,
+    version::{VersionedInstanceDigest, VersionedSolutionDigest},
+};
+
+#[derive(Debug, Error)]
+pub enum KnownInstancesError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid known-instances file: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Everything recorded about a single instance
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KnownInstance {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+
+    /// Smallest score of a checked solution
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_score: Option<u32>,
+
+    /// Digest of the solution achieving `best_score`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_solution: Option<VersionedSolutionDigest>,
+
+    /// Score proven to be optimal (e.g. by an exact solver); no solution can be smaller
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proven_optimum: Option<u32>,
+}
+
+/// Reason why a recorded result is not trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum Suspicion {
+    #[error("Score {score} is below the proven optimum {optimum}")]
+    BelowProvenOptimum { score: u32, optimum: u32 },
+
+    #[error("Score {score} differs from the score {encoded} encoded in the solution digest")]
+    DigestScoreMismatch { score: u32, encoded: u32 },
+}
+
+/// Result of [`KnownInstances::record`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordOutcome {
+    /// The solution improves on the best known score (or is the first one recorded)
+    Improvement { previous: Option<u32> },
+
+    /// The solution matches the best known score
+    Matched,
+
+    /// The solution is worse than the best known score
+    Worse { best: u32 },
+
+    /// The result was not stored
+    Suspicious(Suspicion),
+}
+
+/// An improvement recorded since the database was loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Improvement {
+    pub instance: VersionedInstanceDigest,
+    pub previous: Option<u32>,
+    pub score: u32,
+    pub solution: VersionedSolutionDigest,
+}
+
+/// File-backed database of known instances, keyed by their [`VersionedInstanceDigest`]. Since
+/// digests of different versions are incomparable, an instance digested with two versions has
+/// two entries. In contrast to
+/// [`BestKnownScores`](super::best_known::BestKnownScores), entries survive renaming and
+/// moving instance files, and store the digest of the best solution as evidence.
+///
+/// The file is a JSON object mapping hex digests to [`KnownInstance`] objects. Digests are
+/// written with their version prefix (none for legacy digests, see
+/// [`Versioned`](crate::digest::version::Versioned)).
+///
+/// # Example
+/// ```text
+/// {
+///   "0010b172a28d0664d5521e1296fc3586": {
+///     "name": "tiny01",
+///     "path": "testcases/tiny/tiny01.in",
+///     "best_score": 4,
+///     "best_solution": "00043d5d1c6e5c4c0bbd5bc0be4b3c34"
+///   },
+///   "020010e4d1b3c1a3b2f26a8e07a42f0c31": {
+///     "best_score": 4,
+///     "best_solution": "0200049f0e1e2b0c4e8d3a7c5b1d6f2a90"
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KnownInstances {
+    instances: BTreeMap<VersionedInstanceDigest, KnownInstance>,
+
+    #[serde(skip)]
+    improvements: Vec<Improvement>,
+}
+
+impl KnownInstances {
+    pub fn get(&self, digest: &VersionedInstanceDigest) -> Option<&KnownInstance> {
+        self.instances.get(digest)
+    }
+
+    /// Adds an instance or updates its name and path if it is already known
+    pub fn register(
+        &mut self,
+        digest: VersionedInstanceDigest,
+        name: impl Into<String>,
+        path: &Path,
+    ) {
+        let entry = self.instances.entry(digest).or_default();
+        entry.name = Some(name.into());
+        entry.path = Some(path.to_path_buf());
+    }
+
+    /// Stores a proven optimum for an instance (registering it if necessary). The optimum is
+    /// not stored if a recorded solution is smaller, since then one of both is wrong.
+    pub fn set_proven_optimum(
+        &mut self,
+        digest: VersionedInstanceDigest,
+        optimum: u32,
+    ) -> Result<(), Suspicion> {
+        let entry = self.instances.entry(digest).or_default();
+
+        if let Some(score) = entry.best_score
+            && score < optimum
+        {
+            return Err(Suspicion::BelowProvenOptimum { score, optimum });
+        }
+
+        entry.proven_optimum = Some(optimum);
+        Ok(())
+    }
+
+    /// Records a checked (i.e. feasible) solution of the instance with digest `instance`.
+    /// The solution is stored if it improves on the best known score and is not
+    /// [`RecordOutcome::Suspicious`]. Unknown instances are registered without name and path.
+    pub fn record(
+        &mut self,
+        instance: VersionedInstanceDigest,
+        solution: VersionedSolutionDigest,
+        score: u32,
+    ) -> RecordOutcome {
+        let encoded = solution.digest().score();
+        if encoded != score.min(SolutionDigest::MAX_SCORE) {
+            return RecordOutcome::Suspicious(Suspicion::DigestScoreMismatch { score, encoded });
+        }
+
+        let entry = self.instances.entry(instance).or_default();
+
+        if let Some(optimum) = entry.proven_optimum
+            && score < optimum
+        {
+            return RecordOutcome::Suspicious(Suspicion::BelowProvenOptimum { score, optimum });
+        }
+
+        match entry.best_score {
+            Some(best) if best < score => RecordOutcome::Worse { best },
+            Some(best) if best == score => RecordOutcome::Matched,
+            previous => {
+                entry.best_score = Some(score);
+                entry.best_solution = Some(solution);
+                self.improvements.push(Improvement {
+                    instance,
+                    previous,
+                    score,
+                    solution,
+                });
+                RecordOutcome::Improvement { previous }
+            }
+        }
+    }
+
+    /// Improvements recorded since the database was created or loaded, in order
+    pub fn improvements(&self) -> &[Improvement] {
+        &self.improvements
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&VersionedInstanceDigest, &KnownInstance)> {
+        self.instances.iter()
+    }
+
+    pub fn read_from(reader: impl Read) -> Result<Self, KnownInstancesError> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, KnownInstancesError> {
+        debug!("Read known instances from {path:?}");
+        let file = File::open(path)?;
+        Self::read_from(BufReader::new(file))
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<(), KnownInstancesError> {
+        serde_json::to_writer_pretty(&mut *writer, self)?;
+        writeln!(writer)?;
+        Ok(())
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), KnownInstancesError> {
+        debug!("Write known instances to {path:?}");
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest::version::DigestVersion;
+
+    fn instance(last: char) -> VersionedInstanceDigest {
+        VersionedInstanceDigest::try_from(format!("0010b172a28d0664d5521e1296fc358{last}").as_str())
+            .unwrap()
+    }
+
+    fn solution(score: u16, last: char) -> VersionedSolutionDigest {
+        VersionedSolutionDigest::try_from(
+            format!("{score:04x}b172a28d0664d5521e1296fc358{last}").as_str(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn record() {
+        let mut known = KnownInstances::default();
+        known.register(instance('0'), "tiny01", Path::new("tiny01.in"));
+
+        assert_eq!(
+            known.record(instance('0'), solution(5, 'a'), 5),
+            RecordOutcome::Improvement { previous: None }
+        );
+        assert_eq!(
+            known.record(instance('0'), solution(6, 'a'), 6),
+            RecordOutcome::Worse { best: 5 }
+        );
+        assert_eq!(
+            known.record(instance('0'), solution(5, 'b'), 5),
+            RecordOutcome::Matched
+        );
+        assert_eq!(
+            known.record(instance('0'), solution(4, 'a'), 4),
+            RecordOutcome::Improvement { previous: Some(5) }
+        );
+
+        let entry = known.get(&instance('0')).unwrap();
+        assert_eq!(entry.name.as_deref(), Some("tiny01"));
+        assert_eq!(entry.best_score, Some(4));
+        assert_eq!(entry.best_solution, Some(solution(4, 'a')));
+
+        // unknown instances are registered
+        assert_eq!(
+            known.record(instance('1'), solution(7, 'a'), 7),
+            RecordOutcome::Improvement { previous: None }
+        );
+        assert_eq!(known.len(), 2);
+        assert_eq!(known.get(&instance('1')).unwrap().name, None);
+
+        let improvements: Vec<_> = known
+            .improvements()
+            .iter()
+            .map(|i| (i.instance, i.previous, i.score))
+            .collect();
+        assert_eq!(
+            improvements,
+            vec![
+                (instance('0'), None, 5),
+                (instance('0'), Some(5), 4),
+                (instance('1'), None, 7)
+            ]
+        );
+    }
+
+    #[test]
+    fn suspicious() {
+        let mut known = KnownInstances::default();
+        known.set_proven_optimum(instance('0'), 4).unwrap();
+
+        assert_eq!(
+            known.record(instance('0'), solution(3, 'a'), 3),
+            RecordOutcome::Suspicious(Suspicion::BelowProvenOptimum {
+                score: 3,
+                optimum: 4
+            })
+        );
+        assert_eq!(
+            known.record(instance('0'), solution(4, 'a'), 5),
+            RecordOutcome::Suspicious(Suspicion::DigestScoreMismatch {
+                score: 5,
+                encoded: 4
+            })
+        );
+        assert_eq!(known.get(&instance('0')).unwrap().best_score, None);
+        assert!(known.improvements().is_empty());
+
+        assert_eq!(
+            known.record(instance('0'), solution(4, 'a'), 4),
+            RecordOutcome::Improvement { previous: None }
+        );
+    }
+
+    #[test]
+    fn proven_optimum_below_best_score() {
+        let mut known = KnownInstances::default();
+        known.record(instance('0'), solution(4, 'a'), 4);
+
+        assert_eq!(
+            known.set_proven_optimum(instance('0'), 5),
+            Err(Suspicion::BelowProvenOptimum {
+                score: 4,
+                optimum: 5
+            })
+        );
+        assert_eq!(known.get(&instance('0')).unwrap().proven_optimum, None);
+
+        assert_eq!(known.set_proven_optimum(instance('0'), 4), Ok(()));
+        assert_eq!(known.get(&instance('0')).unwrap().proven_optimum, Some(4));
+    }
+
+    #[test]
+    fn versions_are_separate() {
+        let mut known = KnownInstances::default();
+        let legacy = instance('0');
+        let current = VersionedInstanceDigest::new(DigestVersion::LATEST, *legacy.digest());
+        let solution =
+            VersionedSolutionDigest::new(DigestVersion::LATEST, *solution(4, 'a').digest());
+
+        known.record(legacy, solution, 4);
+        assert_eq!(
+            known.record(current, solution, 4),
+            RecordOutcome::Improvement { previous: None }
+        );
+        assert_eq!(known.len(), 2);
+
+        let mut buffer = Vec::new();
+        known.write_to(&mut buffer).unwrap();
+        let read = KnownInstances::read_from(&buffer[..]).unwrap();
+        assert_eq!(read.get(&legacy), known.get(&legacy));
+        assert_eq!(read.get(&current).unwrap().best_solution, Some(solution));
+    }
+
+    #[test]
+    fn read_and_write() {
+        let mut known = KnownInstances::default();
+        known.register(
+            instance('0'),
+            "tiny01",
+            Path::new("testcases/tiny/tiny01.in"),
+        );
+        known.record(instance('0'), solution(4, 'a'), 4);
+        known.set_proven_optimum(instance('1'), 10).unwrap();
+
+        let mut buffer = Vec::new();
+        known.write_to(&mut buffer).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(
+            json["0010b172a28d0664d5521e1296fc3580"]["best_score"],
+            serde_json::json!(4)
+        );
+        assert_eq!(
+            json["0010b172a28d0664d5521e1296fc3581"],
+            serde_json::json!({"proven_optimum": 10})
+        );
+
+        let read = KnownInstances::read_from(&buffer[..]).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read.get(&instance('0')), known.get(&instance('0')));
+        assert!(read.improvements().is_empty());
+
+        assert!(matches!(
+            KnownInstances::read_from(&b"{\"xyz\": {}}"[..]),
+            Err(KnownInstancesError::Json(_))
+        ));
+    }
+}
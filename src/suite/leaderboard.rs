@@ -0,0 +1,109 @@
+use std::io::Write;
+
+use super::runner::{InstanceOutcome, SuiteReport};
+
+/// Writes a plain-text leaderboard of a [`SuiteReport`], followed by a list of all
+/// solutions improving on the best known scores.
+///
+/// # Example
+/// ```
+/// use std::{io::stdout, path::Path};
+/// use pace26checker::suite::{best_known::BestKnownScores, leaderboard::LeaderboardWriter, runner::SuiteRunner};
+///
+/// let mut runner = SuiteRunner::new(Path::new("testcases/valid"), BestKnownScores::default());
+/// runner.add_solver(Path::new("testcases/valid"));
+/// let report = runner.run().unwrap();
+///
+/// LeaderboardWriter::new(&report).write(&mut stdout().lock()).unwrap();
+/// ```
+pub struct LeaderboardWriter<'a> {
+    report: &'a SuiteReport,
+}
+
+impl<'a> LeaderboardWriter<'a> {
+    pub fn new(report: &'a SuiteReport) -> Self {
+        Self { report }
+    }
+
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        let ranking = self.report.ranking();
+        let name_width = ranking
+            .iter()
+            .map(|s| s.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("Solver".len());
+
+        writeln!(
+            writer,
+            "{:>4}  {:<name_width$}  {:>10}  {:>8}  {:>6}  {:>10}  {:>7}  {:>8}",
+            "Rank", "Solver", "Score", "Feasible", "Best", "Infeasible", "Missing", "New best"
+        )?;
+
+        let mut rank = 0;
+        let mut previous = None;
+        for (i, solver) in ranking.iter().enumerate() {
+            // solvers with identical score and number of best solutions share a rank
+            let key = (solver.score(), solver.num_best());
+            if previous != Some(key) {
+                rank = i + 1;
+                previous = Some(key);
+            }
+
+            writeln!(
+                writer,
+                "{:>4}  {:<name_width$}  {:>10.3}  {:>8}  {:>6}  {:>10}  {:>7}  {:>8}",
+                rank,
+                solver.name,
+                solver.score(),
+                solver.num_feasible(),
+                solver.num_best(),
+                solver.num_infeasible(),
+                solver.num_missing(),
+                solver.num_new_best_known(),
+            )?;
+        }
+
+        let mut new_best = self.report.new_best_known().peekable();
+        if new_best.peek().is_some() {
+            writeln!(writer)?;
+            writeln!(writer, "New best known solutions:")?;
+            for (solver, result) in new_best {
+                if let InstanceOutcome::Feasible { score } = result.outcome {
+                    writeln!(writer, "  {} {score} ({solver})", result.instance)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::tests::test_instances_directory;
+    use crate::suite::{best_known::BestKnownScores, runner::SuiteRunner};
+
+    #[test]
+    fn leaderboard() {
+        let valid = test_instances_directory("valid");
+        let invalid = test_instances_directory("invalid");
+
+        let mut runner = SuiteRunner::new(&valid, BestKnownScores::default());
+        runner
+            .add_named_solver("loser", &invalid)
+            .add_named_solver("winner", &valid);
+        let report = runner.run().unwrap();
+
+        let mut buffer = Vec::new();
+        LeaderboardWriter::new(&report).write(&mut buffer).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+
+        assert!(lines[0].starts_with("Rank"));
+        assert!(lines[1].contains("winner"));
+        assert!(lines[2].contains("loser"));
+        assert!(text.contains("New best known solutions:"));
+    }
+}
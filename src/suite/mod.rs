@@ -0,0 +1,6 @@
+pub mod best_known;
+pub mod leaderboard;
+pub mod runner;
+
+#[cfg(feature = "with_digest")]
+pub mod known_instances;
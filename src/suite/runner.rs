@@ -0,0 +1,503 @@
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use crate::checks::checker::check_instance_and_solution_from;
+use crate::io::{metadata::SolutionMetadata, solution_reader::SolutionInputVisitor};
+
+use super::best_known::BestKnownScores;
+
+#[derive(Debug, Error)]
+pub enum SuiteError {
+    #[error("Failed to access {path:?}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("No instances (*.in) found in {0:?}")]
+    NoInstances(PathBuf),
+}
+
+/// An instance of a benchmark suite; its name is the file stem of the instance file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuiteInstance {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstanceOutcome {
+    /// The solution was checked successfully and consists of `score` trees.
+    Feasible { score: u32 },
+
+    /// The solution file exists, but was rejected by the checker.
+    Infeasible { error: String },
+
+    /// The solver directory contains no solution for this instance.
+    Missing,
+}
+
+impl InstanceOutcome {
+    pub fn score(&self) -> Option<u32> {
+        match self {
+            InstanceOutcome::Feasible { score } => Some(*score),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstanceResult {
+    pub instance: String,
+    pub outcome: InstanceOutcome,
+
+    /// Quality relative to the best known score, i.e. `best_known / score` for feasible
+    /// solutions and `0.0` otherwise.
+    pub quality: f64,
+
+    /// Solution is strictly better than the best known score supplied to the runner.
+    pub new_best_known: bool,
+
+    /// Metadata printed by the solver; also available if the solution is infeasible, but
+    /// empty if it is missing.
+    pub metadata: SolutionMetadata,
+}
+
+#[derive(Debug, Clone)]
+pub struct SolverResults {
+    pub name: String,
+    pub directory: PathBuf,
+    pub results: Vec<InstanceResult>,
+}
+
+impl SolverResults {
+    /// Suite score following the PACE heuristic track rules, i.e. the sum of the
+    /// per-instance qualities.
+    pub fn score(&self) -> f64 {
+        self.results.iter().map(|r| r.quality).sum()
+    }
+
+    pub fn num_feasible(&self) -> usize {
+        self.count(|r| matches!(r.outcome, InstanceOutcome::Feasible { .. }))
+    }
+
+    pub fn num_infeasible(&self) -> usize {
+        self.count(|r| matches!(r.outcome, InstanceOutcome::Infeasible { .. }))
+    }
+
+    pub fn num_missing(&self) -> usize {
+        self.count(|r| matches!(r.outcome, InstanceOutcome::Missing))
+    }
+
+    /// Number of instances for which the solver matches the (updated) best known score.
+    pub fn num_best(&self) -> usize {
+        self.count(|r| r.quality >= 1.0)
+    }
+
+    pub fn num_new_best_known(&self) -> usize {
+        self.count(|r| r.new_best_known)
+    }
+
+    fn count(&self, pred: impl Fn(&InstanceResult) -> bool) -> usize {
+        self.results.iter().filter(|r| pred(r)).count()
+    }
+}
+
+pub struct SuiteReport {
+    pub instances: Vec<SuiteInstance>,
+
+    /// Best known scores including all improvements found during the run.
+    pub best_known: BestKnownScores,
+
+    pub solvers: Vec<SolverResults>,
+}
+
+impl SuiteReport {
+    /// Solvers ordered by their suite score; ties are broken by the number of best
+    /// solutions and then by name.
+    pub fn ranking(&self) -> Vec<&SolverResults> {
+        let mut ranking: Vec<_> = self.solvers.iter().collect();
+        ranking.sort_by(|a, b| {
+            b.score()
+                .total_cmp(&a.score())
+                .then_with(|| b.num_best().cmp(&a.num_best()))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        ranking
+    }
+
+    /// All solutions that improve on the best known scores supplied to the runner as
+    /// pairs of solver name and instance result.
+    pub fn new_best_known(&self) -> impl Iterator<Item = (&str, &InstanceResult)> {
+        self.solvers.iter().flat_map(|s| {
+            s.results
+                .iter()
+                .filter(|r| r.new_best_known)
+                .map(|r| (s.name.as_str(), r))
+        })
+    }
+}
+
+/// Checks the solutions of one or more solvers against a directory of instances and
+/// scores them relative to the best known solutions.
+///
+/// Each instance `{name}.in` of the instance directory is matched with the file
+/// `{name}.out` of each solver directory; the solver's name is the name of its directory.
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use pace26checker::suite::{best_known::BestKnownScores, runner::SuiteRunner};
+///
+/// let mut runner = SuiteRunner::new(Path::new("testcases/valid"), BestKnownScores::default());
+/// runner.add_solver(Path::new("testcases/valid"));
+/// let report = runner.run().unwrap();
+///
+/// assert_eq!(report.solvers[0].num_feasible(), report.instances.len());
+/// ```
+pub struct SuiteRunner {
+    instance_dir: PathBuf,
+    solver_dirs: Vec<(String, PathBuf)>,
+    best_known: BestKnownScores,
+    paranoid: bool,
+}
+
+impl SuiteRunner {
+    pub fn new(instance_dir: &Path, best_known: BestKnownScores) -> Self {
+        Self {
+            instance_dir: instance_dir.to_path_buf(),
+            solver_dirs: Vec::new(),
+            best_known,
+            paranoid: false,
+        }
+    }
+
+    /// Adds a solver whose name is derived from the directory name.
+    pub fn add_solver(&mut self, dir: &Path) -> &mut Self {
+        let name = dir
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| dir.to_string_lossy().into_owned());
+        self.add_named_solver(name, dir)
+    }
+
+    pub fn add_named_solver(&mut self, name: impl Into<String>, dir: &Path) -> &mut Self {
+        self.solver_dirs.push((name.into(), dir.to_path_buf()));
+        self
+    }
+
+    pub fn set_paranoid(&mut self, paranoid: bool) -> &mut Self {
+        self.paranoid = paranoid;
+        self
+    }
+
+    pub fn run(&self) -> Result<SuiteReport, SuiteError> {
+        let instances = collect_instances(&self.instance_dir)?;
+
+        // outcomes[solver][instance]
+        let mut outcomes = Vec::with_capacity(self.solver_dirs.len());
+        for (name, dir) in &self.solver_dirs {
+            info!("Check solver {name} in {dir:?}");
+            let mut solver_outcomes = Vec::with_capacity(instances.len());
+            for instance in &instances {
+                solver_outcomes.push(self.check(instance, dir)?);
+            }
+            outcomes.push(solver_outcomes);
+        }
+
+        let mut best_known = self.best_known.clone();
+        for solver_outcomes in &outcomes {
+            for (instance, (outcome, _)) in instances.iter().zip(solver_outcomes) {
+                if let Some(score) = outcome.score() {
+                    best_known.update(&instance.name, score);
+                }
+            }
+        }
+
+        let solvers = self
+            .solver_dirs
+            .iter()
+            .zip(outcomes)
+            .map(|((name, dir), solver_outcomes)| {
+                let results = instances
+                    .iter()
+                    .zip(solver_outcomes)
+                    .map(|(instance, (outcome, metadata))| {
+                        let score = outcome.score();
+                        let best = best_known.get(&instance.name);
+
+                        let quality = match (score, best) {
+                            (Some(score), Some(best)) if score > 0 => best as f64 / score as f64,
+                            _ => 0.0,
+                        };
+
+                        let new_best_known = score.is_some_and(|s| {
+                            self.best_known.get(&instance.name).is_none_or(|b| s < b)
+                        });
+
+                        InstanceResult {
+                            instance: instance.name.clone(),
+                            outcome,
+                            quality,
+                            new_best_known,
+                            metadata,
+                        }
+                    })
+                    .collect();
+
+                SolverResults {
+                    name: name.clone(),
+                    directory: dir.clone(),
+                    results,
+                }
+            })
+            .collect();
+
+        Ok(SuiteReport {
+            instances,
+            best_known,
+            solvers,
+        })
+    }
+
+    fn check(
+        &self,
+        instance: &SuiteInstance,
+        dir: &Path,
+    ) -> Result<(InstanceOutcome, SolutionMetadata), SuiteError> {
+        let solution_path = dir.join(format!("{}.out", instance.name));
+        if !solution_path.is_file() {
+            debug!("No solution for {} in {dir:?}", instance.name);
+            return Ok((InstanceOutcome::Missing, SolutionMetadata::default()));
+        }
+
+        let open = |path: &Path| {
+            File::open(path)
+                .map(BufReader::new)
+                .map_err(|source| SuiteError::Io {
+                    path: path.to_path_buf(),
+                    source,
+                })
+        };
+
+        let instance_reader = open(&instance.path)?;
+        let solution_reader = open(&solution_path)?;
+
+        Ok(
+            match check_instance_and_solution_from(
+                instance_reader,
+                solution_reader,
+                self.paranoid,
+                false,
+            ) {
+                Ok((_, solution, _)) => (
+                    InstanceOutcome::Feasible {
+                        score: solution.num_trees() as u32,
+                    },
+                    solution.metadata,
+                ),
+                Err(e) => {
+                    warn!("Solution {solution_path:?} rejected: {e}");
+
+                    // the stride lines are read independently of the trees, so the solver
+                    // can be identified even if its solution is infeasible
+                    let metadata = SolutionInputVisitor::process(open(&solution_path)?, 0).metadata;
+                    (
+                        InstanceOutcome::Infeasible {
+                            error: e.to_string(),
+                        },
+                        metadata,
+                    )
+                }
+            },
+        )
+    }
+}
+
+/// Returns all instances (`*.in`) of a directory sorted by name.
+pub fn collect_instances(dir: &Path) -> Result<Vec<SuiteInstance>, SuiteError> {
+    let io_error = |source| SuiteError::Io {
+        path: dir.to_path_buf(),
+        source,
+    };
+
+    let mut instances = Vec::new();
+    for entry in dir.read_dir().map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.extension() != Some(OsStr::new("in")) || !path.is_file() {
+            continue;
+        }
+
+        if let Some(name) = path.file_stem() {
+            instances.push(SuiteInstance {
+                name: name.to_string_lossy().into_owned(),
+                path,
+            });
+        }
+    }
+
+    if instances.is_empty() {
+        return Err(SuiteError::NoInstances(dir.to_path_buf()));
+    }
+
+    instances.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(instances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::tests::{TempDir, test_instances_directory};
+
+    /// Test instances encode the score of their solution in the name, e.g. `score10_n07l_lkc`
+    fn score_from_name(name: &str) -> u32 {
+        name.strip_prefix("score")
+            .and_then(|s| s.split('_').next())
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn valid_without_best_known() {
+        let dir = test_instances_directory("valid");
+        let mut runner = SuiteRunner::new(&dir, BestKnownScores::default());
+        runner.add_solver(&dir);
+        let report = runner.run().unwrap();
+
+        let solver = &report.solvers[0];
+        assert_eq!(solver.name, "valid");
+        assert_eq!(solver.num_feasible(), report.instances.len());
+        assert_eq!(solver.num_best(), report.instances.len());
+        assert_eq!(solver.num_new_best_known(), report.instances.len());
+        assert_eq!(solver.score(), report.instances.len() as f64);
+
+        for result in &solver.results {
+            assert_eq!(
+                result.outcome.score(),
+                Some(score_from_name(&result.instance))
+            );
+        }
+    }
+
+    #[test]
+    fn metadata_passes_through() {
+        let temp = TempDir::new("suite_meta");
+        let dir = temp.path();
+        std::fs::write(dir.join("a.in"), "#p 2 3\n((1,2),3);\n(1,(2,3));\n").unwrap();
+        std::fs::write(
+            dir.join("a.out"),
+            "#s solver \"greedy\"\n#s version \"0.1\"\n#s score 2\n(2,3);\n1;\n",
+        )
+        .unwrap();
+
+        let mut runner = SuiteRunner::new(dir, BestKnownScores::default());
+        runner.add_named_solver("greedy", dir);
+        let report = runner.run().unwrap();
+
+        let result = &report.solvers[0].results[0];
+        assert_eq!(result.outcome.score(), Some(2));
+        assert_eq!(result.metadata.solver.as_deref(), Some("greedy"));
+        assert_eq!(result.metadata.version.as_deref(), Some("0.1"));
+        assert_eq!(result.metadata.score, Some(2));
+    }
+
+    #[test]
+    fn metadata_of_infeasible_solution() {
+        let temp = TempDir::new("suite_infeasible");
+        let dir = temp.path();
+        std::fs::write(dir.join("a.in"), "#p 2 3\n((1,2),3);\n(1,(2,3));\n").unwrap();
+        std::fs::write(
+            dir.join("a.out"),
+            "#s solver \"greedy\"\n#s score 1\n((1,2),3);\n",
+        )
+        .unwrap();
+
+        let mut runner = SuiteRunner::new(dir, BestKnownScores::default());
+        runner.add_named_solver("greedy", dir);
+        let report = runner.run().unwrap();
+
+        let result = &report.solvers[0].results[0];
+        assert!(matches!(result.outcome, InstanceOutcome::Infeasible { .. }));
+        assert_eq!(result.metadata.solver.as_deref(), Some("greedy"));
+        assert_eq!(result.metadata.score, Some(1));
+    }
+
+    #[test]
+    fn valid_with_best_known() {
+        let dir = test_instances_directory("valid");
+        let instances = collect_instances(&dir).unwrap();
+
+        // the first instance has a better known solution, the second one is improved upon
+        let improved = &instances[0].name;
+        let worse = &instances[1].name;
+        let best_known: BestKnownScores = instances
+            .iter()
+            .map(|i| {
+                let score = score_from_name(&i.name);
+                let best = if &i.name == improved {
+                    score / 2
+                } else if &i.name == worse {
+                    score + 1
+                } else {
+                    score
+                };
+                (i.name.clone(), best)
+            })
+            .collect();
+
+        let mut runner = SuiteRunner::new(&dir, best_known);
+        runner.add_named_solver("solver", &dir);
+        let report = runner.run().unwrap();
+        let solver = &report.solvers[0];
+
+        assert_eq!(solver.num_best(), instances.len() - 1);
+        assert_eq!(solver.num_new_best_known(), 1);
+        assert!(solver.results[0].quality < 1.0);
+
+        let new_best: Vec<_> = report.new_best_known().collect();
+        assert_eq!(new_best.len(), 1);
+        assert_eq!(new_best[0].0, "solver");
+        assert_eq!(&new_best[0].1.instance, worse);
+        assert_eq!(report.best_known.get(worse), Some(score_from_name(worse)));
+    }
+
+    #[test]
+    fn invalid_and_missing() {
+        let valid = test_instances_directory("valid");
+        let invalid = test_instances_directory("invalid");
+
+        let mut runner = SuiteRunner::new(&invalid, BestKnownScores::default());
+        runner.add_solver(&invalid).add_solver(&valid);
+        let report = runner.run().unwrap();
+
+        let ranking = report.ranking();
+        assert_eq!(ranking.len(), 2);
+
+        // every solution in `invalid` is rejected
+        let invalid_solver = report.solvers.iter().find(|s| s.name == "invalid").unwrap();
+        assert_eq!(invalid_solver.num_infeasible(), report.instances.len());
+        assert_eq!(invalid_solver.score(), 0.0);
+
+        // `valid` only contains solutions for some instances in `invalid`
+        let valid_solver = report.solvers.iter().find(|s| s.name == "valid").unwrap();
+        assert!(valid_solver.num_missing() > 0);
+    }
+
+    #[test]
+    fn no_instances() {
+        let dir = test_instances_directory("valid").join("does_not_exist");
+        assert!(matches!(
+            SuiteRunner::new(&dir, BestKnownScores::default()).run(),
+            Err(SuiteError::Io { .. })
+        ));
+    }
+}